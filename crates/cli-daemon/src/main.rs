@@ -2,7 +2,6 @@ use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
 use tracing::{error, info};
-use tracing_subscriber;
 
 #[derive(Parser, Debug)]
 #[command(name = "av1d")]
@@ -99,26 +98,24 @@ async fn main() -> Result<()> {
 
     // Select encoder based on preference and availability
     info!("Selecting encoder...");
-    let selected_encoder =
-        match av1d_daemon::startup::select_encoder(&available_encoders, config.prefer_encoder) {
-            Ok(encoder) => {
-                info!(
-                    "Selected encoder: {:?} ({})",
-                    encoder.encoder, encoder.codec_name
-                );
-                encoder
-            }
-            Err(e) => {
-                error!("Failed to select encoder: {}", e);
-                return Err(e);
-            }
-        };
+    match av1d_daemon::startup::select_encoder(&available_encoders, config.prefer_encoder) {
+        Ok(encoder) => {
+            info!(
+                "Selected encoder: {:?} ({})",
+                encoder.encoder, encoder.codec_name
+            );
+        }
+        Err(e) => {
+            error!("Failed to select encoder: {}", e);
+            return Err(e);
+        }
+    }
 
     info!("Startup validation complete");
     info!("Starting daemon main loop...");
 
     // Run the daemon main loop
-    if let Err(e) = av1d_daemon::run_daemon_loop(config, selected_encoder).await {
+    if let Err(e) = av1d_daemon::run_daemon_loop(config, available_encoders).await {
         error!("Daemon loop error: {}", e);
         return Err(e);
     }
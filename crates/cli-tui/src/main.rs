@@ -5,6 +5,9 @@ use clap::Parser;
 mod metadata;
 mod models;
 
+use av1d_daemon::commands::{CommandAck, ACK_DIR_NAME};
+use av1d_daemon::interlace::ScanType;
+use av1d_daemon::subtitles::SubtitleAction;
 use humansize::{format_size, DECIMAL};
 use metadata::has_estimation_metadata;
 use models::{load_all_jobs, Job, JobStatus, TranscodeConfig};
@@ -917,10 +920,12 @@ struct App {
 }
 
 impl App {
-    /// Generate temp output path using configured temp_output_dir (same logic as daemon)
-    /// The daemon uses job.id as the filename, so we need to look up the job ID
-    fn get_temp_output_path(&self, job_id: &str) -> PathBuf {
-        self.temp_output_dir.join(format!("{}.mkv", job_id))
+    /// Temp output path of a job: the path the daemon recorded, or
+    /// `{temp_output_dir}/{job.id}.mkv` for jobs written before it did
+    fn get_temp_output_path(&self, job: &Job) -> PathBuf {
+        job.output_path
+            .clone()
+            .unwrap_or_else(|| self.temp_output_dir.join(format!("{}.mkv", job.id)))
     }
 
     fn new(job_state_dir: PathBuf, temp_output_dir: PathBuf) -> Self {
@@ -1249,6 +1254,45 @@ impl App {
         }
     }
 
+    /// Show the newest command acknowledgement written by the daemon and consume all acks
+    fn poll_command_acks(&mut self) {
+        let ack_dir = self.command_dir.join(ACK_DIR_NAME);
+        let Ok(entries) = std::fs::read_dir(&ack_dir) else {
+            return;
+        };
+
+        let mut latest: Option<CommandAck> = None;
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+            let is_ack = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| !n.starts_with('.') && n.ends_with(".json"))
+                .unwrap_or(false);
+            if !is_ack {
+                continue;
+            }
+
+            if let Some(ack) = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<CommandAck>(&s).ok())
+            {
+                if latest
+                    .as_ref()
+                    .is_none_or(|l| ack.processed_at > l.processed_at)
+                {
+                    latest = Some(ack);
+                }
+            }
+            let _ = std::fs::remove_file(&path);
+        }
+
+        if let Some(ack) = latest {
+            let icon = if ack.success { "✅" } else { "❌" };
+            self.last_message = Some(format!("{} Daemon: {}", icon, ack.message));
+            self.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+        }
+    }
+
     /// Clear message if timeout expired
     fn update_message(&mut self) {
         if let Some(timeout) = self.message_timeout {
@@ -1272,7 +1316,7 @@ impl App {
         }
 
         let now = Utc::now();
-        let temp_output = self.get_temp_output_path(&job.id);
+        let temp_output = self.get_temp_output_path(job);
         let orig_backup = job.source_path.with_extension("orig.mkv");
 
         // Get original size
@@ -1431,7 +1475,7 @@ impl App {
                 // If daemon provided live progress, prefer it
                 if job.progress.is_some() || job.encoded_bytes.is_some() || job.stage.is_some() {
                     let mut progress = JobProgress::new(
-                        self.get_temp_output_path(&job.id),
+                        self.get_temp_output_path(&job),
                         job.original_bytes.unwrap_or(0),
                     );
                     let bytes = job.encoded_bytes.unwrap_or(0);
@@ -1462,7 +1506,10 @@ impl App {
                     // ETA based on remaining bytes and speed
                     let bps = job.speed_bps.unwrap_or(0.0);
                     progress.bytes_per_second = bps;
-                    if bps > 0.0 && bytes < est_final && progress.progress_percent < 99.0 {
+                    if matches!(job.stage, Some(av1d_daemon::jobs::JobStage::Verifying)) {
+                        // Decode verification reports its own throughput and ETA
+                        progress.estimated_completion = job.eta;
+                    } else if bps > 0.0 && bytes < est_final && progress.progress_percent < 99.0 {
                         let remaining = est_final.saturating_sub(bytes);
                         let seconds = remaining as f64 / bps;
                         progress.estimated_completion =
@@ -1485,7 +1532,7 @@ impl App {
                     continue;
                 }
 
-                let temp_output = self.get_temp_output_path(&job.id);
+                let temp_output = self.get_temp_output_path(&job);
                 let original_size = job.original_bytes.unwrap_or(0);
                 let total_duration = self.get_source_duration_secs(&job);
 
@@ -1722,6 +1769,9 @@ impl App {
             self.statistics_cache = StatisticsCache::calculate(&self.jobs);
         }
 
+        // Show the outcome of commands the daemon has handled
+        self.poll_command_acks();
+
         self.last_refresh = now;
 
         Ok(())
@@ -2609,6 +2659,60 @@ fn render_detail_view(f: &mut Frame, app: &App, area: Rect) {
 
     // Encoding parameters (Task 9.2) with Unicode symbols and improved spacing
     lines.push("⚙  ENCODING PARAMETERS:".to_string());
+    lines.push(format!(
+        "   Encoding Profile: {}",
+        job.encoding_profile.as_deref().unwrap_or("(default)")
+    ));
+    if let Some(interlace) = &job.interlace {
+        let scan = match interlace.scan_type {
+            ScanType::Progressive => "progressive",
+            ScanType::Interlaced => "interlaced, deinterlaced",
+            ScanType::Telecined => "telecined, inverse telecined",
+        };
+        lines.push(format!(
+            "   Scan: {} (field order {})",
+            scan,
+            interlace.field_order.as_deref().unwrap_or("unknown")
+        ));
+    }
+    if let Some(detection) = &job.crop_detection {
+        let crop = match detection.crop {
+            Some(c) => format!("{}x{} at {},{}", c.width, c.height, c.x, c.y),
+            None => "none".to_string(),
+        };
+        lines.push(format!(
+            "   Crop: {} ({} of {} samples agree)",
+            crop, detection.agreeing, detection.samples
+        ));
+    }
+    if let Some(subtitles) = &job.subtitles {
+        let count = |action: fn(&SubtitleAction) -> bool| {
+            subtitles.iter().filter(|t| action(&t.action)).count()
+        };
+        lines.push(format!(
+            "   Subtitles: {} copied, {} converted, {} dropped, {} extracted",
+            count(|a| matches!(a, SubtitleAction::Copy)),
+            count(|a| matches!(a, SubtitleAction::Convert { .. })),
+            count(|a| matches!(a, SubtitleAction::Drop { .. })),
+            subtitles.iter().filter(|t| t.sidecar.is_some()).count()
+        ));
+        for track in subtitles {
+            if let SubtitleAction::Drop { reason } = &track.action {
+                lines.push(format!("      Stream {}: {}", track.index, reason));
+            }
+        }
+    }
+    if let Some(grain) = &job.grain_analysis {
+        let synthesis = match (grain.film_grain, grain.denoise) {
+            (0, _) => "off".to_string(),
+            (strength, true) => format!("{} with denoise", strength),
+            (strength, false) => strength.to_string(),
+        };
+        lines.push(format!(
+            "   Film Grain: {} (noise level {:.2})",
+            synthesis, grain.noise_level
+        ));
+    }
 
     if let Some(quality) = job.av1_quality {
         lines.push(format!("   AV1 Quality (CRF): {}", quality));
@@ -2676,6 +2780,22 @@ fn render_detail_view(f: &mut Frame, app: &App, area: Rect) {
         lines.push("   New Size: (not available)".to_string());
     }
 
+    if let Some(prediction) = &job.savings_prediction {
+        lines.push(format!(
+            "   Predicted Size: {} ({:.1}% of original, {} samples)",
+            format_size(prediction.predicted_bytes, DECIMAL),
+            prediction.predicted_ratio * 100.0,
+            prediction.samples
+        ));
+        if let Some(new_bytes) = job.new_bytes {
+            if prediction.predicted_bytes > 0 {
+                let error_pct =
+                    (new_bytes as f64 / prediction.predicted_bytes as f64 - 1.0) * 100.0;
+                lines.push(format!("   Prediction Error: {:+.1}%", error_pct));
+            }
+        }
+    }
+
     lines.push("".to_string());
     lines.push(
         "╚═══════════════════════════════════════════════════════════════════════════════╝"
@@ -3751,8 +3871,26 @@ mod tests {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         }
     }
 
@@ -3813,8 +3951,26 @@ mod tests {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             })
     }
 
@@ -195,6 +195,7 @@ mod tests {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -204,6 +205,23 @@ mod tests {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         }
     }
 
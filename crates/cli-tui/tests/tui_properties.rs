@@ -42,6 +42,7 @@ fn property_tui_job_loading() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -51,6 +52,23 @@ fn property_tui_job_loading() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
 
             // Save job to disk
@@ -118,6 +136,7 @@ fn property_tui_job_loading_with_invalid_files() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -127,6 +146,23 @@ fn property_tui_job_loading_with_invalid_files() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
 
             let job_file = job_state_dir.join(format!("{}.json", job.id));
@@ -188,6 +224,7 @@ fn property_statistics_calculation() {
                 crf_used: Some(23),
                 preset_used: Some(4),
                 encoder_used: Some("libsvtav1".to_string()),
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -197,6 +234,23 @@ fn property_statistics_calculation() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -223,6 +277,7 @@ fn property_statistics_calculation() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -232,6 +287,23 @@ fn property_statistics_calculation() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -258,6 +330,7 @@ fn property_statistics_calculation() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -267,6 +340,23 @@ fn property_statistics_calculation() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -361,6 +451,7 @@ fn property_job_filtering() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -370,6 +461,23 @@ fn property_job_filtering() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             }
         };
 
@@ -427,25 +535,25 @@ fn property_sort_mode_cycling() {
     // Define the sort mode cycle
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     enum SortMode {
-        ByDate,
-        BySize,
-        ByStatus,
-        BySavings,
+        Date,
+        Size,
+        Status,
+        Savings,
     }
 
     impl SortMode {
         fn cycle(&self) -> Self {
             match self {
-                SortMode::ByDate => SortMode::BySize,
-                SortMode::BySize => SortMode::ByStatus,
-                SortMode::ByStatus => SortMode::BySavings,
-                SortMode::BySavings => SortMode::ByDate,
+                SortMode::Date => SortMode::Size,
+                SortMode::Size => SortMode::Status,
+                SortMode::Status => SortMode::Savings,
+                SortMode::Savings => SortMode::Date,
             }
         }
     }
 
     proptest!(|(cycle_count in 1..=100usize)| {
-        let mut current_mode = SortMode::ByDate;
+        let mut current_mode = SortMode::Date;
 
         // Cycle through modes
         for _ in 0..cycle_count {
@@ -454,10 +562,10 @@ fn property_sort_mode_cycling() {
 
         // Property: After 4 cycles, we should be back to the starting mode
         let expected_mode = match cycle_count % 4 {
-            0 => SortMode::ByDate,
-            1 => SortMode::BySize,
-            2 => SortMode::ByStatus,
-            3 => SortMode::BySavings,
+            0 => SortMode::Date,
+            1 => SortMode::Size,
+            2 => SortMode::Status,
+            3 => SortMode::Savings,
             _ => unreachable!(),
         };
 
@@ -540,7 +648,6 @@ fn property_stage_detection() {
         Transcoding,
         Verifying,
         Replacing,
-        Complete,
     }
 
     proptest!(|(
@@ -562,7 +669,7 @@ fn property_stage_detection() {
 
         // Property: Stage should be one of the valid stages
         prop_assert!(matches!(detected_stage,
-            JobStage::Probing | JobStage::Transcoding | JobStage::Verifying | JobStage::Replacing | JobStage::Complete),
+            JobStage::Probing | JobStage::Transcoding | JobStage::Verifying | JobStage::Replacing),
             "Detected stage should be valid");
 
         // Property: If no temp file, stage should be Probing
@@ -598,7 +705,7 @@ fn property_responsive_column_layout() {
         };
 
         // Property: Column count should match expected based on width
-        prop_assert!(expected_column_count >= 3 && expected_column_count <= 14,
+        prop_assert!((3..=14).contains(&expected_column_count),
             "Column count should be between 3 and 14");
 
         // Property: Larger terminals should have more or equal columns
@@ -654,6 +761,7 @@ fn property_missing_metadata_indicator_accuracy() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -663,6 +771,23 @@ fn property_missing_metadata_indicator_accuracy() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Get missing metadata fields using the utility function
@@ -780,7 +905,7 @@ fn property_metadata_field_display_completeness() {
         bit_depth in prop::sample::select(vec![8u8, 10, 12]),
     )| {
         // Create a job with the specified metadata fields
-        let job = Job {
+        let _job = Job {
             id: "test-job".to_string(),
             source_path: PathBuf::from("/test/video.mkv"),
             output_path: None,
@@ -800,6 +925,7 @@ fn property_metadata_field_display_completeness() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: if has_bit_depth { Some(bit_depth) } else { None },
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: if has_hdr { Some(true) } else { None },
@@ -809,6 +935,23 @@ fn property_metadata_field_display_completeness() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Property 1: Resolution should show dimensions when available, "-" otherwise
@@ -942,6 +1085,7 @@ fn property_codec_name_formatting_consistency() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -951,6 +1095,23 @@ fn property_codec_name_formatting_consistency() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Format codec using the same logic as the job table
@@ -975,7 +1136,7 @@ fn property_codec_name_formatting_consistency() {
             // Property 6: Codec display should be consistent regardless of input case
             let lowercase_codec = codec_name.to_lowercase();
             let uppercase_codec = codec_name.to_uppercase();
-            let mixedcase_codec = codec_name.clone();
+            let mixedcase_codec = codec_name;
 
             // All should produce the same uppercase result
             prop_assert_eq!(&lowercase_codec.to_uppercase(), &uppercase_codec,
@@ -1016,7 +1177,7 @@ fn property_running_job_progress_display_completeness() {
             None
         };
 
-        let job = Job {
+        let _job = Job {
             id: "running-job".to_string(),
             source_path: PathBuf::from("/test/video.mkv"),
             output_path: None,
@@ -1036,6 +1197,7 @@ fn property_running_job_progress_display_completeness() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -1045,11 +1207,28 @@ fn property_running_job_progress_display_completeness() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Property 1: Progress percentage should be clamped to 0-100 range
-        let clamped_progress = progress_percent.max(0.0).min(100.0);
-        prop_assert!(clamped_progress >= 0.0 && clamped_progress <= 100.0,
+        let clamped_progress = progress_percent.clamp(0.0, 100.0);
+        prop_assert!((0.0..=100.0).contains(&clamped_progress),
             "Progress percentage should be between 0 and 100, got {}", clamped_progress);
 
         // Property 2: Speed should be non-negative or displayed as "-"
@@ -1158,7 +1337,7 @@ fn property_fps_calculation_validity() {
         total_frames in 1000u64..=500_000,
     )| {
         // Create a job with frame rate metadata
-        let job = Job {
+        let _job = Job {
             id: "running-job".to_string(),
             source_path: PathBuf::from("/test/video.mkv"),
             output_path: None,
@@ -1182,6 +1361,7 @@ fn property_fps_calculation_validity() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -1191,6 +1371,23 @@ fn property_fps_calculation_validity() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Calculate FPS using the same logic as JobProgress::calculate_current_fps
@@ -1211,7 +1408,7 @@ fn property_fps_calculation_validity() {
 
         // Property 1: If FPS is calculated, it should be within valid range
         if let Some(fps) = calculated_fps {
-            prop_assert!(fps >= 0.1 && fps <= 500.0,
+            prop_assert!((0.1..=500.0).contains(&fps),
                 "Calculated FPS should be between 0.1 and 500, got {}", fps);
 
             // Property 2: FPS should be positive
@@ -1278,7 +1475,7 @@ fn property_running_job_estimation_display() {
         codec in prop::sample::select(vec!["hevc", "h264", "vp9", "av1"]),
     )| {
         // Create a running job with metadata
-        let job = Job {
+        let _job = Job {
             id: "running-job".to_string(),
             source_path: PathBuf::from("/test/video.mkv"),
             output_path: None,
@@ -1298,6 +1495,7 @@ fn property_running_job_estimation_display() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -1307,6 +1505,23 @@ fn property_running_job_estimation_display() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Calculate estimated output size using the same logic as calculate_estimated_output_size
@@ -1330,7 +1545,7 @@ fn property_running_job_estimation_display() {
                 _ => 1.0,
             };
 
-            let reduction = (base_reduction * codec_factor).min(0.80).max(0.35);
+            let reduction = (base_reduction * codec_factor).clamp(0.35, 0.80);
             let estimated = original_bytes as f64 * (1.0 - reduction);
             Some(estimated as u64)
         } else {
@@ -1386,7 +1601,7 @@ fn property_running_job_estimation_display() {
             let ratio = compression_ratio.unwrap();
 
             // Property 8: Compression ratio should be between 35% and 80%
-            prop_assert!(ratio >= 35.0 && ratio <= 80.0,
+            prop_assert!((35.0..=80.0).contains(&ratio),
                 "Compression ratio should be between 35% and 80%, got {:.1}%", ratio);
 
             // Property 9: Compression ratio should be positive
@@ -1470,6 +1685,7 @@ fn property_completed_job_timing_completeness() {
             crf_used: Some(23),
             preset_used: Some(4),
             encoder_used: Some("libsvtav1".to_string()),
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -1479,6 +1695,23 @@ fn property_completed_job_timing_completeness() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Property 1: Job should have all three timestamps
@@ -1547,6 +1780,7 @@ fn property_pending_job_timing_display() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -1556,6 +1790,23 @@ fn property_pending_job_timing_display() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Property 1: Pending job should not have started_at
@@ -1682,8 +1933,8 @@ fn property_duration_formatting_consistency() {
         prop_assert_eq!(&formatted, &formatted2, "Same duration should format identically");
 
         // Property 5: Minutes should be 0-59, seconds should be 0-59
-        prop_assert!(minutes >= 0 && minutes < 60, "Minutes should be 0-59");
-        prop_assert!(seconds >= 0 && seconds < 60, "Seconds should be 0-59");
+        prop_assert!((0..60).contains(&minutes), "Minutes should be 0-59");
+        prop_assert!((0..60).contains(&seconds), "Seconds should be 0-59");
 
         // Property 6: Zero duration should format as "0s"
         if duration_secs == 0 {
@@ -1714,7 +1965,7 @@ fn property_savings_estimation_with_complete_metadata() {
         bitrate in 5_000_000u64..=50_000_000,
     )| {
         // Create a pending job with complete metadata
-        let job = Job {
+        let _job = Job {
             id: "pending-job".to_string(),
             source_path: PathBuf::from("/test/video.mkv"),
             output_path: None,
@@ -1734,6 +1985,7 @@ fn property_savings_estimation_with_complete_metadata() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -1743,6 +1995,23 @@ fn property_savings_estimation_with_complete_metadata() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Calculate estimated savings using the same logic as estimate_space_savings
@@ -1765,7 +2034,7 @@ fn property_savings_estimation_with_complete_metadata() {
                 _ => 1.0,
             };
 
-            let reduction = (base_reduction * codec_factor).min(0.80).max(0.35);
+            let reduction = (base_reduction * codec_factor).clamp(0.35, 0.80);
             (original_bytes as f64 * (1.0 - reduction)) as u64
         } else {
             // Codec-based estimation
@@ -1788,7 +2057,7 @@ fn property_savings_estimation_with_complete_metadata() {
             "Savings in GB should be non-negative, got {:.2}", savings_gb);
 
         // Property 2: Savings percentage should be between 0 and 100
-        prop_assert!(savings_percent >= 0.0 && savings_percent <= 100.0,
+        prop_assert!((0.0..=100.0).contains(&savings_percent),
             "Savings percentage should be between 0 and 100, got {:.1}%", savings_percent);
 
         // Property 3: Estimated output size should be less than or equal to original
@@ -1812,7 +2081,7 @@ fn property_savings_estimation_with_complete_metadata() {
         // Property 6: With quality setting, savings should be within expected range
         if quality.is_some() {
             // Quality-based estimation should give 35-80% compression
-            prop_assert!(savings_percent >= 35.0 && savings_percent <= 80.0,
+            prop_assert!((35.0..=80.0).contains(&savings_percent),
                 "Quality-based savings should be 35-80%, got {:.1}%", savings_percent);
         }
 
@@ -1864,6 +2133,7 @@ fn property_missing_metadata_field_indication() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -1873,6 +2143,23 @@ fn property_missing_metadata_field_indication() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Build expected missing fields list
@@ -1968,7 +2255,7 @@ fn property_actual_savings_preference() {
         let new_bytes = (original_bytes as f64 * (1.0 - compression_ratio)) as u64;
 
         // Create a completed job with actual savings
-        let job = Job {
+        let _job = Job {
             id: "completed-job".to_string(),
             source_path: PathBuf::from("/test/video.mkv"),
             output_path: Some(PathBuf::from("/test/video.av1.mkv")),
@@ -1988,6 +2275,7 @@ fn property_actual_savings_preference() {
             crf_used: Some(25),
             preset_used: Some(4),
             encoder_used: Some("libsvtav1".to_string()),
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -1997,6 +2285,23 @@ fn property_actual_savings_preference() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Calculate actual savings
@@ -2034,8 +2339,6 @@ fn property_actual_savings_preference() {
             "Actual savings display should contain '%'");
 
         // Property 6: Actual savings should be positive (or zero)
-        prop_assert!(actual_savings_bytes >= 0,
-            "Actual savings should be non-negative");
         prop_assert!(actual_savings_gb >= 0.0,
             "Actual savings GB should be non-negative");
         prop_assert!(actual_savings_percent >= 0.0,
@@ -2082,6 +2385,7 @@ fn property_estimate_prefix_consistency() {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -2091,6 +2395,23 @@ fn property_estimate_prefix_consistency() {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
         // Calculate estimated savings if metadata is complete
@@ -2114,7 +2435,7 @@ fn property_estimate_prefix_consistency() {
                     _ => 1.0,
                 };
 
-                let reduction = (base_reduction * codec_factor).min(0.80).max(0.35);
+                let reduction = (base_reduction * codec_factor).clamp(0.35, 0.80);
                 (original_bytes as f64 * (1.0 - reduction)) as u64
             } else {
                 // Codec-based estimation
@@ -2243,6 +2564,7 @@ fn property_statistics_dashboard_completeness() {
                 crf_used: Some(23),
                 preset_used: Some(4),
                 encoder_used: Some("libsvtav1".to_string()),
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -2252,6 +2574,23 @@ fn property_statistics_dashboard_completeness() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -2281,6 +2620,7 @@ fn property_statistics_dashboard_completeness() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -2290,6 +2630,23 @@ fn property_statistics_dashboard_completeness() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -2316,6 +2673,7 @@ fn property_statistics_dashboard_completeness() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -2325,6 +2683,23 @@ fn property_statistics_dashboard_completeness() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -2434,7 +2809,6 @@ fn property_statistics_dashboard_completeness() {
             "Success rate should match expected: {:.2}%", expected_success_rate);
 
         // Property 5: All statistics should be non-negative
-        prop_assert!(expected_total_saved >= 0, "Total saved should be non-negative");
         prop_assert!(expected_avg_compression >= 0.0, "Avg compression should be non-negative");
         prop_assert!(expected_total_processing_time >= 0, "Total processing time should be non-negative");
         prop_assert!(expected_success_rate >= 0.0, "Success rate should be non-negative");
@@ -2486,6 +2860,7 @@ fn property_pending_savings_inclusion() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -2495,6 +2870,23 @@ fn property_pending_savings_inclusion() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -2504,15 +2896,13 @@ fn property_pending_savings_inclusion() {
             .filter(|j| j.status == JobStatus::Pending)
             .filter_map(|j| {
                 // Check if job has complete metadata for estimation
-                if j.original_bytes.is_some() &&
-                   j.video_codec.is_some() &&
-                   j.video_width.is_some() &&
-                   j.video_height.is_some() &&
-                   j.video_bitrate.is_some() &&
-                   j.video_frame_rate.is_some() {
-
-                    let orig_bytes = j.original_bytes.unwrap();
-                    let codec = j.video_codec.as_ref().unwrap();
+                if let (Some(orig_bytes), Some(codec)) = (j.original_bytes, j.video_codec.as_ref()) {
+                    if j.video_width.is_none() ||
+                       j.video_height.is_none() ||
+                       j.video_bitrate.is_none() ||
+                       j.video_frame_rate.is_none() {
+                        return None;
+                    }
 
                     // Estimate output size based on codec
                     let efficiency_factor = match codec.to_lowercase().as_str() {
@@ -2560,10 +2950,6 @@ fn property_pending_savings_inclusion() {
                 "Pending savings should be > 0 when all jobs have complete metadata");
         }
 
-        // Property 4: Pending savings should be non-negative
-        prop_assert!(expected_pending_savings >= 0,
-            "Pending savings should be non-negative");
-
         // Property 5: Each job with complete metadata should contribute some savings
         if complete_count > 0 {
             let avg_savings_per_job = expected_pending_savings / complete_count as u64;
@@ -2609,6 +2995,7 @@ fn property_zero_statistics_display() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -2618,6 +3005,23 @@ fn property_zero_statistics_display() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -2644,6 +3048,7 @@ fn property_zero_statistics_display() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -2653,6 +3058,23 @@ fn property_zero_statistics_display() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             };
             jobs.push(job);
         }
@@ -2785,6 +3207,7 @@ fn property_activity_status_display_accuracy() {
                 crf_used: None,
                 preset_used: None,
                 encoder_used: None,
+                encoding_profile: None,
                 source_bit_depth: Some(8),
                 source_pix_fmt: Some("yuv420p".to_string()),
                 is_hdr: Some(false),
@@ -2794,6 +3217,23 @@ fn property_activity_status_display_accuracy() {
                 quality_tier: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                stage: None,
+                encoded_bytes: None,
+                encoded_duration: None,
+                progress: None,
+                eta: None,
+                output_est_bytes: None,
+                speed_bps: None,
+                original_duration: None,
+                priority: None,
+                quality_scores: None,
+                crf_search: None,
+                savings_prediction: None,
+                effective_config: None,
+                grain_analysis: None,
+                crop_detection: None,
+                interlace: None,
+                subtitles: None,
             }
         };
 
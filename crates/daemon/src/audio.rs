@@ -0,0 +1,117 @@
+use crate::config::{AudioCodec, AudioPolicy, TrackPolicy};
+use crate::probe::{AudioStream, ProbeResult};
+use crate::tracks::select_tracks;
+
+/// One audio track of the output, in output order
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioOutput {
+    /// Input stream index the track is made from
+    pub source_index: usize,
+    /// `None` when the track is copied
+    pub transcode: Option<AudioTranscode>,
+    /// Untouched copy of a transcoded track, kept because of `keep_original`
+    pub is_kept_original: bool,
+}
+
+/// How a transcoded audio track is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioTranscode {
+    pub codec: AudioCodec,
+    pub bitrate_kbps: u32,
+    /// Channel count to downmix to, when the source has more than the codec supports
+    pub downmix: Option<u32>,
+}
+
+/// How `stream` is transcoded under `policy`, or `None` to copy it
+pub fn transcode_for(stream: &AudioStream, policy: &AudioPolicy) -> Option<AudioTranscode> {
+    if !policy.enabled
+        || !policy
+            .codecs
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(&stream.codec_name))
+    {
+        return None;
+    }
+
+    let downmix = stream
+        .channels
+        .filter(|&c| c > policy.codec.max_channels())
+        .map(|_| policy.codec.max_channels());
+    let bitrate_kbps = policy.bitrates.for_channels(downmix.or(stream.channels));
+
+    // Nothing to gain from re-encoding a track that is already this small
+    if stream
+        .bitrate
+        .is_some_and(|b| b <= bitrate_kbps as u64 * 1000)
+    {
+        return None;
+    }
+
+    Some(AudioTranscode {
+        codec: policy.codec,
+        bitrate_kbps,
+        downmix,
+    })
+}
+
+/// Output audio tracks for the tracks `track_policy` keeps.
+///
+/// A transcoded track is followed by its source track when `keep_original` is set.
+pub fn plan_audio(
+    probe: &ProbeResult,
+    track_policy: &TrackPolicy,
+    audio_policy: &AudioPolicy,
+) -> Vec<AudioOutput> {
+    let selection = select_tracks(probe, track_policy);
+    let mut outputs = Vec::new();
+    for index in selection.audio {
+        let transcode = probe
+            .audio_streams
+            .iter()
+            .find(|s| s.index == index)
+            .and_then(|s| transcode_for(s, audio_policy));
+        outputs.push(AudioOutput {
+            source_index: index,
+            transcode,
+            is_kept_original: false,
+        });
+        if transcode.is_some() && audio_policy.keep_original {
+            outputs.push(AudioOutput {
+                source_index: index,
+                transcode: None,
+                is_kept_original: true,
+            });
+        }
+    }
+    outputs
+}
+
+/// Per-track `-c:a:N` arguments for `outputs`, numbered in output order
+pub fn audio_codec_flags(outputs: &[AudioOutput]) -> Vec<String> {
+    let mut flags = Vec::new();
+    for (n, output) in outputs.iter().enumerate() {
+        let Some(transcode) = output.transcode else {
+            flags.extend([format!("-c:a:{}", n), "copy".to_string()]);
+            if output.is_kept_original {
+                // The transcoded track before it keeps the default flag
+                flags.extend([format!("-disposition:a:{}", n), "0".to_string()]);
+            }
+            continue;
+        };
+
+        flags.extend([
+            format!("-c:a:{}", n),
+            transcode.codec.encoder().to_string(),
+            format!("-b:a:{}", n),
+            format!("{}k", transcode.bitrate_kbps),
+        ]);
+        if let Some(channels) = transcode.downmix {
+            flags.extend([format!("-ac:a:{}", n), channels.to_string()]);
+        }
+        if transcode.codec == AudioCodec::Opus {
+            // Surround layouts need the Vorbis channel mapping family
+            flags.extend([format!("-mapping_family:a:{}", n), "1".to_string()]);
+        }
+    }
+    flags
+}
@@ -1,14 +1,15 @@
 use crate::probe::{select_main_video_stream, ProbeResult};
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SourceType {
     WebLike,
     DiscLike,
     Unknown,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SourceClassification {
     pub source_type: SourceType,
     pub web_score: i32,
@@ -62,7 +63,7 @@ pub fn classify_source(path: &Path, probe: &ProbeResult) -> SourceClassification
                 // 2160p with bitrate < 10 Mbps
                 web_score += 5;
                 reasons.push(format!("Low bitrate for 2160p: {} bps", br));
-            } else if height >= 1080 && height < 2160 && br < 5_000_000 {
+            } else if (1080..2160).contains(&height) && br < 5_000_000 {
                 // 1080p with bitrate < 5 Mbps
                 web_score += 5;
                 reasons.push(format!("Low bitrate for 1080p: {} bps", br));
@@ -73,7 +74,7 @@ pub fn classify_source(path: &Path, probe: &ProbeResult) -> SourceClassification
                 // 2160p with bitrate > 40 Mbps
                 disc_score += 5;
                 reasons.push(format!("High bitrate for 2160p: {} bps", br));
-            } else if height >= 1080 && height < 2160 && br > 15_000_000 {
+            } else if (1080..2160).contains(&height) && br > 15_000_000 {
                 // 1080p with bitrate > 15 Mbps
                 disc_score += 5;
                 reasons.push(format!("High bitrate for 1080p: {} bps", br));
@@ -0,0 +1,405 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use crate::config::DaemonConfig;
+use crate::jobs::{load_all_jobs, reset_for_requeue, update_job_status, Job, JobStatus};
+use crate::retry::update_ledger;
+use crate::sidecars::{create_skip_marker, remove_skip_marker, write_why_file};
+
+/// Highest command file format version this daemon understands
+pub const COMMAND_FORMAT_VERSION: u32 = 1;
+
+/// Subdirectory of the command directory where acknowledgements are written
+pub const ACK_DIR_NAME: &str = "acks";
+
+/// A command file written into the command directory (e.g. by av1top)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandRequest {
+    /// Format version; files written before versioning are treated as version 1
+    #[serde(default = "default_command_version")]
+    pub version: u32,
+    pub action: CommandAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// Source file path, used by skip/unskip when no job exists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Priority increment for bump_priority (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+fn default_command_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandAction {
+    /// Stop a running job (if any) and put it back in the queue
+    Requeue,
+    /// Stop the current attempt of a running or pending job and mark it failed.
+    ///
+    /// Nothing holds the file back afterwards (no skip marker, no retry-ledger
+    /// entry), so the next scan queues it again; use `Skip` to keep it out.
+    Cancel,
+    /// Put a failed or skipped job back in the queue
+    Retry,
+    /// Add a permanent .av1skip marker
+    Skip,
+    /// Remove the .av1skip marker and .why.txt file
+    Unskip,
+    /// Raise a pending job's priority
+    BumpPriority,
+}
+
+/// Acknowledgement written back for every processed command file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandAck {
+    pub version: u32,
+    /// Name of the command file this acknowledges
+    pub command_file: String,
+    pub action: Option<CommandAction>,
+    pub job_id: Option<String>,
+    pub success: bool,
+    pub message: String,
+    pub processed_at: DateTime<Utc>,
+}
+
+/// Request delivered to a job that is currently running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobInterrupt {
+    Requeue,
+    Cancel,
+    Skip,
+}
+
+/// Registry of running jobs that can be interrupted by commands
+#[derive(Debug, Clone, Default)]
+pub struct RunningJobs {
+    inner: Arc<Mutex<HashMap<String, watch::Sender<Option<JobInterrupt>>>>>,
+}
+
+impl RunningJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a running job and get the receiver its encode should watch
+    pub fn register(&self, job_id: &str) -> watch::Receiver<Option<JobInterrupt>> {
+        let (tx, rx) = watch::channel(None);
+        self.inner.lock().unwrap().insert(job_id.to_string(), tx);
+        rx
+    }
+
+    /// Register a running job until the returned guard is dropped
+    pub fn register_scoped(
+        &self,
+        job_id: &str,
+    ) -> (RunningJobGuard, watch::Receiver<Option<JobInterrupt>>) {
+        let guard = RunningJobGuard {
+            running: self.clone(),
+            job_id: job_id.to_string(),
+        };
+        (guard, self.register(job_id))
+    }
+
+    pub fn unregister(&self, job_id: &str) {
+        self.inner.lock().unwrap().remove(job_id);
+    }
+
+    pub fn is_running(&self, job_id: &str) -> bool {
+        self.inner.lock().unwrap().contains_key(job_id)
+    }
+
+    /// Send an interrupt to a running job; returns false if the job is not running
+    pub fn interrupt(&self, job_id: &str, interrupt: JobInterrupt) -> bool {
+        match self.inner.lock().unwrap().get(job_id) {
+            Some(tx) => tx.send(Some(interrupt)).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/// Unregisters a running job when dropped, however its pipeline ends
+#[derive(Debug)]
+pub struct RunningJobGuard {
+    running: RunningJobs,
+    job_id: String,
+}
+
+impl Drop for RunningJobGuard {
+    fn drop(&mut self) {
+        self.running.unregister(&self.job_id);
+    }
+}
+
+/// Poll the command directory forever, handling commands as they arrive
+pub async fn run_command_loop(config: DaemonConfig, running: RunningJobs) {
+    let command_dir = config.command_dir();
+    info!("Watching command directory: {:?}", command_dir);
+
+    loop {
+        if let Err(e) = process_pending_commands(&config, &running) {
+            warn!(
+                "Failed to process command directory {:?}: {}",
+                command_dir, e
+            );
+        }
+        tokio::time::sleep(Duration::from_secs(config.command_poll_interval_secs)).await;
+    }
+}
+
+/// Handle every command file currently in the command directory.
+///
+/// Each file is removed once handled and an acknowledgement is written to
+/// `<command_dir>/acks/<name>`. Returns the acknowledgements in processing order.
+pub fn process_pending_commands(
+    config: &DaemonConfig,
+    running: &RunningJobs,
+) -> Result<Vec<CommandAck>> {
+    let command_dir = config.command_dir();
+    if !command_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut command_files: Vec<PathBuf> = fs::read_dir(&command_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_command_file(path))
+        .collect();
+    command_files.sort();
+
+    let mut acks = Vec::new();
+    for path in command_files {
+        let ack = process_command_file(&path, config, running);
+        if ack.success {
+            info!("Command {}: {}", ack.command_file, ack.message);
+        } else {
+            warn!("Command {} rejected: {}", ack.command_file, ack.message);
+        }
+
+        write_ack(&command_dir, &ack)?;
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove command file {}", path.display()))?;
+        acks.push(ack);
+    }
+
+    Ok(acks)
+}
+
+/// Command files are visible `*.json` files (temp files start with '.')
+fn is_command_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    path.is_file() && !name.starts_with('.') && name.ends_with(".json")
+}
+
+fn process_command_file(path: &Path, config: &DaemonConfig, running: &RunningJobs) -> CommandAck {
+    let command_file = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    debug!("Processing command file: {:?}", path);
+
+    let request = fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| {
+            serde_json::from_str::<CommandRequest>(&contents)
+                .context("Failed to parse command file")
+        });
+
+    let request = match request {
+        Ok(request) => request,
+        Err(e) => {
+            return CommandAck {
+                version: COMMAND_FORMAT_VERSION,
+                command_file,
+                action: None,
+                job_id: None,
+                success: false,
+                message: format!("{:#}", e),
+                processed_at: Utc::now(),
+            };
+        }
+    };
+
+    let result = handle_command(&request, config, running);
+    CommandAck {
+        version: COMMAND_FORMAT_VERSION,
+        command_file,
+        action: Some(request.action),
+        job_id: request.job_id.clone(),
+        success: result.is_ok(),
+        message: result.unwrap_or_else(|e| format!("{:#}", e)),
+        processed_at: Utc::now(),
+    }
+}
+
+/// Apply a single command and return a human-readable result message
+pub fn handle_command(
+    request: &CommandRequest,
+    config: &DaemonConfig,
+    running: &RunningJobs,
+) -> Result<String> {
+    if request.version > COMMAND_FORMAT_VERSION {
+        anyhow::bail!(
+            "Unsupported command version {} (max supported: {})",
+            request.version,
+            COMMAND_FORMAT_VERSION
+        );
+    }
+
+    let state_dir = &config.job_state_dir;
+    let mut job = match &request.job_id {
+        Some(id) => Some(find_job(state_dir, id)?),
+        None => None,
+    };
+
+    match request.action {
+        CommandAction::Requeue => {
+            let job = require_job(&mut job, request.action)?;
+            if running.interrupt(&job.id, JobInterrupt::Requeue) {
+                return Ok(format!("Requeue requested for running job {}", job.id));
+            }
+            if !matches!(job.status, JobStatus::Running | JobStatus::Pending) {
+                anyhow::bail!(
+                    "Job {} is {:?}; only running or pending jobs can be requeued (use retry)",
+                    job.id,
+                    job.status
+                );
+            }
+            reset_for_requeue(job);
+            update_job_status(job, JobStatus::Pending, state_dir)?;
+            Ok(format!("Job {} requeued", job.id))
+        }
+        CommandAction::Cancel => {
+            let job = require_job(&mut job, request.action)?;
+            if running.interrupt(&job.id, JobInterrupt::Cancel) {
+                return Ok(format!("Cancel requested for running job {}", job.id));
+            }
+            if !matches!(job.status, JobStatus::Running | JobStatus::Pending) {
+                anyhow::bail!("Job {} is {:?} and cannot be cancelled", job.id, job.status);
+            }
+            job.reason = Some(cancel_reason(request));
+            update_job_status(job, JobStatus::Failed, state_dir)?;
+            Ok(format!("Job {} cancelled", job.id))
+        }
+        CommandAction::Retry => {
+            let job = require_job(&mut job, request.action)?;
+            if !matches!(job.status, JobStatus::Failed | JobStatus::Skipped) {
+                anyhow::bail!(
+                    "Job {} is {:?}; only failed or skipped jobs can be retried",
+                    job.id,
+                    job.status
+                );
+            }
+            remove_skip_marker(&job.source_path)?;
+            update_ledger(config, |ledger| ledger.clear(&job.source_path))?;
+            reset_for_requeue(job);
+            update_job_status(job, JobStatus::Pending, state_dir)?;
+            Ok(format!("Job {} queued for retry", job.id))
+        }
+        CommandAction::Skip => {
+            let path = target_path(request, job.as_ref())?;
+            create_skip_marker(&path)?;
+            if config.write_why_sidecars {
+                write_why_file(&path, &skip_reason(request))?;
+            }
+            if let Some(job) = job.as_mut() {
+                if running.interrupt(&job.id, JobInterrupt::Skip) {
+                    return Ok(format!(
+                        "Skip marker added; stopping running job {}",
+                        job.id
+                    ));
+                }
+                if job.status == JobStatus::Pending {
+                    job.reason = Some(skip_reason(request));
+                    update_job_status(job, JobStatus::Skipped, state_dir)?;
+                }
+            }
+            Ok(format!("Skip marker added for {}", path.display()))
+        }
+        CommandAction::Unskip => {
+            let path = target_path(request, job.as_ref())?;
+            remove_skip_marker(&path)?;
+            update_ledger(config, |ledger| ledger.clear(&path))?;
+            Ok(format!("Skip marker removed for {}", path.display()))
+        }
+        CommandAction::BumpPriority => {
+            let job = require_job(&mut job, request.action)?;
+            if job.status != JobStatus::Pending {
+                anyhow::bail!(
+                    "Job {} is {:?}; only pending jobs can be reprioritized",
+                    job.id,
+                    job.status
+                );
+            }
+            let priority = job.priority.unwrap_or(0) + request.priority.unwrap_or(1);
+            job.priority = Some(priority);
+            update_job_status(job, JobStatus::Pending, state_dir)?;
+            Ok(format!("Job {} priority set to {}", job.id, priority))
+        }
+    }
+}
+
+fn find_job(state_dir: &Path, job_id: &str) -> Result<Job> {
+    load_all_jobs(state_dir)?
+        .into_iter()
+        .find(|job| job.id == job_id)
+        .ok_or_else(|| anyhow::anyhow!("Job {} not found", job_id))
+}
+
+fn require_job(job: &mut Option<Job>, action: CommandAction) -> Result<&mut Job> {
+    job.as_mut()
+        .ok_or_else(|| anyhow::anyhow!("{:?} command requires a job_id", action))
+}
+
+fn target_path(request: &CommandRequest, job: Option<&Job>) -> Result<PathBuf> {
+    request
+        .path
+        .clone()
+        .or_else(|| job.map(|j| j.source_path.clone()))
+        .ok_or_else(|| anyhow::anyhow!("{:?} command requires a job_id or path", request.action))
+}
+
+/// Reason recorded on a job cancelled by a command
+fn cancel_reason(request: &CommandRequest) -> String {
+    match &request.reason {
+        Some(reason) => format!("Cancelled by user: {}", reason),
+        None => "Cancelled by user".to_string(),
+    }
+}
+
+fn skip_reason(request: &CommandRequest) -> String {
+    match &request.reason {
+        Some(reason) => format!("Skipped by user: {}", reason),
+        None => "Skipped by user".to_string(),
+    }
+}
+
+fn write_ack(command_dir: &Path, ack: &CommandAck) -> Result<()> {
+    let ack_dir = command_dir.join(ACK_DIR_NAME);
+    fs::create_dir_all(&ack_dir)?;
+
+    let ack_file = ack_dir.join(&ack.command_file);
+    let temp_file = ack_dir.join(format!(".{}.tmp", ack.command_file));
+    fs::write(&temp_file, serde_json::to_string_pretty(ack)?)
+        .with_context(|| format!("Failed to write ack file: {}", temp_file.display()))?;
+    fs::rename(&temp_file, &ack_file)
+        .with_context(|| format!("Failed to rename ack file: {}", ack_file.display()))?;
+
+    Ok(())
+}
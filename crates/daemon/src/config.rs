@@ -1,7 +1,11 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+use crate::profiles::{EncodingProfile, ProfileRule, ResolutionStep};
+use crate::startup::AvailableEncoder;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct DaemonConfig {
@@ -9,6 +13,14 @@ pub struct DaemonConfig {
     pub min_bytes: u64,
     pub max_size_ratio: f64,
     pub scan_interval_secs: u64,
+    /// Extensions (without the dot) treated as video, matched case-insensitively
+    pub video_extensions: Vec<String>,
+    /// Include/exclude rules applied while walking the library roots
+    pub scan_rules: Vec<ScanRules>,
+    /// A scanned file must not have been modified for this long before it is processed
+    pub stable_quiet_secs: u64,
+    /// Size and mtime of all scanned files are compared again after this long
+    pub stable_recheck_secs: u64,
     pub job_state_dir: PathBuf,
     pub temp_output_dir: PathBuf,
     pub max_concurrent_jobs: usize,
@@ -16,6 +28,606 @@ pub struct DaemonConfig {
     pub quality_tier: QualityTier,
     pub keep_original: bool,
     pub write_why_sidecars: bool,
+    /// Directory watched for TUI command files (default: {job_state_dir}/../commands)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_dir: Option<PathBuf>,
+    pub command_poll_interval_secs: u64,
+    /// What to do at startup with jobs a previous daemon run left unfinished
+    pub interrupted_job_policy: InterruptedJobPolicy,
+    /// Failed attempts per file before it gets a skip marker
+    pub max_attempts: u32,
+    /// Backoff after the first failure; doubles with each further failure
+    pub retry_base_delay_secs: u64,
+    pub retry_max_delay_secs: u64,
+    /// Stop encodes whose projected output size will clearly fail the size gate
+    pub early_abort: bool,
+    pub early_abort_min_percent: f64,
+    pub early_abort_min_encoded_secs: u64,
+    pub early_abort_margin: f64,
+    /// Keep the source container when possible, or always write Matroska
+    pub output_container: OutputContainerPolicy,
+    /// Queue files from inotify events instead of rescanning every `scan_interval_secs`
+    pub watch_mode: bool,
+    /// Quiet period after the last close-write/moved-to event before a file is queued
+    pub watch_debounce_secs: u64,
+    /// Full rescan interval in watch mode, for events missed on network filesystems
+    pub watch_full_rescan_secs: u64,
+    /// Remember probe, classification and gate results for unchanged files
+    pub scan_cache: bool,
+    /// Which audio and subtitle tracks are kept in the output
+    pub track_policy: TrackPolicy,
+    /// Which audio codecs are transcoded instead of copied, and to what
+    pub audio_policy: AudioPolicy,
+    /// Which subtitle tracks are also extracted to sidecar files
+    pub subtitle_policy: SubtitlePolicy,
+    /// Sample-based SSIM/PSNR/VMAF comparison before the original is replaced
+    pub quality_check: QualityCheckConfig,
+    /// Full decode of the output before the original is replaced
+    pub verify: VerifyConfig,
+    /// Per-file CRF search toward a target quality score
+    pub crf_search: CrfSearchConfig,
+    /// Sample-based size prediction that skips files before a full encode
+    pub savings_prediction: SavingsPredictionConfig,
+    /// CRF by resolution for each encoder and quality tier
+    pub crf_tables: CrfTables,
+    /// Speed preset by resolution for each encoder and quality tier
+    pub preset_tables: PresetTables,
+    /// Raise or lower the CRF for sources with unusually low or high bitrates
+    pub bitrate_adjustment: BitrateAdjustment,
+    /// Sample-based noise estimate that turns on film grain synthesis
+    pub grain_detection: GrainDetectionConfig,
+    /// Sample-based black bar detection that crops letterboxed video
+    pub crop_detection: CropDetectionConfig,
+    /// Deinterlacing and inverse telecine for interlaced sources
+    pub deinterlace: DeinterlaceConfig,
+    /// Named encoder settings, picked per file by `profile_rules`
+    pub profiles: BTreeMap<String, EncodingProfile>,
+    /// Checked in order; the first matching rule picks the profile
+    pub profile_rules: Vec<ProfileRule>,
+}
+
+/// Include/exclude rules for the files scanned under a library root.
+///
+/// Patterns are matched against the path relative to the library root, e.g.
+/// `TV/Show/S01E01.mkv`. Globs are case-insensitive and `*` also matches `/`;
+/// regexes are matched as written. A file is excluded when any exclude pattern
+/// matches, or when include patterns are set and none matches.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanRules {
+    /// Library root these rules apply to; rules without a root apply to all roots
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub include_regex: Vec<String>,
+    pub exclude_regex: Vec<String>,
+    /// Deepest directory level scanned; files directly in the root are at depth 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+}
+
+/// Audio and subtitle track selection.
+///
+/// Language codes are matched case-insensitively and exactly, so list both
+/// forms where a library mixes them (e.g. `"en"` and `"eng"`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackPolicy {
+    /// When non-empty, audio tracks in other languages are dropped
+    pub audio_keep_languages: Vec<String>,
+    pub audio_drop_languages: Vec<String>,
+    /// When non-empty, subtitle tracks in other languages are dropped
+    pub subtitle_keep_languages: Vec<String>,
+    pub subtitle_drop_languages: Vec<String>,
+    /// Drop non-default tracks flagged or titled as commentary
+    pub drop_commentary: bool,
+    /// Drop tracks with no language tag or `und`
+    pub drop_undetermined: bool,
+}
+
+/// Transcode audio tracks in lossless or bloated codecs instead of copying them.
+///
+/// Kept tracks whose codec is listed in `codecs` are encoded to `codec` at the
+/// bitrate for their channel layout; all other tracks are copied. Tracks whose
+/// reported bitrate is already at or below the target are copied too.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioPolicy {
+    pub enabled: bool,
+    /// ffprobe codec names to transcode, e.g. `truehd`, `dts`, `pcm_s24le`
+    pub codecs: Vec<String>,
+    pub codec: AudioCodec,
+    pub bitrates: AudioBitrates,
+    /// Also keep the source track, after the transcoded one and not flagged default
+    pub keep_original: bool,
+}
+
+impl Default for AudioPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            codecs: [
+                "truehd",
+                "dts",
+                "pcm_s16le",
+                "pcm_s24le",
+                "pcm_bluray",
+                "pcm_dvd",
+            ]
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+            codec: AudioCodec::Opus,
+            bitrates: AudioBitrates::default(),
+            keep_original: false,
+        }
+    }
+}
+
+/// Transcode bitrates in kbps by channel layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioBitrates {
+    pub mono: u32,
+    pub stereo: u32,
+    /// Three to six channels
+    pub surround: u32,
+    /// Seven or more channels
+    pub surround_wide: u32,
+}
+
+impl Default for AudioBitrates {
+    fn default() -> Self {
+        Self {
+            mono: 64,
+            stereo: 128,
+            surround: 320,
+            surround_wide: 448,
+        }
+    }
+}
+
+impl AudioBitrates {
+    /// Bitrate for a track with `channels`; unknown layouts get the stereo rate
+    pub fn for_channels(&self, channels: Option<u32>) -> u32 {
+        match channels {
+            Some(1) => self.mono,
+            Some(3..=6) => self.surround,
+            Some(7..) => self.surround_wide,
+            _ => self.stereo,
+        }
+    }
+}
+
+/// Subtitle handling beyond the track policy.
+///
+/// Kept subtitle tracks are copied when the output container can hold them,
+/// converted when they are text, and dropped otherwise; sidecar extraction
+/// keeps dropped text and PGS tracks next to the video.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitlePolicy {
+    pub extract: SubtitleExtraction,
+}
+
+/// Which kept text and PGS subtitle tracks are written to sidecar files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtitleExtraction {
+    #[default]
+    None,
+    /// Only tracks the output container cannot hold
+    Dropped,
+    All,
+}
+
+/// Compare evenly spaced samples of source and output before replacing.
+///
+/// Scores are per sample and the worst sample must meet each threshold.
+/// A threshold of 0 disables that metric.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityCheckConfig {
+    pub enabled: bool,
+    /// Number of evenly spaced segments to compare
+    pub samples: u32,
+    /// Length of each segment in seconds
+    pub sample_secs: u32,
+    /// SSIM (All), 0.0-1.0
+    pub min_ssim: f64,
+    /// Average PSNR in dB
+    pub min_psnr: f64,
+    /// VMAF, 0-100; only measured when ffmpeg is built with libvmaf
+    pub min_vmaf: f64,
+}
+
+impl Default for QualityCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            samples: 5,
+            sample_secs: 10,
+            min_ssim: 0.95,
+            min_psnr: 35.0,
+            min_vmaf: 85.0,
+        }
+    }
+}
+
+/// Decode the whole output after validation; any decoder error fails the job.
+///
+/// Catches corruption that probing cannot see, at the cost of reading and
+/// decoding the full file once more.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerifyConfig {
+    pub enabled: bool,
+}
+
+/// Pick the CRF per file by encoding samples at candidate CRFs.
+///
+/// The highest CRF whose mean sample score reaches `target` is used; when none
+/// does, `min_crf` is used. Without libvmaf a VMAF search falls back to the
+/// resolution-based CRF.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrfSearchConfig {
+    pub enabled: bool,
+    pub metric: QualityMetric,
+    /// Score to reach: VMAF 0-100, SSIM 0.0-1.0 or PSNR in dB
+    pub target: f64,
+    pub min_crf: u8,
+    pub max_crf: u8,
+    /// Number of evenly spaced segments encoded per candidate CRF
+    pub samples: u32,
+    /// Length of each segment in seconds
+    pub sample_secs: u32,
+}
+
+impl Default for CrfSearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            metric: QualityMetric::Vmaf,
+            target: 95.0,
+            min_crf: 14,
+            max_crf: 40,
+            samples: 3,
+            sample_secs: 10,
+        }
+    }
+}
+
+/// Predict the output size from sample encodes before the full encode.
+///
+/// Files whose predicted size ratio reaches `max_size_ratio * margin` are
+/// skipped without encoding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavingsPredictionConfig {
+    pub enabled: bool,
+    /// Number of evenly spaced segments to encode
+    pub samples: u32,
+    /// Length of each segment in seconds
+    pub sample_secs: u32,
+    /// How far above `max_size_ratio` the prediction must be, e.g. 1.05 = 5% above
+    pub margin: f64,
+}
+
+impl Default for SavingsPredictionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            samples: 4,
+            sample_secs: 20,
+            margin: 1.05,
+        }
+    }
+}
+
+/// Estimate the source's grain from sampled frames and synthesise it.
+///
+/// The noise level is the luma difference between the source and a denoised
+/// copy. Sources at or above `min_noise` get film grain synthesis of
+/// `noise_level * strength_per_noise`, capped at `max_film_grain`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrainDetectionConfig {
+    pub enabled: bool,
+    /// Number of evenly spaced segments to analyse
+    pub samples: u32,
+    /// Length of each segment in seconds
+    pub sample_secs: u32,
+    /// Noise level below which the source is treated as clean
+    pub min_noise: f64,
+    /// Film grain strength per unit of noise level
+    pub strength_per_noise: f64,
+    /// Highest film grain strength to use (1-50)
+    pub max_film_grain: u8,
+    /// Let the encoder denoise the source, so grain is synthesised instead of coded
+    pub denoise: bool,
+}
+
+impl Default for GrainDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            samples: 4,
+            sample_secs: 2,
+            min_noise: 1.5,
+            strength_per_noise: 4.0,
+            max_film_grain: 25,
+            denoise: true,
+        }
+    }
+}
+
+/// Detect black bars with ffmpeg `cropdetect` on sampled segments and crop them.
+///
+/// The crop is only applied when at least `min_agreement` of the samples find
+/// the same bars, so mixed-aspect films and dark scenes keep the full frame.
+/// Encoding profiles can turn detection on or off with `crop`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CropDetectionConfig {
+    pub enabled: bool,
+    /// Number of evenly spaced segments to analyse
+    pub samples: u32,
+    /// Length of each segment in seconds
+    pub sample_secs: u32,
+    /// Brightness (0-255) at or below which a row or column counts as black
+    pub limit: u8,
+    /// Fraction of samples that must agree on the crop, 0.5-1.0
+    pub min_agreement: f64,
+}
+
+impl Default for CropDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            samples: 8,
+            sample_secs: 5,
+            limit: 24,
+            min_agreement: 0.75,
+        }
+    }
+}
+
+/// Deinterlace sources whose field order says they are interlaced.
+///
+/// With `detect`, an ffmpeg `idet` pass over sampled segments confirms it
+/// first: content that turns out progressive is left alone and telecined film
+/// gets inverse telecine instead of `bwdif`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeinterlaceConfig {
+    pub enabled: bool,
+    /// Confirm interlacing with an `idet` pass on sampled segments
+    pub detect: bool,
+    /// Number of evenly spaced segments to analyse
+    pub samples: u32,
+    /// Length of each segment in seconds
+    pub sample_secs: u32,
+    /// Fraction of decided frames `idet` must find interlaced
+    pub min_interlaced_ratio: f64,
+    /// Fraction of frames with a repeated field that marks telecined film
+    pub min_repeated_ratio: f64,
+}
+
+impl Default for DeinterlaceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            detect: false,
+            samples: 4,
+            sample_secs: 10,
+            min_interlaced_ratio: 0.2,
+            min_repeated_ratio: 0.1,
+        }
+    }
+}
+
+/// Resolution tables for both quality tiers, ordered by descending `min_height`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TierTables {
+    pub high: Vec<ResolutionStep>,
+    pub very_high: Vec<ResolutionStep>,
+}
+
+impl TierTables {
+    pub fn get(&self, quality_tier: QualityTier) -> &[ResolutionStep] {
+        match quality_tier {
+            QualityTier::High => &self.high,
+            QualityTier::VeryHigh => &self.very_high,
+        }
+    }
+}
+
+fn steps(table: &[(i32, u8)]) -> Vec<ResolutionStep> {
+    table
+        .iter()
+        .map(|&(min_height, value)| ResolutionStep { min_height, value })
+        .collect()
+}
+
+fn default_crf_tiers() -> TierTables {
+    TierTables {
+        high: steps(&[(2160, 18), (1440, 19), (1080, 20), (0, 21)]),
+        very_high: steps(&[(2160, 16), (1440, 17), (1080, 18), (0, 19)]),
+    }
+}
+
+/// CRF tables per encoder (`-qp` for rav1e)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrfTables {
+    #[serde(default = "default_crf_tiers")]
+    pub svt: TierTables,
+    #[serde(default = "default_crf_tiers")]
+    pub aom: TierTables,
+    #[serde(default = "default_crf_tiers")]
+    pub rav1e: TierTables,
+}
+
+impl Default for CrfTables {
+    fn default() -> Self {
+        Self {
+            svt: default_crf_tiers(),
+            aom: default_crf_tiers(),
+            rav1e: default_crf_tiers(),
+        }
+    }
+}
+
+fn default_svt_presets() -> TierTables {
+    TierTables {
+        high: steps(&[(2160, 1), (1080, 2), (0, 3)]),
+        very_high: steps(&[(1080, 0), (0, 1)]),
+    }
+}
+
+fn default_aom_presets() -> TierTables {
+    TierTables {
+        high: steps(&[(1081, 3), (0, 4)]),
+        very_high: steps(&[(1081, 3), (0, 4)]),
+    }
+}
+
+fn default_rav1e_presets() -> TierTables {
+    TierTables {
+        high: Vec::new(),
+        very_high: Vec::new(),
+    }
+}
+
+/// Speed preset tables per encoder: SVT-AV1 `-preset`, libaom `-cpu-used` and
+/// rav1e `-speed`. An empty table leaves the encoder's own default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetTables {
+    #[serde(default = "default_svt_presets")]
+    pub svt: TierTables,
+    #[serde(default = "default_aom_presets")]
+    pub aom: TierTables,
+    #[serde(default = "default_rav1e_presets")]
+    pub rav1e: TierTables,
+}
+
+impl Default for PresetTables {
+    fn default() -> Self {
+        Self {
+            svt: default_svt_presets(),
+            aom: default_aom_presets(),
+            rav1e: default_rav1e_presets(),
+        }
+    }
+}
+
+impl CrfTables {
+    pub fn get(&self, encoder: AvailableEncoder) -> &TierTables {
+        match encoder {
+            AvailableEncoder::SvtAv1 => &self.svt,
+            AvailableEncoder::LibaomAv1 => &self.aom,
+            AvailableEncoder::Librav1e => &self.rav1e,
+        }
+    }
+}
+
+impl PresetTables {
+    pub fn get(&self, encoder: AvailableEncoder) -> &TierTables {
+        match encoder {
+            AvailableEncoder::SvtAv1 => &self.svt,
+            AvailableEncoder::LibaomAv1 => &self.aom,
+            AvailableEncoder::Librav1e => &self.rav1e,
+        }
+    }
+}
+
+/// Bitrate-aware CRF adjustment.
+///
+/// Thresholds are for a 1080p source and scale with the pixel count, so a 4K
+/// source needs four times the bitrate. Sources already starved of bits gain
+/// little from a low CRF; very high bitrate sources have detail worth keeping.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BitrateAdjustment {
+    pub enabled: bool,
+    /// Bits per second below which the CRF is raised
+    pub low_bitrate: u64,
+    pub low_bitrate_crf_offset: u8,
+    /// Bits per second above which the CRF is lowered
+    pub high_bitrate: u64,
+    pub high_bitrate_crf_offset: u8,
+}
+
+impl Default for BitrateAdjustment {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            low_bitrate: 4_000_000,
+            low_bitrate_crf_offset: 2,
+            high_bitrate: 30_000_000,
+            high_bitrate_crf_offset: 1,
+        }
+    }
+}
+
+impl BitrateAdjustment {
+    /// `crf` adjusted for a source of `height` at `bitrate`
+    pub fn adjust(&self, crf: u8, height: i32, bitrate: Option<u64>) -> u8 {
+        let Some(bitrate) = bitrate.filter(|_| self.enabled) else {
+            return crf;
+        };
+        let scale = (height.max(1) as f64 / 1080.0).powi(2);
+        let bitrate = bitrate as f64;
+        if bitrate < self.low_bitrate as f64 * scale {
+            crf.saturating_add(self.low_bitrate_crf_offset).min(63)
+        } else if bitrate > self.high_bitrate as f64 * scale {
+            crf.saturating_sub(self.high_bitrate_crf_offset)
+        } else {
+            crf
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QualityMetric {
+    Ssim,
+    Psnr,
+    Vmaf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioCodec {
+    Opus,
+    Eac3,
+}
+
+impl AudioCodec {
+    /// ffmpeg encoder name
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            AudioCodec::Opus => "libopus",
+            AudioCodec::Eac3 => "eac3",
+        }
+    }
+
+    /// Codec name ffprobe reports for the encoded track
+    pub fn codec_name(&self) -> &'static str {
+        match self {
+            AudioCodec::Opus => "opus",
+            AudioCodec::Eac3 => "eac3",
+        }
+    }
+
+    /// Most channels the encoder can write; wider tracks are downmixed
+    pub fn max_channels(&self) -> u32 {
+        match self {
+            AudioCodec::Opus => 8,
+            AudioCodec::Eac3 => 6,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -33,6 +645,26 @@ pub enum QualityTier {
     VeryHigh,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputContainerPolicy {
+    /// Keep MP4/WebM sources in their container when all streams fit, else switch to mkv
+    KeepSource,
+    /// Always write Matroska, renaming non-mkv sources to .mkv
+    Mkv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterruptedJobPolicy {
+    /// Requeue every interrupted job
+    Requeue,
+    /// Requeue jobs that were still queued; fail jobs that were mid-encode
+    RequeueQueued,
+    /// Fail every interrupted job and mark its source with a skip marker
+    Fail,
+}
+
 impl Default for DaemonConfig {
     fn default() -> Self {
         Self {
@@ -40,6 +672,13 @@ impl Default for DaemonConfig {
             min_bytes: 2_147_483_648, // 2 GiB
             max_size_ratio: 0.90,
             scan_interval_secs: 60,
+            video_extensions: crate::scan::VIDEO_EXTENSIONS
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_string())
+                .collect(),
+            scan_rules: Vec::new(),
+            stable_quiet_secs: 60,
+            stable_recheck_secs: 10,
             job_state_dir: PathBuf::from("/var/lib/av1d/jobs"),
             temp_output_dir: PathBuf::from("/var/lib/av1d/temp"),
             max_concurrent_jobs: 1,
@@ -47,10 +686,68 @@ impl Default for DaemonConfig {
             quality_tier: QualityTier::VeryHigh,
             keep_original: false,
             write_why_sidecars: true,
+            command_dir: None,
+            command_poll_interval_secs: 2,
+            interrupted_job_policy: InterruptedJobPolicy::Requeue,
+            max_attempts: 3,
+            retry_base_delay_secs: 600,   // 10 minutes
+            retry_max_delay_secs: 86_400, // 1 day
+            early_abort: true,
+            early_abort_min_percent: 10.0,
+            early_abort_min_encoded_secs: 300,
+            early_abort_margin: 1.10,
+            output_container: OutputContainerPolicy::KeepSource,
+            watch_mode: false,
+            watch_debounce_secs: 30,
+            watch_full_rescan_secs: 21_600, // 6 hours
+            scan_cache: true,
+            track_policy: TrackPolicy::default(),
+            audio_policy: AudioPolicy::default(),
+            subtitle_policy: SubtitlePolicy::default(),
+            quality_check: QualityCheckConfig::default(),
+            verify: VerifyConfig::default(),
+            crf_search: CrfSearchConfig::default(),
+            savings_prediction: SavingsPredictionConfig::default(),
+            crf_tables: CrfTables::default(),
+            preset_tables: PresetTables::default(),
+            bitrate_adjustment: BitrateAdjustment::default(),
+            grain_detection: GrainDetectionConfig::default(),
+            crop_detection: CropDetectionConfig::default(),
+            deinterlace: DeinterlaceConfig::default(),
+            profiles: BTreeMap::new(),
+            profile_rules: Vec::new(),
         }
     }
 }
 
+impl DaemonConfig {
+    /// Get the command directory path, deriving it from job_state_dir if not explicitly set
+    pub fn command_dir(&self) -> PathBuf {
+        self.command_dir.clone().unwrap_or_else(|| {
+            self.job_state_dir
+                .parent()
+                .map(|p| p.join("commands"))
+                .unwrap_or_else(|| PathBuf::from("/var/lib/av1d/commands"))
+        })
+    }
+
+    /// Path of the per-file retry ledger, next to the job state directory
+    pub fn retry_ledger_path(&self) -> PathBuf {
+        self.job_state_dir
+            .parent()
+            .map(|p| p.join("retry_ledger.json"))
+            .unwrap_or_else(|| PathBuf::from("/var/lib/av1d/retry_ledger.json"))
+    }
+
+    /// Path of the scan cache, next to the job state directory
+    pub fn scan_cache_path(&self) -> PathBuf {
+        self.job_state_dir
+            .parent()
+            .map(|p| p.join("scan_cache.json"))
+            .unwrap_or_else(|| PathBuf::from("/var/lib/av1d/scan_cache.json"))
+    }
+}
+
 pub fn load_config(path: Option<&std::path::Path>) -> Result<DaemonConfig> {
     let config = if let Some(config_path) = path {
         if config_path.exists() {
@@ -87,12 +784,192 @@ pub fn validate_config(config: &DaemonConfig) -> Result<()> {
         anyhow::bail!("max_concurrent_jobs must be at least 1");
     }
 
+    if config.command_poll_interval_secs == 0 {
+        anyhow::bail!("command_poll_interval_secs must be at least 1");
+    }
+
+    if config.max_attempts == 0 {
+        anyhow::bail!("max_attempts must be at least 1");
+    }
+
+    if config.retry_max_delay_secs < config.retry_base_delay_secs {
+        anyhow::bail!("retry_max_delay_secs must not be less than retry_base_delay_secs");
+    }
+
+    if !(0.0..=100.0).contains(&config.early_abort_min_percent) {
+        anyhow::bail!("early_abort_min_percent must be between 0 and 100");
+    }
+
+    if config.early_abort_margin < 1.0 {
+        anyhow::bail!("early_abort_margin must be at least 1.0");
+    }
+
+    if config.video_extensions.is_empty() {
+        anyhow::bail!("video_extensions cannot be empty");
+    }
+
+    crate::scan_filter::ScanFilter::new(config)?;
+
+    if config.watch_debounce_secs == 0 || config.watch_full_rescan_secs == 0 {
+        anyhow::bail!("watch_debounce_secs and watch_full_rescan_secs must be at least 1");
+    }
+
+    let quality = &config.quality_check;
+    if quality.samples == 0 || quality.sample_secs == 0 {
+        anyhow::bail!("quality_check.samples and quality_check.sample_secs must be at least 1");
+    }
+
+    if !(0.0..=1.0).contains(&quality.min_ssim) {
+        anyhow::bail!("quality_check.min_ssim must be between 0.0 and 1.0");
+    }
+
+    if quality.min_psnr < 0.0 {
+        anyhow::bail!("quality_check.min_psnr must not be negative");
+    }
+
+    if !(0.0..=100.0).contains(&quality.min_vmaf) {
+        anyhow::bail!("quality_check.min_vmaf must be between 0 and 100");
+    }
+
+    let search = &config.crf_search;
+    if search.min_crf > search.max_crf || search.max_crf > 63 {
+        anyhow::bail!("crf_search needs min_crf <= max_crf <= 63");
+    }
+
+    if search.samples == 0 || search.sample_secs == 0 {
+        anyhow::bail!("crf_search.samples and crf_search.sample_secs must be at least 1");
+    }
+
+    let target_range = match search.metric {
+        QualityMetric::Ssim => 0.0..=1.0,
+        QualityMetric::Psnr => 0.0..=100.0,
+        QualityMetric::Vmaf => 0.0..=100.0,
+    };
+    if !target_range.contains(&search.target) {
+        anyhow::bail!(
+            "crf_search.target {} is out of range for {:?}",
+            search.target,
+            search.metric
+        );
+    }
+
+    let encoders = [
+        ("svt", AvailableEncoder::SvtAv1),
+        ("aom", AvailableEncoder::LibaomAv1),
+        ("rav1e", AvailableEncoder::Librav1e),
+    ];
+    for (name, encoder) in encoders {
+        for (tier, quality_tier) in [
+            ("high", QualityTier::High),
+            ("very_high", QualityTier::VeryHigh),
+        ] {
+            let crf = config.crf_tables.get(encoder).get(quality_tier);
+            if crf.is_empty() {
+                anyhow::bail!("crf_tables.{}.{} cannot be empty", name, tier);
+            }
+            crate::profiles::validate_table(
+                crf,
+                crate::profiles::max_crf(encoder),
+                &format!("crf_tables.{}.{}", name, tier),
+            )?;
+            let max_preset = match encoder {
+                AvailableEncoder::SvtAv1 => 13,
+                AvailableEncoder::LibaomAv1 => 8,
+                AvailableEncoder::Librav1e => 10,
+            };
+            crate::profiles::validate_table(
+                config.preset_tables.get(encoder).get(quality_tier),
+                max_preset,
+                &format!("preset_tables.{}.{}", name, tier),
+            )?;
+        }
+    }
+
+    let adjustment = &config.bitrate_adjustment;
+    if adjustment.low_bitrate >= adjustment.high_bitrate {
+        anyhow::bail!("bitrate_adjustment.low_bitrate must be below high_bitrate");
+    }
+
+    crate::profiles::validate_profiles(config)?;
+
+    let prediction = &config.savings_prediction;
+    if prediction.samples == 0 || prediction.sample_secs == 0 {
+        anyhow::bail!(
+            "savings_prediction.samples and savings_prediction.sample_secs must be at least 1"
+        );
+    }
+
+    if prediction.margin < 1.0 {
+        anyhow::bail!("savings_prediction.margin must be at least 1.0");
+    }
+
+    let grain = &config.grain_detection;
+    if grain.samples == 0 || grain.sample_secs == 0 {
+        anyhow::bail!("grain_detection.samples and grain_detection.sample_secs must be at least 1");
+    }
+    if grain.min_noise < 0.0 || grain.strength_per_noise <= 0.0 {
+        anyhow::bail!(
+            "grain_detection.min_noise must not be negative and strength_per_noise must be positive"
+        );
+    }
+    if !(1..=50).contains(&grain.max_film_grain) {
+        anyhow::bail!("grain_detection.max_film_grain must be between 1 and 50");
+    }
+
+    let crop = &config.crop_detection;
+    if crop.samples == 0 || crop.sample_secs == 0 {
+        anyhow::bail!("crop_detection.samples and crop_detection.sample_secs must be at least 1");
+    }
+    if !(0.5..=1.0).contains(&crop.min_agreement) {
+        anyhow::bail!("crop_detection.min_agreement must be between 0.5 and 1.0");
+    }
+
+    let audio = &config.audio_policy;
+    if audio.enabled && audio.codecs.is_empty() {
+        anyhow::bail!("audio_policy.codecs cannot be empty when audio_policy is enabled");
+    }
+    let bitrates = &audio.bitrates;
+    let max_bitrate = match audio.codec {
+        AudioCodec::Opus => 512,
+        AudioCodec::Eac3 => 6144,
+    };
+    for (name, bitrate) in [
+        ("mono", bitrates.mono),
+        ("stereo", bitrates.stereo),
+        ("surround", bitrates.surround),
+        ("surround_wide", bitrates.surround_wide),
+    ] {
+        if !(6..=max_bitrate).contains(&bitrate) {
+            anyhow::bail!(
+                "audio_policy.bitrates.{} must be between 6 and {} kbps for {:?}",
+                name,
+                max_bitrate,
+                audio.codec
+            );
+        }
+    }
+
+    let deinterlace = &config.deinterlace;
+    if deinterlace.samples == 0 || deinterlace.sample_secs == 0 {
+        anyhow::bail!("deinterlace.samples and deinterlace.sample_secs must be at least 1");
+    }
+    for (name, ratio) in [
+        ("min_interlaced_ratio", deinterlace.min_interlaced_ratio),
+        ("min_repeated_ratio", deinterlace.min_repeated_ratio),
+    ] {
+        if !(ratio > 0.0 && ratio <= 1.0) {
+            anyhow::bail!("deinterlace.{} must be above 0 and at most 1", name);
+        }
+    }
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::classify::SourceType;
+    use crate::profiles::ResolutionStep;
     use proptest::prelude::*;
     use std::io::Write;
     use tempfile::NamedTempFile;
@@ -110,7 +987,433 @@ mod tests {
         prop_oneof![Just(QualityTier::High), Just(QualityTier::VeryHigh),]
     }
 
-    fn arb_daemon_config() -> impl Strategy<Value = DaemonConfig> {
+    fn arb_interrupted_job_policy() -> impl Strategy<Value = InterruptedJobPolicy> {
+        prop_oneof![
+            Just(InterruptedJobPolicy::Requeue),
+            Just(InterruptedJobPolicy::RequeueQueued),
+            Just(InterruptedJobPolicy::Fail),
+        ]
+    }
+
+    fn arb_output_container_policy() -> impl Strategy<Value = OutputContainerPolicy> {
+        prop_oneof![
+            Just(OutputContainerPolicy::KeepSource),
+            Just(OutputContainerPolicy::Mkv),
+        ]
+    }
+
+    fn arb_track_policy() -> impl Strategy<Value = TrackPolicy> {
+        let languages = || prop::collection::vec("[a-z]{2,3}", 0..3);
+        (
+            languages(),
+            languages(),
+            languages(),
+            languages(),
+            any::<bool>(),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(
+                    audio_keep_languages,
+                    audio_drop_languages,
+                    subtitle_keep_languages,
+                    subtitle_drop_languages,
+                    drop_commentary,
+                    drop_undetermined,
+                )| TrackPolicy {
+                    audio_keep_languages,
+                    audio_drop_languages,
+                    subtitle_keep_languages,
+                    subtitle_drop_languages,
+                    drop_commentary,
+                    drop_undetermined,
+                },
+            )
+    }
+
+    fn arb_scan_rules() -> impl Strategy<Value = ScanRules> {
+        let globs = || {
+            prop::collection::vec(
+                prop_oneof![
+                    Just("**/Extras/**".to_string()),
+                    Just("*trailer*".to_string()),
+                    Just("TV/**".to_string()),
+                ],
+                0..3,
+            )
+        };
+        let regexes = || prop::collection::vec("[a-z]{1,8}", 0..3);
+        (
+            prop::option::of(any::<String>().prop_map(PathBuf::from)),
+            globs(),
+            globs(),
+            regexes(),
+            regexes(),
+            prop::option::of(1_usize..10_usize),
+        )
+            .prop_map(
+                |(root, include, exclude, include_regex, exclude_regex, max_depth)| ScanRules {
+                    root,
+                    include,
+                    exclude,
+                    include_regex,
+                    exclude_regex,
+                    max_depth,
+                },
+            )
+    }
+
+    fn arb_quality_check() -> impl Strategy<Value = QualityCheckConfig> {
+        (
+            any::<bool>(),
+            1_u32..20_u32,
+            1_u32..60_u32,
+            0.0_f64..1.0_f64,
+            0.0_f64..60.0_f64,
+            0.0_f64..100.0_f64,
+        )
+            .prop_map(
+                |(enabled, samples, sample_secs, min_ssim, min_psnr, min_vmaf)| {
+                    QualityCheckConfig {
+                        enabled,
+                        samples,
+                        sample_secs,
+                        min_ssim,
+                        min_psnr,
+                        min_vmaf,
+                    }
+                },
+            )
+    }
+
+    fn arb_crf_search() -> impl Strategy<Value = CrfSearchConfig> {
+        (
+            any::<bool>(),
+            prop_oneof![
+                Just(QualityMetric::Ssim),
+                Just(QualityMetric::Psnr),
+                Just(QualityMetric::Vmaf),
+            ],
+            0.0_f64..1.0_f64,
+            (0_u8..30_u8, 0_u8..30_u8),
+            1_u32..10_u32,
+            1_u32..60_u32,
+        )
+            .prop_map(
+                |(enabled, metric, target, (min_crf, extra_crf), samples, sample_secs)| {
+                    CrfSearchConfig {
+                        enabled,
+                        metric,
+                        target,
+                        min_crf,
+                        max_crf: min_crf + extra_crf,
+                        samples,
+                        sample_secs,
+                    }
+                },
+            )
+    }
+
+    fn arb_savings_prediction() -> impl Strategy<Value = SavingsPredictionConfig> {
+        (
+            any::<bool>(),
+            1_u32..10_u32,
+            1_u32..60_u32,
+            1.0_f64..2.0_f64,
+        )
+            .prop_map(
+                |(enabled, samples, sample_secs, margin)| SavingsPredictionConfig {
+                    enabled,
+                    samples,
+                    sample_secs,
+                    margin,
+                },
+            )
+    }
+
+    fn arb_grain_detection() -> impl Strategy<Value = GrainDetectionConfig> {
+        (
+            any::<bool>(),
+            1_u32..10_u32,
+            1_u32..10_u32,
+            0.0_f64..5.0_f64,
+            0.5_f64..10.0_f64,
+            1_u8..=50_u8,
+            any::<bool>(),
+        )
+            .prop_map(
+                |(
+                    enabled,
+                    samples,
+                    sample_secs,
+                    min_noise,
+                    strength_per_noise,
+                    max_film_grain,
+                    denoise,
+                )| GrainDetectionConfig {
+                    enabled,
+                    samples,
+                    sample_secs,
+                    min_noise,
+                    strength_per_noise,
+                    max_film_grain,
+                    denoise,
+                },
+            )
+    }
+
+    fn arb_crop_detection() -> impl Strategy<Value = CropDetectionConfig> {
+        (
+            any::<bool>(),
+            1_u32..20_u32,
+            1_u32..30_u32,
+            any::<u8>(),
+            0.5_f64..=1.0_f64,
+        )
+            .prop_map(|(enabled, samples, sample_secs, limit, min_agreement)| {
+                CropDetectionConfig {
+                    enabled,
+                    samples,
+                    sample_secs,
+                    limit,
+                    min_agreement,
+                }
+            })
+    }
+
+    fn arb_audio_policy() -> impl Strategy<Value = AudioPolicy> {
+        (
+            any::<bool>(),
+            prop::collection::vec(
+                prop_oneof![Just("truehd"), Just("dts"), Just("pcm_s24le"), Just("flac")],
+                1..4,
+            ),
+            prop_oneof![Just(AudioCodec::Opus), Just(AudioCodec::Eac3)],
+            (
+                6_u32..512_u32,
+                6_u32..512_u32,
+                6_u32..512_u32,
+                6_u32..512_u32,
+            ),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(
+                    enabled,
+                    codecs,
+                    codec,
+                    (mono, stereo, surround, surround_wide),
+                    keep_original,
+                )| {
+                    AudioPolicy {
+                        enabled,
+                        codecs: codecs.into_iter().map(String::from).collect(),
+                        codec,
+                        bitrates: AudioBitrates {
+                            mono,
+                            stereo,
+                            surround,
+                            surround_wide,
+                        },
+                        keep_original,
+                    }
+                },
+            )
+    }
+
+    fn arb_subtitle_policy() -> impl Strategy<Value = SubtitlePolicy> {
+        prop_oneof![
+            Just(SubtitleExtraction::None),
+            Just(SubtitleExtraction::Dropped),
+            Just(SubtitleExtraction::All),
+        ]
+        .prop_map(|extract| SubtitlePolicy { extract })
+    }
+
+    fn arb_verify() -> impl Strategy<Value = VerifyConfig> {
+        any::<bool>().prop_map(|enabled| VerifyConfig { enabled })
+    }
+
+    fn arb_deinterlace() -> impl Strategy<Value = DeinterlaceConfig> {
+        (
+            any::<bool>(),
+            any::<bool>(),
+            1_u32..10_u32,
+            1_u32..60_u32,
+            0.01_f64..=1.0_f64,
+            0.01_f64..=1.0_f64,
+        )
+            .prop_map(
+                |(
+                    enabled,
+                    detect,
+                    samples,
+                    sample_secs,
+                    min_interlaced_ratio,
+                    min_repeated_ratio,
+                )| DeinterlaceConfig {
+                    enabled,
+                    detect,
+                    samples,
+                    sample_secs,
+                    min_interlaced_ratio,
+                    min_repeated_ratio,
+                },
+            )
+    }
+
+    fn arb_resolution_table(max: u8) -> impl Strategy<Value = Vec<ResolutionStep>> {
+        prop::collection::btree_set(0_i32..4320_i32, 0..4).prop_flat_map(move |heights| {
+            let heights: Vec<i32> = heights.into_iter().rev().collect();
+            prop::collection::vec(0..=max, heights.len()).prop_map(move |values| {
+                heights
+                    .iter()
+                    .zip(values)
+                    .map(|(&min_height, value)| ResolutionStep { min_height, value })
+                    .collect()
+            })
+        })
+    }
+
+    fn arb_tier_tables(max: u8, min_len: usize) -> impl Strategy<Value = TierTables> {
+        (arb_resolution_table(max), arb_resolution_table(max))
+            .prop_filter("CRF tables cannot be empty", move |(high, very_high)| {
+                high.len() >= min_len && very_high.len() >= min_len
+            })
+            .prop_map(|(high, very_high)| TierTables { high, very_high })
+    }
+
+    fn arb_encoder_tables() -> impl Strategy<Value = (CrfTables, PresetTables, BitrateAdjustment)> {
+        (
+            (
+                arb_tier_tables(63, 1),
+                arb_tier_tables(63, 1),
+                arb_tier_tables(63, 1),
+            ),
+            (
+                arb_tier_tables(13, 0),
+                arb_tier_tables(8, 0),
+                arb_tier_tables(10, 0),
+            ),
+            (
+                any::<bool>(),
+                0_u64..10_000_000_u64,
+                0_u8..10_u8,
+                10_000_000_u64..100_000_000_u64,
+                0_u8..10_u8,
+            ),
+        )
+            .prop_map(
+                |(
+                    (crf_svt, crf_aom, crf_rav1e),
+                    (preset_svt, preset_aom, preset_rav1e),
+                    (
+                        enabled,
+                        low_bitrate,
+                        low_bitrate_crf_offset,
+                        high_bitrate,
+                        high_bitrate_crf_offset,
+                    ),
+                )| {
+                    (
+                        CrfTables {
+                            svt: crf_svt,
+                            aom: crf_aom,
+                            rav1e: crf_rav1e,
+                        },
+                        PresetTables {
+                            svt: preset_svt,
+                            aom: preset_aom,
+                            rav1e: preset_rav1e,
+                        },
+                        BitrateAdjustment {
+                            enabled,
+                            low_bitrate,
+                            low_bitrate_crf_offset,
+                            high_bitrate,
+                            high_bitrate_crf_offset,
+                        },
+                    )
+                },
+            )
+    }
+
+    fn arb_encoding_profile() -> impl Strategy<Value = EncodingProfile> {
+        (
+            prop::option::of(arb_encoder_preference()),
+            arb_resolution_table(63),
+            arb_resolution_table(13),
+            prop::collection::vec("[a-z-]{1,8}=[0-9]{1,2}", 0..3),
+            prop::option::of(0_u8..=50_u8),
+            prop::option::of(1_u32..600_u32),
+            prop::option::of(any::<bool>()),
+        )
+            .prop_map(
+                |(encoder, crf, preset, svt_params, film_grain, keyint, crop)| EncodingProfile {
+                    encoder,
+                    crf,
+                    preset,
+                    svt_params,
+                    film_grain,
+                    keyint,
+                    crop,
+                },
+            )
+    }
+
+    fn arb_profiles() -> impl Strategy<Value = (BTreeMap<String, EncodingProfile>, Vec<ProfileRule>)>
+    {
+        prop::collection::btree_map("[a-z]{1,8}", arb_encoding_profile(), 0..3).prop_flat_map(
+            |profiles| {
+                let names: Vec<String> = profiles.keys().cloned().collect();
+                let rules = if names.is_empty() {
+                    Just(Vec::new()).boxed()
+                } else {
+                    prop::collection::vec(
+                        (
+                            prop::sample::select(names),
+                            prop::option::of(prop_oneof![
+                                Just(SourceType::WebLike),
+                                Just(SourceType::DiscLike),
+                            ]),
+                            prop::option::of(0_i32..4320_i32),
+                            prop::collection::vec("[a-z0-9]{1,8}", 0..3),
+                            prop::option::of(0_u64..100_000_000_u64),
+                            prop::option::of(any::<bool>()),
+                            prop::collection::vec(Just("**/Anime/**".to_string()), 0..2),
+                        )
+                            .prop_map(
+                                |(
+                                    profile,
+                                    source_type,
+                                    min_height,
+                                    codecs,
+                                    max_bitrate,
+                                    hdr,
+                                    paths,
+                                )| {
+                                    ProfileRule {
+                                        profile,
+                                        source_type,
+                                        min_height,
+                                        codecs,
+                                        max_bitrate,
+                                        hdr,
+                                        paths,
+                                        ..Default::default()
+                                    }
+                                },
+                            ),
+                        0..3,
+                    )
+                    .boxed()
+                };
+                (Just(profiles), rules)
+            },
+        )
+    }
+
+    fn arb_core_config() -> impl Strategy<Value = DaemonConfig> {
         (
             prop::collection::vec(any::<String>().prop_map(PathBuf::from), 1..5),
             1_000_000_u64..100_000_000_000_u64,
@@ -123,6 +1426,10 @@ mod tests {
             arb_quality_tier(),
             any::<bool>(),
             any::<bool>(),
+            (
+                prop::collection::vec("[a-z0-9]{1,4}", 1..5),
+                prop::collection::vec(arb_scan_rules(), 0..3),
+            ),
         )
             .prop_map(
                 |(
@@ -137,6 +1444,7 @@ mod tests {
                     quality_tier,
                     keep_original,
                     write_why_sidecars,
+                    (video_extensions, scan_rules),
                 )| {
                     DaemonConfig {
                         library_roots,
@@ -150,11 +1458,125 @@ mod tests {
                         quality_tier,
                         keep_original,
                         write_why_sidecars,
+                        video_extensions,
+                        scan_rules,
+                        ..Default::default()
                     }
                 },
             )
     }
 
+    fn arb_daemon_config() -> impl Strategy<Value = DaemonConfig> {
+        (
+            arb_core_config(),
+            prop::option::of(any::<String>().prop_map(PathBuf::from)),
+            1_u64..60_u64,
+            arb_interrupted_job_policy(),
+            1_u32..10_u32,
+            (0_u64..3600_u64, 0_u64..86_400_u64),
+            (
+                any::<bool>(),
+                0.0_f64..100.0_f64,
+                0_u64..3600_u64,
+                1.0_f64..2.0_f64,
+            ),
+            (
+                arb_output_container_policy(),
+                any::<bool>(),
+                1_u64..600_u64,
+                1_u64..86_400_u64,
+                any::<bool>(),
+                (0_u64..3600_u64, 0_u64..60_u64),
+            ),
+            arb_track_policy(),
+            arb_quality_check(),
+            arb_crf_search(),
+            (
+                arb_savings_prediction(),
+                arb_profiles(),
+                arb_encoder_tables(),
+                arb_grain_detection(),
+                arb_crop_detection(),
+                arb_deinterlace(),
+                arb_audio_policy(),
+                arb_subtitle_policy(),
+                arb_verify(),
+            ),
+        )
+            .prop_map(
+                |(
+                    core,
+                    command_dir,
+                    command_poll_interval_secs,
+                    interrupted_job_policy,
+                    max_attempts,
+                    (retry_base_delay_secs, extra_delay),
+                    (
+                        early_abort,
+                        early_abort_min_percent,
+                        early_abort_min_encoded_secs,
+                        early_abort_margin,
+                    ),
+                    (
+                        output_container,
+                        watch_mode,
+                        watch_debounce_secs,
+                        watch_full_rescan_secs,
+                        scan_cache,
+                        (stable_quiet_secs, stable_recheck_secs),
+                    ),
+                    track_policy,
+                    quality_check,
+                    crf_search,
+                    (
+                        savings_prediction,
+                        (profiles, profile_rules),
+                        (crf_tables, preset_tables, bitrate_adjustment),
+                        grain_detection,
+                        crop_detection,
+                        deinterlace,
+                        audio_policy,
+                        subtitle_policy,
+                        verify,
+                    ),
+                )| DaemonConfig {
+                    command_dir,
+                    command_poll_interval_secs,
+                    interrupted_job_policy,
+                    max_attempts,
+                    retry_base_delay_secs,
+                    retry_max_delay_secs: retry_base_delay_secs + extra_delay,
+                    early_abort,
+                    early_abort_min_percent,
+                    early_abort_min_encoded_secs,
+                    early_abort_margin,
+                    output_container,
+                    watch_mode,
+                    watch_debounce_secs,
+                    watch_full_rescan_secs,
+                    scan_cache,
+                    stable_quiet_secs,
+                    stable_recheck_secs,
+                    track_policy,
+                    audio_policy,
+                    subtitle_policy,
+                    quality_check,
+                    verify,
+                    crf_search,
+                    savings_prediction,
+                    crf_tables,
+                    preset_tables,
+                    bitrate_adjustment,
+                    grain_detection,
+                    crop_detection,
+                    deinterlace,
+                    profiles,
+                    profile_rules,
+                    ..core
+                },
+            )
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(100))]
 
@@ -298,6 +1720,21 @@ min_bytes = 5000000000
             .contains("max_concurrent_jobs"));
     }
 
+    #[test]
+    fn test_command_dir_derived_from_job_state_dir() {
+        let config = DaemonConfig {
+            job_state_dir: PathBuf::from("/srv/av1d/jobs"),
+            ..Default::default()
+        };
+        assert_eq!(config.command_dir(), PathBuf::from("/srv/av1d/commands"));
+
+        let config = DaemonConfig {
+            command_dir: Some(PathBuf::from("/custom/commands")),
+            ..Default::default()
+        };
+        assert_eq!(config.command_dir(), PathBuf::from("/custom/commands"));
+    }
+
     #[test]
     fn test_encoder_preference_serialization() {
         // Test serialization through a wrapper struct
@@ -325,6 +1762,61 @@ min_bytes = 5000000000
         assert!(rav1e.contains("pref = \"rav1e\""));
     }
 
+    #[test]
+    fn test_interrupted_job_policy_parsing() {
+        let config: DaemonConfig =
+            toml::from_str(r#"interrupted_job_policy = "requeue_queued""#).unwrap();
+        assert_eq!(
+            config.interrupted_job_policy,
+            InterruptedJobPolicy::RequeueQueued
+        );
+        assert_eq!(
+            DaemonConfig::default().interrupted_job_policy,
+            InterruptedJobPolicy::Requeue
+        );
+    }
+
+    #[test]
+    fn test_track_policy_section_parsing() {
+        let config: DaemonConfig = toml::from_str(
+            r#"
+library_roots = ["/media"]
+
+[track_policy]
+audio_drop_languages = ["ru", "rus"]
+drop_commentary = true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.track_policy.audio_drop_languages, vec!["ru", "rus"]);
+        assert!(config.track_policy.drop_commentary);
+        assert!(config.track_policy.audio_keep_languages.is_empty());
+        assert!(!config.track_policy.drop_undetermined);
+    }
+
+    #[test]
+    fn test_quality_check_section_validation() {
+        let mut config: DaemonConfig = toml::from_str(
+            r#"
+library_roots = ["/media"]
+
+[quality_check]
+enabled = true
+min_vmaf = 0.0
+"#,
+        )
+        .unwrap();
+
+        assert!(config.quality_check.enabled);
+        assert_eq!(config.quality_check.samples, 5);
+        assert_eq!(config.quality_check.min_ssim, 0.95);
+        assert!(validate_config(&config).is_ok());
+
+        config.quality_check.min_ssim = 1.5;
+        assert!(validate_config(&config).is_err());
+    }
+
     #[test]
     fn test_quality_tier_serialization() {
         // Test serialization through a wrapper struct
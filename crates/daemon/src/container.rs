@@ -0,0 +1,133 @@
+use crate::config::OutputContainerPolicy;
+use crate::probe::ProbeResult;
+use std::path::{Path, PathBuf};
+
+/// Container the encoder writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputContainer {
+    Matroska,
+    Mp4,
+    WebM,
+}
+
+impl OutputContainer {
+    /// Container ffmpeg picks for an output path from its extension
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("mp4" | "m4v") => OutputContainer::Mp4,
+            Some("webm") => OutputContainer::WebM,
+            _ => OutputContainer::Matroska,
+        }
+    }
+
+    /// Extension used for the temp output, which also selects ffmpeg's muxer
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputContainer::Matroska => "mkv",
+            OutputContainer::Mp4 => "mp4",
+            OutputContainer::WebM => "webm",
+        }
+    }
+}
+
+/// Where the encoded file goes and in which container
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerPlan {
+    pub container: OutputContainer,
+    /// Final path of the encoded file; differs from the source when the file is renamed
+    pub final_path: PathBuf,
+    /// Why the source container could not be kept, if it was switched
+    pub switch_reason: Option<String>,
+}
+
+impl ContainerPlan {
+    pub fn is_renamed(&self, source: &Path) -> bool {
+        self.final_path != source
+    }
+}
+
+const MP4_AUDIO_CODECS: &[&str] = &[
+    "aac", "ac3", "eac3", "mp3", "mp2", "opus", "flac", "alac", "dts",
+];
+const MP4_SUBTITLE_CODECS: &[&str] = &["mov_text"];
+const WEBM_AUDIO_CODECS: &[&str] = &["opus", "vorbis"];
+const WEBM_SUBTITLE_CODECS: &[&str] = &["webvtt"];
+
+/// Decide the output container for `source`.
+///
+/// Matroska sources stay Matroska. MP4 (`.mp4`, `.m4v`) and WebM sources keep
+/// their container when every audio and subtitle stream can be stored there
+/// next to AV1 video; anything else is written as Matroska and renamed to `.mkv`.
+pub fn plan_output_container(
+    source: &Path,
+    probe: &ProbeResult,
+    policy: OutputContainerPolicy,
+) -> ContainerPlan {
+    let extension = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    let keep = |container| ContainerPlan {
+        container,
+        final_path: source.to_path_buf(),
+        switch_reason: None,
+    };
+    let switch = |reason: String| ContainerPlan {
+        container: OutputContainer::Matroska,
+        final_path: source.with_extension("mkv"),
+        switch_reason: Some(reason),
+    };
+
+    if extension == "mkv" {
+        return keep(OutputContainer::Matroska);
+    }
+
+    let candidate = match extension.as_str() {
+        "mp4" | "m4v" => Some((OutputContainer::Mp4, MP4_AUDIO_CODECS, MP4_SUBTITLE_CODECS)),
+        "webm" => Some((
+            OutputContainer::WebM,
+            WEBM_AUDIO_CODECS,
+            WEBM_SUBTITLE_CODECS,
+        )),
+        _ => None,
+    };
+
+    let Some((container, audio_codecs, subtitle_codecs)) = candidate else {
+        return switch(format!(".{} cannot hold AV1 video", extension));
+    };
+
+    if policy == OutputContainerPolicy::Mkv {
+        return switch("output_container policy is mkv".to_string());
+    }
+
+    let incompatible: Vec<String> = probe
+        .audio_streams
+        .iter()
+        .filter(|s| !audio_codecs.contains(&s.codec_name.as_str()))
+        .map(|s| format!("audio {}", s.codec_name))
+        .chain(
+            probe
+                .subtitle_streams
+                .iter()
+                .filter(|s| !subtitle_codecs.contains(&s.codec_name.as_str()))
+                .map(|s| format!("subtitle {}", s.codec_name)),
+        )
+        .collect();
+
+    if incompatible.is_empty() {
+        keep(container)
+    } else {
+        switch(format!(
+            ".{} cannot hold {}",
+            extension,
+            incompatible.join(", ")
+        ))
+    }
+}
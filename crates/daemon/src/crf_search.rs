@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::config::{CrfSearchConfig, QualityMetric};
+use crate::encode::common::SourceFilters;
+use crate::encode::{encode_sample, video_encoder_args, VideoSettings};
+use crate::jobs::Job;
+use crate::probe::ProbeResult;
+use crate::quality::{compare_segment, sample_offsets, vmaf_available};
+
+/// What the CRF search tried and what it picked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrfSearchTrace {
+    pub metric: QualityMetric,
+    pub target: f64,
+    /// Candidates in the order they were tried
+    pub attempts: Vec<CrfAttempt>,
+    pub chosen_crf: u8,
+    /// False when even `min_crf` missed the target
+    pub met_target: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrfAttempt {
+    pub crf: u8,
+    /// Mean score over the samples
+    pub score: f64,
+}
+
+/// Binary search for the highest CRF in `min_crf..=max_crf` whose score
+/// reaches `target`, assuming quality falls as CRF rises.
+///
+/// `score` is called once per candidate; its results are returned in the
+/// order they were requested.
+pub async fn binary_search_crf<F, Fut>(
+    min_crf: u8,
+    max_crf: u8,
+    target: f64,
+    mut score: F,
+) -> Result<(Option<u8>, Vec<CrfAttempt>)>
+where
+    F: FnMut(u8) -> Fut,
+    Fut: std::future::Future<Output = Result<f64>>,
+{
+    let mut attempts = Vec::new();
+    let mut best = None;
+    let (mut low, mut high) = (min_crf as i32, max_crf as i32);
+
+    while low <= high {
+        let crf = ((low + high) / 2) as u8;
+        let value = score(crf).await?;
+        attempts.push(CrfAttempt { crf, score: value });
+
+        if value >= target {
+            best = Some(crf);
+            low = crf as i32 + 1;
+        } else {
+            high = crf as i32 - 1;
+        }
+    }
+
+    Ok((best, attempts))
+}
+
+/// Encode samples of the source at candidate CRFs and pick the highest CRF
+/// that meets the configured target.
+///
+/// Sample encodes are written to `work_dir` as `<job id>.crf<N>.<i>.mkv` and
+/// removed after scoring.
+pub async fn search_crf(
+    ffmpeg: &str,
+    job: &Job,
+    probe: &ProbeResult,
+    settings: &VideoSettings,
+    config: &CrfSearchConfig,
+    work_dir: &Path,
+) -> Result<CrfSearchTrace> {
+    let video = probe
+        .main_video_stream()
+        .context("Source has no video stream to sample")?;
+    let duration = probe
+        .format
+        .duration
+        .context("Source duration is unknown")?;
+    let use_vmaf = config.metric == QualityMetric::Vmaf;
+    if use_vmaf && !vmaf_available(ffmpeg).await {
+        anyhow::bail!("CRF search by VMAF needs an ffmpeg built with libvmaf");
+    }
+
+    let sample_secs = config.sample_secs as f64;
+    let offsets = sample_offsets(duration, config.samples, sample_secs);
+    let height = job.video_height.unwrap_or(video.height);
+    let source_filters = &SourceFilters::for_job(job);
+
+    let score_crf = |crf: u8| {
+        let offsets = offsets.clone();
+        async move {
+            let mut total = 0.0;
+            for (i, offset) in offsets.iter().enumerate() {
+                let sample_path = work_dir.join(format!("{}.crf{}.{}.mkv", job.id, crf, i));
+
+                encode_sample(
+                    ffmpeg,
+                    job,
+                    probe,
+                    &video_encoder_args(settings, probe, height, crf),
+                    (*offset, sample_secs),
+                    &sample_path,
+                )
+                .await
+                .with_context(|| format!("CRF search failed at CRF {}", crf))?;
+
+                let result = compare_segment(
+                    ffmpeg,
+                    (&sample_path, 0.0),
+                    (&job.source_path, *offset),
+                    sample_secs,
+                    video,
+                    source_filters,
+                    use_vmaf,
+                )
+                .await;
+                let _ = std::fs::remove_file(&sample_path);
+
+                let sample = result?;
+                total += sample
+                    .get(config.metric)
+                    .context("Metric missing from sample scores")?;
+            }
+
+            let mean = total / offsets.len() as f64;
+            debug!("Job {}: CRF {} scored {:.4}", job.id, crf, mean);
+            Ok(mean)
+        }
+    };
+
+    let (best, attempts) =
+        binary_search_crf(config.min_crf, config.max_crf, config.target, score_crf).await?;
+
+    let trace = CrfSearchTrace {
+        metric: config.metric,
+        target: config.target,
+        attempts,
+        chosen_crf: best.unwrap_or(config.min_crf),
+        met_target: best.is_some(),
+    };
+    info!(
+        "Job {}: CRF search chose {} ({:?} target {}, {} candidates tried)",
+        job.id,
+        trace.chosen_crf,
+        trace.metric,
+        trace.target,
+        trace.attempts.len()
+    );
+
+    Ok(trace)
+}
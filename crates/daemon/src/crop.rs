@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::config::CropDetectionConfig;
+use crate::probe::ProbeResult;
+use crate::quality::sample_offsets;
+
+/// Pixels two sample crops may differ by on each edge and still agree
+pub const CROP_TOLERANCE: i32 = 4;
+
+/// Area of the frame kept by a crop, in source pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub width: i32,
+    pub height: i32,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl CropRect {
+    /// ffmpeg `crop` filter for this area
+    pub fn filter(&self) -> String {
+        format!("crop={}:{}:{}:{}", self.width, self.height, self.x, self.y)
+    }
+
+    fn right(&self) -> i32 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> i32 {
+        self.y + self.height
+    }
+
+    /// Whether every edge is within [`CROP_TOLERANCE`] of `other`'s
+    pub fn agrees_with(&self, other: &CropRect) -> bool {
+        (self.x - other.x).abs() <= CROP_TOLERANCE
+            && (self.y - other.y).abs() <= CROP_TOLERANCE
+            && (self.right() - other.right()).abs() <= CROP_TOLERANCE
+            && (self.bottom() - other.bottom()).abs() <= CROP_TOLERANCE
+    }
+
+    /// Whether no edge lies more than [`CROP_TOLERANCE`] outside `other`
+    pub fn fits_within(&self, other: &CropRect) -> bool {
+        self.x >= other.x - CROP_TOLERANCE
+            && self.y >= other.y - CROP_TOLERANCE
+            && self.right() <= other.right() + CROP_TOLERANCE
+            && self.bottom() <= other.bottom() + CROP_TOLERANCE
+    }
+}
+
+/// Crops found in the sampled segments and the one applied to the encode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CropDetection {
+    pub samples: u32,
+    /// Samples whose crop matched the chosen one
+    pub agreeing: u32,
+    /// Crop applied to the encode; `None` when there are no black bars or the samples disagree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crop: Option<CropRect>,
+}
+
+/// The last crop ffmpeg's `cropdetect` reported, which covers the whole segment.
+///
+/// Segments that are entirely black report an empty or negative area and give `None`.
+pub fn parse_cropdetect(stderr: &str) -> Option<CropRect> {
+    let crop_re = Regex::new(r"crop=(-?\d+):(-?\d+):(-?\d+):(-?\d+)").unwrap();
+    let caps = crop_re.captures_iter(stderr).last()?;
+    let value = |i: usize| caps[i].parse::<i32>().ok();
+    let crop = CropRect {
+        width: value(1)?,
+        height: value(2)?,
+        x: value(3)?,
+        y: value(4)?,
+    };
+    (crop.width > 0 && crop.height > 0).then_some(crop)
+}
+
+/// Agree on one crop for a `width` x `height` source from each sample's result.
+///
+/// The crop most samples agree with wins if at least `min_agreement` of the
+/// samples with a result agree on it; the union of the agreeing crops is used
+/// so no picture is cut off. Crops within [`CROP_TOLERANCE`] of the full frame
+/// are not applied, and neither is a crop that would cut into the active area
+/// of any other sample, as in mixed-aspect films. Dark scenes only make a
+/// sample's crop smaller, so they never veto one.
+pub fn agree_on_crop(
+    results: &[Option<CropRect>],
+    width: i32,
+    height: i32,
+    min_agreement: f64,
+) -> CropDetection {
+    let found: Vec<CropRect> = results.iter().flatten().copied().collect();
+    let agreeing_with = |candidate: &CropRect| -> Vec<CropRect> {
+        found
+            .iter()
+            .filter(|other| candidate.agrees_with(other))
+            .copied()
+            .collect()
+    };
+    let best = found
+        .iter()
+        .map(agreeing_with)
+        .max_by_key(|group| group.len())
+        .unwrap_or_default();
+
+    let agreeing = best.len() as u32;
+    let mut detection = CropDetection {
+        samples: results.len() as u32,
+        agreeing,
+        crop: None,
+    };
+    if best.is_empty() || (agreeing as f64) < min_agreement * found.len() as f64 {
+        return detection;
+    }
+
+    let x = best.iter().map(|c| c.x).min().unwrap_or(0).max(0);
+    let y = best.iter().map(|c| c.y).min().unwrap_or(0).max(0);
+    let right = best
+        .iter()
+        .map(|c| c.right())
+        .max()
+        .unwrap_or(width)
+        .min(width);
+    let bottom = best
+        .iter()
+        .map(|c| c.bottom())
+        .max()
+        .unwrap_or(height)
+        .min(height);
+    let crop = CropRect {
+        width: right - x,
+        height: bottom - y,
+        x,
+        y,
+    };
+    let full_frame = CropRect {
+        width,
+        height,
+        x: 0,
+        y: 0,
+    };
+    if !crop.agrees_with(&full_frame) && found.iter().all(|c| c.fits_within(&crop)) {
+        detection.crop = Some(crop);
+    }
+    detection
+}
+
+/// Run `cropdetect` over evenly spaced samples of `source` and agree on a crop
+pub async fn detect_crop(
+    ffmpeg: &str,
+    source: &Path,
+    probe: &ProbeResult,
+    config: &CropDetectionConfig,
+) -> Result<CropDetection> {
+    let video = probe
+        .main_video_stream()
+        .context("Source has no video stream to analyse")?;
+    let duration = probe
+        .format
+        .duration
+        .context("Source duration is unknown")?;
+    // A fractional limit is relative to the pixel format's range, so it suits 10-bit sources too
+    let filter = format!(
+        "[0:{}]cropdetect=limit={:.4}:round=2:reset=0",
+        video.index,
+        config.limit as f64 / 255.0
+    );
+
+    let sample_secs = config.sample_secs as f64;
+    let mut results = Vec::new();
+    for offset in sample_offsets(duration, config.samples, sample_secs) {
+        let result = Command::new(ffmpeg)
+            .arg("-hide_banner")
+            .arg("-nostats")
+            .arg("-ss")
+            .arg(format!("{:.3}", offset))
+            .arg("-t")
+            .arg(format!("{:.3}", sample_secs))
+            .arg("-i")
+            .arg(source)
+            .arg("-lavfi")
+            .arg(&filter)
+            .arg("-f")
+            .arg("null")
+            .arg("-")
+            .output()
+            .await
+            .context("Failed to execute ffmpeg for crop detection")?;
+
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        if !result.status.success() {
+            anyhow::bail!(
+                "Crop detection ffmpeg failed at {:.0}s: {}",
+                offset,
+                stderr.trim()
+            );
+        }
+        results.push(parse_cropdetect(&stderr));
+    }
+
+    Ok(agree_on_crop(
+        &results,
+        video.width,
+        video.height,
+        config.min_agreement,
+    ))
+}
@@ -1,140 +1,536 @@
 use anyhow::Result;
-use std::time::Duration;
+use chrono::Utc;
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
+use crate::audio::plan_audio;
 use crate::classify::classify_source;
+use crate::commands::{run_command_loop, JobInterrupt, RunningJobs};
 use crate::config::DaemonConfig;
-use crate::encode::{build_command, execute_encode, JobExecutor};
-use crate::gates::{check_gates, GateResult};
-use crate::jobs::{create_job, load_all_jobs, save_job, update_job_status, JobStatus};
-use crate::probe::probe_file;
-use crate::replace::atomic_replace;
-use crate::scan::{scan_libraries, CandidateFile};
+use crate::container::plan_output_container;
+use crate::crf_search::search_crf;
+use crate::crop::detect_crop;
+use crate::encode::common::SourceFilters;
+use crate::encode::{
+    build_command, execute_encode_interruptible, video_encoder_args, EncodeAbortedEarly,
+    EncodeInterrupted, JobExecutor, VideoSettings,
+};
+use crate::gates::{check_gates, GateResult, SkipReason};
+use crate::grain::detect_grain;
+use crate::interlace::{detect_interlacing, is_interlaced_field_order, InterlaceDecision};
+use crate::jobs::{
+    create_job, load_all_jobs, load_job, reset_for_requeue, save_job, update_job_status, Job,
+    JobStatus,
+};
+use crate::overrides::{EffectiveConfig, FileConfig, OverrideResolver};
+use crate::predict::predict_savings;
+use crate::probe::{probe_file, ProbeResult, PROBE_SCHEMA_VERSION};
+use crate::profiles::select_profile;
+use crate::quality::{check_quality, measure_quality};
+use crate::replace::{atomic_replace_to, move_sibling_files};
+use crate::retry::{
+    classify_failure, load_ledger, update_ledger, FailureKind, RetryDecision, RetryLedger,
+};
+use crate::scan::{candidate_from_path, scan_libraries_with, CandidateFile};
+use crate::scan_cache::{
+    load_scan_cache, save_scan_cache, FileFingerprint, GateSettings, ScanCache, ScanCacheEntry,
+};
+use crate::scan_filter::ScanFilter;
 use crate::sidecars::{create_skip_marker, has_skip_marker, write_why_file};
-use crate::size_gate::{check_size_gate, SizeGateResult};
-use crate::stable::check_stability;
-use crate::startup::SelectedEncoder;
-use crate::validate::validate_output;
+use crate::size_gate::{check_size_gate, EarlyAbortPolicy, SizeGateResult};
+use crate::stable::find_unstable;
+use crate::startup::{
+    recover_interrupted_jobs, recover_orphaned_jobs, select_encoder, AvailableEncoder,
+};
+use crate::subtitles::{extract_sidecar, plan_sidecars, plan_subtitles, SubtitleAction};
+use crate::tracks::select_tracks;
+use crate::validate::{validate_output, ExpectedStreams, ValidationResult};
+use crate::verify::verify_decode;
+use crate::watch::{Debouncer, LibraryWatcher, WatchEvent};
 
-/// Main daemon loop that orchestrates the entire encoding workflow
-pub async fn run_daemon_loop(config: DaemonConfig, encoder: SelectedEncoder) -> Result<()> {
+/// A probed, gate-checked job waiting for an encode slot
+#[derive(Debug, Clone)]
+pub struct QueuedJob {
+    pub job: Job,
+    pub probe: ProbeResult,
+    /// Daemon config with the file's directory overrides applied
+    pub config: Arc<DaemonConfig>,
+}
+
+/// State shared between the scan producer and the encode workers
+#[derive(Clone)]
+struct PipelineContext {
+    config: Arc<DaemonConfig>,
+    available_encoders: Arc<Vec<AvailableEncoder>>,
+    scan_filter: Arc<ScanFilter>,
+    running: RunningJobs,
+    /// Source paths that are queued or encoding in this process
+    in_flight: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl PipelineContext {
+    fn is_in_flight(&self, path: &PathBuf) -> bool {
+        self.in_flight.lock().unwrap().contains(path)
+    }
+
+    fn mark_in_flight(&self, path: PathBuf) {
+        self.in_flight.lock().unwrap().insert(path);
+    }
+
+    fn clear_in_flight(&self, path: &PathBuf) {
+        self.in_flight.lock().unwrap().remove(path);
+    }
+}
+
+/// Main daemon loop that orchestrates the entire encoding workflow.
+///
+/// Scanning, stability checks, probing and gating run in this task and feed a
+/// bounded queue; an encode dispatcher pulls from the queue and runs up to
+/// `max_concurrent_jobs` encodes at once. Each job picks from
+/// `available_encoders` by its effective `prefer_encoder`.
+pub async fn run_daemon_loop(
+    config: DaemonConfig,
+    available_encoders: Vec<AvailableEncoder>,
+) -> Result<()> {
+    let encoder = select_encoder(&available_encoders, config.prefer_encoder)?;
     info!("Starting daemon main loop");
-    info!("Scan interval: {} seconds", config.scan_interval_secs);
+    if config.watch_mode {
+        info!("Watch mode: on");
+    } else {
+        info!("Scan interval: {} seconds", config.scan_interval_secs);
+    }
     info!("Max concurrent jobs: {}", config.max_concurrent_jobs);
     info!("Selected encoder: {:?}", encoder.encoder);
 
     // Create job executor for managing concurrent encoding jobs
-    let executor = JobExecutor::new(config.max_concurrent_jobs);
+    let executor = Arc::new(JobExecutor::new(config.max_concurrent_jobs));
 
     // Ensure job state directory exists
     std::fs::create_dir_all(&config.job_state_dir)?;
     std::fs::create_dir_all(&config.temp_output_dir)?;
+    std::fs::create_dir_all(config.command_dir())?;
+
+    // Clean up after a previous run that was killed mid-encode
+    match recover_interrupted_jobs(&config) {
+        Ok(recovered) if !recovered.is_empty() => {
+            info!("Recovered {} interrupted job(s)", recovered.len());
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to recover interrupted jobs: {}", e),
+    }
+
+    // Handle TUI commands (requeue, cancel, ...) alongside the scan loop
+    let running = RunningJobs::new();
+    tokio::spawn(run_command_loop(config.clone(), running.clone()));
+
+    let ctx = PipelineContext {
+        scan_filter: Arc::new(ScanFilter::new(&config)?),
+        config: Arc::new(config),
+        available_encoders: Arc::new(available_encoders),
+        running,
+        in_flight: Arc::new(Mutex::new(HashSet::new())),
+    };
+
+    // Keep at most one prepared job per encode slot waiting in the queue
+    let (queue_tx, queue_rx) = mpsc::channel::<QueuedJob>(ctx.config.max_concurrent_jobs);
+    let worker_ctx = ctx.clone();
+    tokio::spawn(run_encode_dispatcher(queue_rx, executor, move |queued| {
+        encode_queued_job(queued, worker_ctx.clone())
+    }));
+
+    // Probe results for files that have not changed since they were last seen
+    let mut scan_cache = ctx.config.scan_cache.then(|| {
+        let settings = GateSettings::from_config(&ctx.config);
+        load_scan_cache(&ctx.config.scan_cache_path(), &settings).unwrap_or_else(|e| {
+            warn!("Failed to load scan cache, starting empty: {}", e);
+            ScanCache::new(settings)
+        })
+    });
+
+    if ctx.config.watch_mode {
+        let filter = ScanFilter::clone(&ctx.scan_filter);
+        match LibraryWatcher::start_with_filter(&ctx.config.library_roots, filter) {
+            Ok(watcher) => run_watch_loop(watcher, &ctx, &queue_tx, &mut scan_cache).await?,
+            Err(e) => warn!("Watch mode unavailable, using interval scans: {}", e),
+        }
+    }
 
     loop {
-        info!("Starting scan cycle");
+        run_scan_cycle(&ctx, &queue_tx, &mut scan_cache).await?;
 
-        // Load existing jobs to avoid duplicates
-        let existing_jobs = load_all_jobs(&config.job_state_dir).unwrap_or_else(|e| {
-            warn!("Failed to load existing jobs: {}", e);
-            Vec::new()
-        });
+        info!(
+            "Scan cycle complete, waiting {} seconds",
+            ctx.config.scan_interval_secs
+        );
+        sleep(Duration::from_secs(ctx.config.scan_interval_secs)).await;
+    }
+}
 
-        // Scan all library roots for video files
-        match scan_libraries(&config.library_roots) {
-            Ok(candidates) => {
-                info!("Found {} candidate files", candidates.len());
-
-                // Process each candidate file
-                for candidate in candidates {
-                    if let Err(e) =
-                        process_candidate(candidate, &config, &encoder, &executor, &existing_jobs)
-                            .await
-                    {
-                        error!("Error processing candidate: {}", e);
-                        // Continue with next file
-                    }
+/// Queue files as the watcher reports them settled, with a slow full rescan as
+/// a safety net for events missed on network filesystems.
+///
+/// Returns if the watcher stops, so the caller can fall back to interval scans.
+async fn run_watch_loop(
+    mut watcher: LibraryWatcher,
+    ctx: &PipelineContext,
+    queue_tx: &mpsc::Sender<QueuedJob>,
+    scan_cache: &mut Option<ScanCache>,
+) -> Result<()> {
+    let config = ctx.config.as_ref();
+    info!(
+        "Watching library roots (debounce {}s, full rescan every {}s)",
+        config.watch_debounce_secs, config.watch_full_rescan_secs
+    );
+    let rescan_interval = Duration::from_secs(config.watch_full_rescan_secs);
+    let mut debouncer = Debouncer::new(Duration::from_secs(config.watch_debounce_secs));
+    let mut next_rescan = Instant::now();
+
+    loop {
+        if Instant::now() >= next_rescan {
+            run_scan_cycle(ctx, queue_tx, scan_cache).await?;
+            next_rescan = Instant::now() + rescan_interval;
+        }
+
+        let wake_at = debouncer
+            .next_deadline()
+            .map_or(next_rescan, |deadline| deadline.min(next_rescan));
+        tokio::select! {
+            event = watcher.next_event() => match event {
+                Some(WatchEvent::File(path)) => {
+                    debug!("Watch event for {:?}", path);
+                    debouncer.touch(path, Instant::now());
+                }
+                Some(WatchEvent::Overflow) => {
+                    warn!("inotify event queue overflowed, rescanning libraries");
+                    next_rescan = Instant::now();
                 }
+                None => {
+                    warn!("Library watcher stopped, falling back to interval scans");
+                    return Ok(());
+                }
+            },
+            _ = tokio::time::sleep_until(wake_at.into()) => {}
+        }
+
+        let settled = debouncer.take_ready(Instant::now());
+        if settled.is_empty() {
+            continue;
+        }
+        let candidates = settled
+            .iter()
+            .filter_map(|path| match candidate_from_path(path) {
+                Ok(candidate) => Some(candidate),
+                Err(e) => {
+                    debug!("Watched file {:?} is gone: {}", path, e);
+                    None
+                }
+            })
+            .collect();
+        queue_candidates(candidates, ctx, queue_tx, scan_cache, false).await?;
+    }
+}
+
+/// Scan every library root and queue the files that pass the discovery checks
+async fn run_scan_cycle(
+    ctx: &PipelineContext,
+    queue_tx: &mpsc::Sender<QueuedJob>,
+    scan_cache: &mut Option<ScanCache>,
+) -> Result<()> {
+    info!("Starting scan cycle");
+
+    // An encoder left behind by a previous run keeps its job Running until it exits
+    match recover_orphaned_jobs(&ctx.config, |job| ctx.is_in_flight(&job.source_path)) {
+        Ok(recovered) if !recovered.is_empty() => {
+            info!(
+                "Recovered {} job(s) after their encoder exited",
+                recovered.len()
+            );
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to recover orphaned jobs: {}", e),
+    }
+
+    // Scan all library roots for video files
+    match scan_libraries_with(&ctx.config.library_roots, &ctx.scan_filter) {
+        Ok(candidates) => {
+            info!("Found {} candidate files", candidates.len());
+            queue_candidates(candidates, ctx, queue_tx, scan_cache, true).await?;
+        }
+        Err(e) => {
+            error!("Error scanning libraries: {}", e);
+            // Continue to next scan cycle
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the scan cache has results for `candidate` as it is now
+fn is_cached(
+    scan_cache: Option<&ScanCache>,
+    candidate: &CandidateFile,
+    config: &DaemonConfig,
+) -> bool {
+    let Some(cache) = scan_cache else {
+        return false;
+    };
+    let gate_settings = GateSettings::from_config(config);
+    FileFingerprint::of(&candidate.path).is_ok_and(|fingerprint| {
+        cache
+            .lookup(&candidate.path, &fingerprint, &gate_settings)
+            .is_some()
+    })
+}
+
+/// Run the discovery checks on `candidates` and send the survivors to the
+/// encode queue.
+///
+/// `full_scan` is false for files the watcher already saw settle; those skip
+/// the stability check and leave cache entries for other files alone.
+async fn queue_candidates(
+    candidates: Vec<CandidateFile>,
+    ctx: &PipelineContext,
+    queue_tx: &mpsc::Sender<QueuedJob>,
+    scan_cache: &mut Option<ScanCache>,
+    full_scan: bool,
+) -> Result<()> {
+    // Forget files that are gone or now have a skip marker
+    if let Some(cache) = scan_cache.as_mut().filter(|_| full_scan) {
+        cache.retain_paths(candidates.iter().map(|c| c.path.as_path()));
+    }
+
+    // Apply per-directory overrides; files below `skip = true` are left alone
+    let mut resolver = OverrideResolver::new(ctx.config.clone());
+    let mut candidates: Vec<(CandidateFile, FileConfig)> = candidates
+        .into_iter()
+        .filter_map(|candidate| match resolver.resolve(&candidate.path) {
+            Ok(file_config) if file_config.skip => {
+                debug!("Skipped by override file: {:?}", candidate.path);
+                None
             }
+            Ok(file_config) => Some((candidate, file_config)),
             Err(e) => {
-                error!("Error scanning libraries: {}", e);
-                // Continue to next scan cycle
+                warn!("Not processing {:?}: {:#}", candidate.path, e);
+                None
             }
+        })
+        .collect();
+
+    // Step 2: Check file stability for every new or changed file at once
+    if full_scan {
+        let unchecked: Vec<&CandidateFile> = candidates
+            .iter()
+            .filter(|(c, file_config)| {
+                !ctx.is_in_flight(&c.path)
+                    && !is_cached(scan_cache.as_ref(), c, &file_config.config)
+            })
+            .map(|(c, _)| c)
+            .collect();
+        let unstable = find_unstable(
+            &unchecked,
+            Duration::from_secs(ctx.config.stable_quiet_secs),
+            Duration::from_secs(ctx.config.stable_recheck_secs),
+        )
+        .await;
+        if !unstable.is_empty() {
+            debug!("{} files are not stable yet", unstable.len());
+            candidates.retain(|(c, _)| !unstable.contains(&c.path));
         }
+    }
 
-        info!(
-            "Scan cycle complete, waiting {} seconds",
-            config.scan_interval_secs
-        );
-        sleep(Duration::from_secs(config.scan_interval_secs)).await;
+    // Load existing jobs to avoid duplicates
+    let existing_jobs = load_all_jobs(&ctx.config.job_state_dir).unwrap_or_else(|e| {
+        warn!("Failed to load existing jobs: {}", e);
+        Vec::new()
+    });
+
+    // Files still backing off after a failed attempt are left for later cycles
+    let retry_ledger = load_ledger(&ctx.config.retry_ledger_path()).unwrap_or_else(|e| {
+        warn!("Failed to load retry ledger: {}", e);
+        RetryLedger::default()
+    });
+
+    // Files with a reprioritized pending job go first
+    candidates.sort_by_key(|(c, _)| std::cmp::Reverse(pending_priority(c, &existing_jobs)));
+
+    // Prepare each candidate file and queue it for encoding
+    for (candidate, file_config) in candidates {
+        let prepared = prepare_candidate(
+            candidate,
+            file_config,
+            ctx,
+            &existing_jobs,
+            &retry_ledger,
+            scan_cache.as_mut(),
+        )
+        .await;
+        match prepared {
+            Ok(Some(queued)) => {
+                ctx.mark_in_flight(queued.job.source_path.clone());
+                if queue_tx.send(queued).await.is_err() {
+                    anyhow::bail!("Encode dispatcher stopped unexpectedly");
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Error processing candidate: {}", e);
+                // Continue with next file
+            }
+        }
     }
+
+    if let Some(cache) = scan_cache.as_mut().filter(|c| c.is_dirty()) {
+        if let Err(e) = save_scan_cache(cache, &ctx.config.scan_cache_path()) {
+            warn!("Failed to save scan cache: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Pull items from `queue` and run them with `run`, never running more than the
+/// executor's limit at once.
+///
+/// A slot is acquired before each item is taken off the queue, so items stay
+/// queued (and the producer is held back) while all slots are busy. Returns once
+/// the queue is closed and every started item has finished.
+pub async fn run_encode_dispatcher<T, F, Fut>(
+    mut queue: mpsc::Receiver<T>,
+    executor: Arc<JobExecutor>,
+    run: F,
+) where
+    T: Send + 'static,
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut tasks = JoinSet::new();
+
+    loop {
+        let permit = match executor.acquire_slot().await {
+            Ok(permit) => permit,
+            Err(e) => {
+                error!("Encode dispatcher stopping: {}", e);
+                break;
+            }
+        };
+        let Some(item) = queue.recv().await else {
+            break;
+        };
+
+        let task = run(item);
+        tasks.spawn(async move {
+            task.await;
+            // Slot is released when the task finishes
+            drop(permit);
+        });
+
+        // Reap finished tasks so the set doesn't grow without bound
+        while tasks.try_join_next().is_some() {}
+    }
+
+    while tasks.join_next().await.is_some() {}
 }
 
-/// Process a single candidate file through the entire workflow
-async fn process_candidate(
+/// Priority of the pending (requeued) job for a candidate, if any
+fn pending_priority(candidate: &CandidateFile, existing_jobs: &[Job]) -> i32 {
+    existing_jobs
+        .iter()
+        .find(|job| job.source_path == candidate.path && job.status == JobStatus::Pending)
+        .and_then(|job| job.priority)
+        .unwrap_or(0)
+}
+
+/// Run discovery-side checks for a candidate and build its job.
+///
+/// Returns `None` when the file is skipped or already queued/encoding.
+async fn prepare_candidate(
     candidate: CandidateFile,
-    config: &DaemonConfig,
-    encoder: &SelectedEncoder,
-    executor: &JobExecutor,
-    existing_jobs: &[crate::jobs::Job],
-) -> Result<()> {
+    file_config: FileConfig,
+    ctx: &PipelineContext,
+    existing_jobs: &[Job],
+    retry_ledger: &RetryLedger,
+    scan_cache: Option<&mut ScanCache>,
+) -> Result<Option<QueuedJob>> {
+    let config = file_config.config.as_ref();
     let path = &candidate.path;
     debug!("Processing candidate: {:?}", path);
 
     // Step 0: Check if job already exists for this file
-    let has_active_job = existing_jobs.iter().any(|job| {
-        job.source_path == *path
-            && (job.status == JobStatus::Pending || job.status == JobStatus::Running)
-    });
+    let has_active_job = ctx.is_in_flight(path)
+        || existing_jobs
+            .iter()
+            .any(|job| job.source_path == *path && job.status == JobStatus::Running);
 
     if has_active_job {
         debug!("Job already exists for file, skipping: {:?}", path);
-        return Ok(());
+        return Ok(None);
     }
 
+    // A pending job for this file was requeued through the command directory
+    let requeued_job = existing_jobs
+        .iter()
+        .find(|job| job.source_path == *path && job.status == JobStatus::Pending);
+
     // Step 1: Check for skip marker
     if has_skip_marker(path) {
         debug!("File has skip marker, skipping: {:?}", path);
-        return Ok(());
+        return Ok(None);
     }
 
-    // Step 2: Check file stability
-    debug!("Checking file stability: {:?}", path);
-    match check_stability(&candidate, Duration::from_secs(10)).await {
-        Ok(is_stable) => {
-            if !is_stable {
-                debug!("File is not stable, skipping for this cycle: {:?}", path);
-                return Ok(());
-            }
-        }
-        Err(e) => {
-            warn!("Error checking file stability for {:?}: {}", path, e);
-            return Ok(());
-        }
+    // Step 1b: Respect the backoff after a failed attempt
+    if let Some(retry_at) = retry_ledger.backoff_until(path, Utc::now()) {
+        debug!(
+            "File is backing off until {}, skipping: {:?}",
+            retry_at, path
+        );
+        return Ok(None);
     }
 
-    // Step 3: Probe file metadata
-    debug!("Probing file: {:?}", path);
-    let probe_result = match probe_file(path).await {
-        Ok(result) => result,
+    // Steps 3-5 are skipped for files unchanged since they were last inspected
+    let fingerprint = match FileFingerprint::of(path) {
+        Ok(fingerprint) => fingerprint,
         Err(e) => {
-            warn!("Failed to probe file {:?}: {}", path, e);
-            create_skip_marker(path)?;
-            if config.write_why_sidecars {
-                write_why_file(path, &format!("Probe failed: {}", e))?;
-            }
-            return Ok(());
+            warn!("Failed to get metadata for {:?}: {}", path, e);
+            return Ok(None);
         }
     };
+    let gate_settings = GateSettings::from_config(config);
+    let cached = scan_cache
+        .as_deref()
+        .and_then(|cache| cache.lookup(path, &fingerprint, &gate_settings))
+        .cloned();
+    let inspected = match cached {
+        Some(entry) => {
+            debug!("Using cached scan results: {:?}", path);
+            entry
+        }
+        None => match inspect_candidate(&candidate, fingerprint, config).await? {
+            Some(entry) => {
+                // A skip marker that appeared mid-check says nothing about the file
+                let marker_appeared = entry.gate == GateResult::Skip(SkipReason::HasSkipMarker);
+                if let Some(cache) = scan_cache.filter(|_| !marker_appeared) {
+                    cache.insert(path.clone(), entry.clone());
+                }
+                entry
+            }
+            None => return Ok(None),
+        },
+    };
+    let ScanCacheEntry {
+        probe: probe_result,
+        classification,
+        gate,
+        ..
+    } = inspected;
 
-    // Step 4: Classify source
-    debug!("Classifying source: {:?}", path);
-    let classification = classify_source(path, &probe_result);
-    debug!("Classification: {:?}", classification.source_type);
-
-    // Step 5: Check gates
-    debug!("Checking gates: {:?}", path);
-    match check_gates(&candidate, &probe_result, config) {
+    match gate {
         GateResult::Pass => {
             debug!("Gates passed: {:?}", path);
         }
@@ -144,12 +540,23 @@ async fn process_candidate(
             if config.write_why_sidecars {
                 write_why_file(path, &format!("{:?}", reason))?;
             }
-            return Ok(());
+            return Ok(None);
         }
     }
 
-    // Step 6: Create job
+    // Step 6: Create job (reusing the identity of a requeued job)
+    let profile = select_profile(config, path, &probe_result, &classification).map(String::from);
     let mut job = create_job(candidate.clone(), probe_result.clone(), classification);
+    if let Some(requeued) = requeued_job {
+        job.id = requeued.id.clone();
+        job.created_at = requeued.created_at;
+        job.priority = requeued.priority;
+    }
+    job.effective_config = Some(EffectiveConfig::new(&file_config));
+    if let Some(name) = &profile {
+        debug!("Using encoding profile {:?} for {:?}", name, path);
+    }
+    job.encoding_profile = profile;
 
     // Populate video metadata from probe result
     if let Some(main_stream) = probe_result.main_video_stream() {
@@ -166,53 +573,411 @@ async fn process_candidate(
     save_job(&job, &config.job_state_dir)?;
     info!("Created job {} for {:?}", job.id, path);
 
+    Ok(Some(QueuedJob {
+        job,
+        probe: probe_result,
+        config: file_config.config,
+    }))
+}
+
+/// Steps 3-5 of discovery: probe, classification and gates.
+///
+/// Returns `None` when the file cannot be probed.
+async fn inspect_candidate(
+    candidate: &CandidateFile,
+    fingerprint: FileFingerprint,
+    config: &DaemonConfig,
+) -> Result<Option<ScanCacheEntry>> {
+    let path = &candidate.path;
+
+    // Step 3: Probe file metadata
+    debug!("Probing file: {:?}", path);
+    let probe_result = match probe_file(path).await {
+        Ok(result) => result,
+        Err(e) => {
+            warn!("Failed to probe file {:?}: {}", path, e);
+            create_skip_marker(path)?;
+            if config.write_why_sidecars {
+                write_why_file(path, &format!("Probe failed: {}", e))?;
+            }
+            return Ok(None);
+        }
+    };
+
+    // Step 4: Classify source
+    debug!("Classifying source: {:?}", path);
+    let classification = classify_source(path, &probe_result);
+    debug!("Classification: {:?}", classification.source_type);
+
+    // Step 5: Check gates
+    debug!("Checking gates: {:?}", path);
+    let gate = check_gates(candidate, &probe_result, config);
+
+    Ok(Some(ScanCacheEntry {
+        fingerprint,
+        gate_settings: GateSettings::from_config(config),
+        probe_schema: PROBE_SCHEMA_VERSION,
+        probe: probe_result,
+        classification,
+        gate,
+    }))
+}
+
+/// Encode a queued job once it has been given a slot, then validate and replace.
+///
+/// Errors are recorded on the job rather than returned; the source path is
+/// released for rescanning when the job is done.
+async fn encode_queued_job(queued: QueuedJob, ctx: PipelineContext) {
+    let path = queued.job.source_path.clone();
+    let job_id = queued.job.id.clone();
+
+    if let Err(e) = run_queued_job(queued, &ctx).await {
+        error!("Error processing job {}: {}", job_id, e);
+    }
+
+    ctx.running.unregister(&job_id);
+    ctx.clear_in_flight(&path);
+}
+
+async fn run_queued_job(queued: QueuedJob, ctx: &PipelineContext) -> Result<()> {
+    let QueuedJob {
+        mut job,
+        probe: probe_result,
+        config,
+    } = queued;
+    let config = config.as_ref();
+    let profile = job
+        .encoding_profile
+        .as_deref()
+        .and_then(|name| config.profiles.get(name))
+        .cloned()
+        .unwrap_or_default();
+    let encoder = &select_encoder(
+        &ctx.available_encoders,
+        profile.encoder.unwrap_or(config.prefer_encoder),
+    )?;
+    let height = job.video_height.unwrap_or(1080);
+    let bitrate = job.video_bitrate.or(probe_result.format.bitrate);
+    let mut video = VideoSettings::new(config, encoder.encoder, &profile, height);
+    let path = job.source_path.clone();
+    let path = &path;
+
+    // The job may have been cancelled or skipped while it waited for a slot
+    let still_pending = load_all_jobs(&config.job_state_dir)?
+        .iter()
+        .any(|j| j.id == job.id && j.status == JobStatus::Pending);
+    if !still_pending || has_skip_marker(path) {
+        info!("Job {} left the queue before encoding, dropping", job.id);
+        return Ok(());
+    }
+
     // Step 7: Execute encoding
     // Update job status to running
     update_job_status(&mut job, JobStatus::Running, &config.job_state_dir)?;
+    // Commands reach the job from here on, not only while ffmpeg runs
+    let (_registration, interrupt) = ctx.running.register_scoped(&job.id);
+
+    // Generate output path in the container the file will end up in
+    let container_plan = plan_output_container(path, &probe_result, config.output_container);
+    if let Some(reason) = &container_plan.switch_reason {
+        info!(
+            "Job {} will be written as {:?} ({})",
+            job.id, container_plan.final_path, reason
+        );
+    }
+    if container_plan.is_renamed(path) && container_plan.final_path.exists() {
+        let reason = format!(
+            "Cannot switch container: {:?} already exists",
+            container_plan.final_path
+        );
+        return skip_job(&mut job, reason, config);
+    }
+
+    // rav1e cannot write Dolby Vision RPUs, and profile 5 is unwatchable without them
+    if let Some(video) = probe_result.main_video_stream() {
+        if let Some(dv) = video.color.dolby_vision {
+            if !dv.has_compatible_base_layer()
+                && matches!(encoder.encoder, crate::startup::AvailableEncoder::Librav1e)
+            {
+                let reason = format!(
+                    "Dolby Vision profile {} has no HDR10/SDR-compatible base layer and {} cannot encode Dolby Vision",
+                    dv.profile, encoder.codec_name
+                );
+                return skip_job(&mut job, reason, config);
+            }
+        }
+        if video.color.hdr_format().is_some() {
+            info!("Job {}: source is {}", job.id, video.color.describe());
+        }
+    }
 
-    // Generate output path
-    let output_path = config.temp_output_dir.join(format!("{}.mkv", job.id));
+    let output_path = config.temp_output_dir.join(format!(
+        "{}.{}",
+        job.id,
+        container_plan.container.extension()
+    ));
     job.output_path = Some(output_path.clone());
 
+    for dropped in select_tracks(&probe_result, &config.track_policy).dropped {
+        info!(
+            "Job {}: dropping {:?} stream {} ({})",
+            job.id, dropped.kind, dropped.index, dropped.reason
+        );
+    }
+    let subtitles = plan_subtitles(
+        &probe_result,
+        &config.track_policy,
+        container_plan.container,
+    );
+    for track in &subtitles {
+        match &track.action {
+            SubtitleAction::Copy => {}
+            SubtitleAction::Convert { codec } => info!(
+                "Job {}: converting {} subtitle stream {} to {}",
+                job.id, track.codec_name, track.index, codec
+            ),
+            SubtitleAction::Drop { reason } => info!(
+                "Job {}: dropping subtitle stream {} ({})",
+                job.id, track.index, reason
+            ),
+        }
+    }
+    job.subtitles = (!subtitles.is_empty()).then_some(subtitles);
+
+    // Deinterlace interlaced sources, confirming with idet when configured
+    let field_order = probe_result
+        .main_video_stream()
+        .and_then(|v| v.field_order.as_deref());
+    if config.deinterlace.enabled && field_order != Some("progressive") {
+        let idet = if config.deinterlace.detect {
+            info!("Detecting interlacing for job {}", job.id);
+            match detect_interlacing("ffmpeg", path, &probe_result, &config.deinterlace).await {
+                Ok(counts) => Some(counts),
+                Err(e) => {
+                    warn!(
+                        "Interlace detection failed for job {}, using the field order: {}",
+                        job.id, e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        if idet.is_some() || is_interlaced_field_order(field_order) {
+            let decision = InterlaceDecision::new(field_order, idet, &config.deinterlace);
+            info!(
+                "Job {}: source is {:?} (field order {})",
+                job.id,
+                decision.scan_type,
+                field_order.unwrap_or("unknown")
+            );
+            job.interlace = Some(decision);
+        }
+    }
+    if stop_if_interrupted(&mut job, &interrupt, config)? {
+        return Ok(());
+    }
+
+    // Crop black bars, when the encoding profile or crop_detection asks for it
+    if profile.crop.unwrap_or(config.crop_detection.enabled) {
+        info!("Detecting black bars for job {}", job.id);
+        match detect_crop("ffmpeg", path, &probe_result, &config.crop_detection).await {
+            Ok(detection) => {
+                match detection.crop {
+                    Some(crop) => info!(
+                        "Job {}: cropping to {} ({} of {} samples agree)",
+                        job.id,
+                        crop.filter(),
+                        detection.agreeing,
+                        detection.samples
+                    ),
+                    None => info!(
+                        "Job {}: not cropping ({} of {} samples agree)",
+                        job.id, detection.agreeing, detection.samples
+                    ),
+                }
+                job.crop_detection = Some(detection);
+            }
+            Err(e) => warn!(
+                "Crop detection failed for job {}, encoding the full frame: {}",
+                job.id, e
+            ),
+        }
+        if stop_if_interrupted(&mut job, &interrupt, config)? {
+            return Ok(());
+        }
+    }
+
+    // Synthesise grain for grainy sources, unless the profile sets film_grain
+    if config.grain_detection.enabled && video.film_grain.is_none() {
+        info!("Measuring grain for job {}", job.id);
+        let source_filters = SourceFilters::for_job(&job);
+        match detect_grain(
+            "ffmpeg",
+            path,
+            &probe_result,
+            &source_filters,
+            &config.grain_detection,
+        )
+        .await
+        {
+            Ok(grain) => {
+                info!(
+                    "Job {}: noise level {:.2}, film grain {}",
+                    job.id, grain.noise_level, grain.film_grain
+                );
+                grain.apply(&mut video);
+                job.grain_analysis = Some(grain);
+            }
+            Err(e) => warn!(
+                "Grain detection failed for job {}, encoding without film grain: {}",
+                job.id, e
+            ),
+        }
+        if stop_if_interrupted(&mut job, &interrupt, config)? {
+            return Ok(());
+        }
+    }
+
+    // Pick the CRF, searching per file when configured
+    let mut crf = profile.crf(config, encoder.encoder, height, bitrate);
+    if config.crf_search.enabled {
+        info!("Searching CRF for job {}", job.id);
+        match search_crf(
+            "ffmpeg",
+            &job,
+            &probe_result,
+            &video,
+            &config.crf_search,
+            &config.temp_output_dir,
+        )
+        .await
+        {
+            Ok(trace) => {
+                if !trace.met_target {
+                    warn!(
+                        "Job {}: no CRF reached {:?} {}, using CRF {}",
+                        job.id, trace.metric, trace.target, trace.chosen_crf
+                    );
+                }
+                crf = trace.chosen_crf;
+                job.crf_search = Some(trace);
+            }
+            Err(e) => warn!(
+                "CRF search failed for job {}, using CRF {}: {}",
+                job.id, crf, e
+            ),
+        }
+        if stop_if_interrupted(&mut job, &interrupt, config)? {
+            return Ok(());
+        }
+    }
+
+    // Predict the output size from samples and skip files that won't shrink enough
+    if config.savings_prediction.enabled {
+        info!("Predicting output size for job {}", job.id);
+        let video_args = video_encoder_args(&video, &probe_result, height, crf);
+        let audio = plan_audio(&probe_result, &config.track_policy, &config.audio_policy);
+        match predict_savings(
+            "ffmpeg",
+            &job,
+            &probe_result,
+            &audio,
+            &video_args,
+            &config.savings_prediction,
+            &config.temp_output_dir,
+        )
+        .await
+        {
+            Ok(prediction) => {
+                info!(
+                    "Job {}: predicted {} bytes ({:.1}% of original) from {} samples",
+                    job.id,
+                    prediction.predicted_bytes,
+                    prediction.predicted_ratio * 100.0,
+                    prediction.samples
+                );
+                let failure = prediction
+                    .gate_failure(config.max_size_ratio, config.savings_prediction.margin);
+                job.savings_prediction = Some(prediction);
+                if let Some(reason) = failure {
+                    warn!("Skipping job {}: {}", job.id, reason);
+                    job.crf_used = Some(crf);
+                    return skip_job(&mut job, reason, config);
+                }
+            }
+            Err(e) => warn!(
+                "Size prediction failed for job {}, encoding anyway: {}",
+                job.id, e
+            ),
+        }
+    }
+
     // Build FFmpeg command
-    let command = build_command(&job, encoder, config, output_path.to_str().unwrap());
+    let command = build_command(
+        &job,
+        &probe_result,
+        &video,
+        config,
+        crf,
+        output_path.to_str().unwrap(),
+    );
 
     // Store encoding parameters in job
     job.encoder_used = Some(encoder.codec_name.clone());
-    job.crf_used = Some(crate::encode::select_crf(
-        job.video_height.unwrap_or(1080),
-        job.video_bitrate,
-        config.quality_tier,
-    ));
-    if matches!(encoder.encoder, crate::startup::AvailableEncoder::SvtAv1) {
-        job.preset_used = Some(crate::encode::select_preset(
-            job.video_height.unwrap_or(1080),
-            config.quality_tier,
-        ));
+    job.crf_used = Some(crf);
+    if matches!(encoder.encoder, AvailableEncoder::SvtAv1) {
+        job.preset_used = video.preset;
     }
 
+    // The prediction may have taken a while; a skip marker or status change
+    // written meanwhile must not be overwritten by the encode
+    if stop_if_interrupted(&mut job, &interrupt, config)? {
+        return Ok(());
+    }
     save_job(&job, &config.job_state_dir)?;
 
     info!("Starting encoding for job {}: {:?}", job.id, path);
     debug!("FFmpeg command: {:?}", command);
 
-    // Execute encoding with concurrency limiting
-    let encode_result = executor
-        .execute_job(|| {
-            let cmd = command.clone();
-            let mut job_clone = job.clone();
-            let state_dir = config.job_state_dir.clone();
-            async move { execute_encode(&mut job_clone, cmd, &state_dir).await }
-        })
-        .await;
+    // The dispatcher already holds an encode slot for this job
+    let early_abort = EarlyAbortPolicy::from_config(config, job.original_bytes.unwrap_or(0));
+    let encode_result = execute_encode_interruptible(
+        &mut job,
+        command,
+        &config.job_state_dir,
+        interrupt.clone(),
+        early_abort,
+    )
+    .await;
 
     let encoded_path = match encode_result {
         Ok(path) => path,
+        Err(e) if e.is::<EncodeInterrupted>() => {
+            let EncodeInterrupted(kind) = e.downcast().expect("checked above");
+            return handle_interrupted_job(&mut job, kind, config);
+        }
+        Err(e) if e.is::<EncodeAbortedEarly>() => {
+            let EncodeAbortedEarly {
+                progress,
+                projected_bytes,
+                threshold_bytes,
+            } = e.downcast().expect("checked above");
+            warn!(
+                "Aborted job {} at {:.1}%: projected {} bytes >= {} bytes threshold",
+                job.id, progress, projected_bytes, threshold_bytes
+            );
+            let reason = format!(
+                "Size gate failed (aborted early at {:.1}%): projected {} bytes >= {} bytes threshold",
+                progress, projected_bytes, threshold_bytes
+            );
+            return skip_job(&mut job, reason, config);
+        }
         Err(e) => {
             error!("Encoding failed for job {}: {}", job.id, e);
-            job.reason = Some(format!("Encoding failed: {}", e));
-            update_job_status(&mut job, JobStatus::Failed, &config.job_state_dir)?;
-            return Ok(());
+            let reason = format!("Encoding failed: {}", e);
+            return fail_job(&mut job, classify_failure(&e), reason, config);
         }
     };
 
@@ -220,14 +985,51 @@ async fn process_candidate(
 
     // Step 8: Validate output
     debug!("Validating output: {:?}", encoded_path);
-    let _output_probe = match validate_output(&encoded_path, &probe_result).await {
-        Ok(result) => result,
-        Err(e) => {
-            error!("Output validation failed for job {}: {}", job.id, e);
-            job.reason = Some(format!("Validation failed: {}", e));
-            update_job_status(&mut job, JobStatus::Failed, &config.job_state_dir)?;
+    let expected = ExpectedStreams::for_job(&job, &probe_result, config, &encoded_path);
+    let validation_failure = match validate_output(&encoded_path, &probe_result, &expected).await {
+        Ok(ValidationResult::Valid(_)) => None,
+        Ok(ValidationResult::Invalid(err)) => Some((
+            FailureKind::Permanent,
+            format!("Validation failed: {:?}", err),
+        )),
+        Err(e) => Some((classify_failure(&e), format!("Validation failed: {}", e))),
+    };
+    if let Some((kind, reason)) = validation_failure {
+        error!("Output validation failed for job {}: {}", job.id, reason);
+        fail_job(&mut job, kind, reason, config)?;
+
+        // Clean up failed output
+        if let Err(cleanup_err) = std::fs::remove_file(&encoded_path) {
+            warn!(
+                "Failed to clean up invalid output {:?}: {}",
+                encoded_path, cleanup_err
+            );
+        }
+
+        return Ok(());
+    }
+
+    info!("Output validation passed for job {}", job.id);
+
+    // Step 8b: Decode the whole output to catch corruption probing cannot see
+    if config.verify.enabled {
+        info!("Verifying full decode for job {}", job.id);
+        let verify_failure =
+            match verify_decode("ffmpeg", &encoded_path, &mut job, &config.job_state_dir).await {
+                Ok(None) => None,
+                Ok(Some(err)) => Some((
+                    FailureKind::Permanent,
+                    format!("Decode verification failed: {:?}", err),
+                )),
+                Err(e) => Some((
+                    classify_failure(&e),
+                    format!("Decode verification failed: {}", e),
+                )),
+            };
+        if let Some((kind, reason)) = verify_failure {
+            error!("Decode verification failed for job {}: {}", job.id, reason);
+            fail_job(&mut job, kind, reason, config)?;
 
-            // Clean up failed output
             if let Err(cleanup_err) = std::fs::remove_file(&encoded_path) {
                 warn!(
                     "Failed to clean up invalid output {:?}: {}",
@@ -237,9 +1039,9 @@ async fn process_candidate(
 
             return Ok(());
         }
-    };
 
-    info!("Output validation passed for job {}", job.id);
+        info!("Decode verification passed for job {}", job.id);
+    }
 
     // Step 9: Check size gate
     let output_size = std::fs::metadata(&encoded_path)?.len();
@@ -277,12 +1079,6 @@ async fn process_candidate(
                 job.id, new_bytes, threshold_bytes
             );
 
-            job.reason = Some(format!(
-                "Size gate failed: {} bytes >= {} bytes threshold",
-                new_bytes, threshold_bytes
-            ));
-            update_job_status(&mut job, JobStatus::Skipped, &config.job_state_dir)?;
-
             // Clean up output
             if let Err(cleanup_err) = std::fs::remove_file(&encoded_path) {
                 warn!(
@@ -291,20 +1087,108 @@ async fn process_candidate(
                 );
             }
 
-            // Create skip marker and why file
-            create_skip_marker(path)?;
-            if config.write_why_sidecars {
-                write_why_file(path, &job.reason.as_ref().unwrap())?;
+            let reason = format!(
+                "Size gate failed: {} bytes >= {} bytes threshold",
+                new_bytes, threshold_bytes
+            );
+            return skip_job(&mut job, reason, config);
+        }
+    }
+
+    // Step 9b: Sampled quality check against the source
+    if config.quality_check.enabled {
+        job.stage = Some(crate::jobs::JobStage::Verifying);
+        save_job(&job, &config.job_state_dir)?;
+
+        info!("Checking sampled quality for job {}", job.id);
+        let quality_failure = match measure_quality(
+            "ffmpeg",
+            path,
+            &encoded_path,
+            &probe_result,
+            &SourceFilters::for_job(&job),
+            &config.quality_check,
+        )
+        .await
+        {
+            Ok(scores) => {
+                info!(
+                    "Quality for job {}: SSIM {:.4}, PSNR {:.2} dB, VMAF {} (worst of {} samples)",
+                    job.id,
+                    scores.ssim,
+                    scores.psnr,
+                    scores
+                        .vmaf
+                        .map(|v| format!("{:.2}", v))
+                        .unwrap_or_else(|| "n/a".to_string()),
+                    scores.samples
+                );
+                let failure = check_quality(&scores, &config.quality_check);
+                job.quality_scores = Some(scores);
+                failure.map(|err| {
+                    (
+                        FailureKind::Permanent,
+                        format!("Quality check failed: {:?}", err),
+                    )
+                })
+            }
+            Err(e) => Some((classify_failure(&e), format!("Quality check failed: {}", e))),
+        };
+        if let Some((kind, reason)) = quality_failure {
+            error!("Quality check failed for job {}: {}", job.id, reason);
+            fail_job(&mut job, kind, reason, config)?;
+
+            if let Err(cleanup_err) = std::fs::remove_file(&encoded_path) {
+                warn!(
+                    "Failed to clean up rejected output {:?}: {}",
+                    encoded_path, cleanup_err
+                );
             }
 
             return Ok(());
         }
     }
 
-    // Step 10: Atomic replacement
+    // Step 9c: Extract subtitle sidecars while the source is still in place
+    if let Some(mut subtitles) = job.subtitles.take() {
+        let sidecars = plan_sidecars(
+            &container_plan.final_path,
+            &subtitles,
+            config.subtitle_policy.extract,
+        );
+        for (position, sidecar) in sidecars {
+            let track = &mut subtitles[position];
+            match extract_sidecar("ffmpeg", path, track, &sidecar).await {
+                Ok(()) => {
+                    info!(
+                        "Job {}: extracted subtitle stream {} to {:?}",
+                        job.id, track.index, sidecar
+                    );
+                    track.sidecar = Some(sidecar);
+                }
+                Err(e) => warn!(
+                    "Job {}: could not extract subtitle stream {}: {}",
+                    job.id, track.index, e
+                ),
+            }
+        }
+        job.subtitles = Some(subtitles);
+    }
+
+    // Step 10: Atomic replacement, unless the job was stopped during the checks
+    if stop_if_interrupted(&mut job, &interrupt, config)? {
+        if let Err(cleanup_err) = std::fs::remove_file(&encoded_path) {
+            warn!(
+                "Failed to clean up output of stopped job {:?}: {}",
+                encoded_path, cleanup_err
+            );
+        }
+        return Ok(());
+    }
     info!("Replacing original file for job {}", job.id);
     info!("  Original: {:?}", path);
     info!("  Encoded: {:?}", encoded_path);
+    info!("  Final: {:?}", container_plan.final_path);
     info!("  Keep original: {}", config.keep_original);
 
     // Log file sizes for debugging
@@ -315,17 +1199,31 @@ async fn process_candidate(
         info!("  Encoded size: {} bytes", enc_meta.len());
     }
 
-    match atomic_replace(path, &encoded_path, config.keep_original) {
+    let final_path = &container_plan.final_path;
+    match atomic_replace_to(path, &encoded_path, final_path, config.keep_original) {
         Ok(()) => {
             info!("Successfully replaced {:?}", path);
+            if final_path != path {
+                match move_sibling_files(path, final_path) {
+                    Ok(moved) => {
+                        for (from, to) in moved {
+                            info!("  Moved sibling {:?} -> {:?}", from, to);
+                        }
+                    }
+                    Err(e) => warn!("Failed to move sibling files of {:?}: {}", path, e),
+                }
+            }
+            update_ledger(config, |ledger| ledger.clear(path))?;
+            job.source_path = final_path.clone();
+            job.output_path = Some(final_path.clone());
             job.stage = Some(crate::jobs::JobStage::Complete);
             update_job_status(&mut job, JobStatus::Success, &config.job_state_dir)?;
         }
         Err(e) => {
             error!("Failed to replace file for job {}: {}", job.id, e);
             error!("Full error chain: {:?}", e);
-            job.reason = Some(format!("Replacement failed: {}", e));
-            update_job_status(&mut job, JobStatus::Failed, &config.job_state_dir)?;
+            let reason = format!("Replacement failed: {}", e);
+            fail_job(&mut job, classify_failure(&e), reason, config)?;
 
             // Keep the output file for manual inspection
             warn!(
@@ -340,3 +1238,101 @@ async fn process_candidate(
     info!("Job {} completed successfully", job.id);
     Ok(())
 }
+
+/// Mark a job skipped and write the skip marker and why file for its source
+fn skip_job(job: &mut Job, reason: String, config: &DaemonConfig) -> Result<()> {
+    create_skip_marker(&job.source_path)?;
+    if config.write_why_sidecars {
+        write_why_file(&job.source_path, &reason)?;
+    }
+    job.reason = Some(reason);
+    update_job_status(job, JobStatus::Skipped, &config.job_state_dir)
+}
+
+/// Mark a job failed and record the attempt in the retry ledger.
+///
+/// Transient failures are retried with exponential backoff until
+/// `max_attempts`; after that, or after a permanent failure, the source gets a
+/// skip marker and a `.why.txt` listing every attempt.
+fn fail_job(job: &mut Job, kind: FailureKind, reason: String, config: &DaemonConfig) -> Result<()> {
+    let path = job.source_path.clone();
+    let decision = update_ledger(config, |ledger| {
+        ledger.record_failure(&path, kind, &reason, Utc::now(), config)
+    })?;
+
+    match decision {
+        RetryDecision::RetryAfter(retry_at) => {
+            info!(
+                "Job {} failed ({:?}); retrying {:?} after {}",
+                job.id, kind, path, retry_at
+            );
+            job.reason = Some(format!(
+                "{} (will retry after {})",
+                reason,
+                retry_at.format("%Y-%m-%d %H:%M:%S UTC")
+            ));
+        }
+        RetryDecision::GiveUp { summary } => {
+            warn!("Giving up on {:?} after job {} failed", path, job.id);
+            job.reason = Some(reason);
+            create_skip_marker(&path)?;
+            if config.write_why_sidecars {
+                write_why_file(&path, &summary)?;
+            }
+        }
+    }
+
+    update_job_status(job, JobStatus::Failed, &config.job_state_dir)
+}
+
+/// Stop a running job between pipeline steps when a command interrupted it,
+/// its source got a skip marker or its job file left the `Running` state.
+///
+/// Returns true when the job was stopped and must not go on.
+fn stop_if_interrupted(
+    job: &mut Job,
+    interrupt: &watch::Receiver<Option<JobInterrupt>>,
+    config: &DaemonConfig,
+) -> Result<bool> {
+    let requested = *interrupt.borrow();
+    let requested =
+        requested.or_else(|| has_skip_marker(&job.source_path).then_some(JobInterrupt::Skip));
+    if let Some(kind) = requested {
+        handle_interrupted_job(job, kind, config)?;
+        return Ok(true);
+    }
+
+    let status = load_job(&config.job_state_dir, &job.id)?.map(|j| j.status);
+    if status != Some(JobStatus::Running) {
+        info!(
+            "Job {} is {:?} on disk, dropping it without touching the job file",
+            job.id, status
+        );
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Record the outcome of a job stopped by a command
+fn handle_interrupted_job(job: &mut Job, kind: JobInterrupt, config: &DaemonConfig) -> Result<()> {
+    info!("Job {} interrupted: {:?}", job.id, kind);
+
+    match kind {
+        JobInterrupt::Requeue => {
+            reset_for_requeue(job);
+            update_job_status(job, JobStatus::Pending, &config.job_state_dir)?;
+        }
+        JobInterrupt::Cancel => {
+            // Only this attempt stops; the retry ledger is left alone on purpose
+            job.reason = Some("Cancelled by user".to_string());
+            update_job_status(job, JobStatus::Failed, &config.job_state_dir)?;
+        }
+        JobInterrupt::Skip => {
+            // The command handler already wrote the skip marker and why file
+            job.reason = Some("Skipped by user".to_string());
+            update_job_status(job, JobStatus::Skipped, &config.job_state_dir)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,9 +1,27 @@
 // libaom-av1 encoder command builder
 
-use super::common::{pad_filter, pad_filter_value, stream_mapping_flags, websafe_input_flags};
+use super::common::{
+    color_flags, keyint_flags, stream_mapping_flags, video_filter_flags, websafe_input_flags,
+};
+use super::VideoSettings;
+use crate::audio::{audio_codec_flags, plan_audio};
+use crate::config::{AudioPolicy, TrackPolicy};
+use crate::container::OutputContainer;
 use crate::jobs::Job;
-
-pub fn build_aom_command(job: &Job, crf: u8, output_path: &str) -> Vec<String> {
+use crate::probe::ProbeResult;
+use crate::subtitles::{plan_subtitles, subtitle_codec_flags};
+use std::path::Path;
+
+pub fn build_aom_command(
+    job: &Job,
+    probe: &ProbeResult,
+    track_policy: &TrackPolicy,
+    audio_policy: &AudioPolicy,
+    video: &VideoSettings,
+    crf: u8,
+    output_path: &str,
+) -> Vec<String> {
+    let container = OutputContainer::from_path(Path::new(output_path));
     let mut command = vec![
         "ffmpeg".to_string(),
         "-hide_banner".to_string(),
@@ -20,43 +38,32 @@ pub fn build_aom_command(job: &Job, crf: u8, output_path: &str) -> Vec<String> {
     command.push(job.source_path.to_string_lossy().to_string());
 
     // Add stream mapping flags
-    command.extend(stream_mapping_flags());
-
-    // Add pad filter if needed
+    command.extend(stream_mapping_flags(
+        probe,
+        track_policy,
+        audio_policy,
+        container,
+    ));
+
+    // Add crop and pad filters if needed
     let width = job.video_width.unwrap_or(1920);
     let height = job.video_height.unwrap_or(1080);
-    if let Some(filter_flag) = pad_filter(width, height, job.is_web_like) {
-        command.push(filter_flag);
-        command.push(pad_filter_value());
-    }
+    command.extend(video_filter_flags(job, width, height));
 
     // Add libaom-av1 encoder parameters
-    command.push("-c:v".to_string());
-    command.push("libaom-av1".to_string());
-    command.push("-b:v".to_string());
-    command.push("0".to_string());
-    command.push("-crf".to_string());
-    command.push(crf.to_string());
-
-    // Add cpu-used based on resolution
-    let cpu_used = select_cpu_used(height);
-    command.push("-cpu-used".to_string());
-    command.push(cpu_used.to_string());
-
-    // Add row-based multithreading
-    command.push("-row-mt".to_string());
-    command.push("1".to_string());
-
-    // Add tile configuration based on resolution
-    let tiles = select_tiles(height);
-    command.push("-tiles".to_string());
-    command.push(tiles.to_string());
-
-    // Copy audio and subtitle streams
-    command.push("-c:a".to_string());
-    command.push("copy".to_string());
-    command.push("-c:s".to_string());
-    command.push("copy".to_string());
+    command.extend(aom_video_args(probe, video, crf, height));
+
+    // Copy or transcode each audio track, and copy or convert each subtitle track
+    command.extend(audio_codec_flags(&plan_audio(
+        probe,
+        track_policy,
+        audio_policy,
+    )));
+    command.extend(subtitle_codec_flags(&plan_subtitles(
+        probe,
+        track_policy,
+        container,
+    )));
 
     // Add max muxing queue size
     command.push("-max_muxing_queue_size".to_string());
@@ -68,6 +75,55 @@ pub fn build_aom_command(job: &Job, crf: u8, output_path: &str) -> Vec<String> {
     command
 }
 
+/// Video encoder arguments for libaom-av1, including colour metadata
+pub fn aom_video_args(
+    probe: &ProbeResult,
+    video: &VideoSettings,
+    crf: u8,
+    height: i32,
+) -> Vec<String> {
+    let mut args = vec![
+        "-c:v".to_string(),
+        "libaom-av1".to_string(),
+        "-b:v".to_string(),
+        "0".to_string(),
+        "-crf".to_string(),
+        crf.to_string(),
+    ];
+
+    // Add cpu-used from the preset table
+    if let Some(cpu_used) = video.preset {
+        args.push("-cpu-used".to_string());
+        args.push(cpu_used.to_string());
+    }
+
+    // Add row-based multithreading
+    args.push("-row-mt".to_string());
+    args.push("1".to_string());
+
+    // Add tile configuration based on resolution
+    args.push("-tiles".to_string());
+    args.push(select_tiles(height).to_string());
+
+    // Film grain synthesis
+    if let Some(film_grain) = video.film_grain {
+        args.push("-denoise-noise-level".to_string());
+        args.push(film_grain.to_string());
+        if let Some(denoise) = video.film_grain_denoise {
+            args.push("-aom-params".to_string());
+            args.push(format!("enable-dnl-denoising={}", u8::from(denoise)));
+        }
+    }
+    args.extend(keyint_flags(video.keyint));
+
+    // Carry the colour description and HDR metadata
+    if let Some(stream) = probe.main_video_stream() {
+        args.extend(color_flags(&stream.color));
+    }
+
+    args
+}
+
 pub fn select_tiles(height: i32) -> &'static str {
     match height {
         h if h > 2160 => "3x2",
@@ -75,10 +131,3 @@ pub fn select_tiles(height: i32) -> &'static str {
         _ => "2x1",
     }
 }
-
-pub fn select_cpu_used(height: i32) -> u8 {
-    match height {
-        h if h > 1080 => 3,
-        _ => 4,
-    }
-}
@@ -1,30 +1,88 @@
 // Common FFmpeg command components
 
+use crate::audio::plan_audio;
+use crate::config::{AudioPolicy, TrackPolicy};
+use crate::container::OutputContainer;
+use crate::crop::CropRect;
+use crate::jobs::Job;
+use crate::probe::{ColorInfo, ProbeResult};
+use crate::subtitles::plan_subtitles;
+
 /// Returns stream mapping flags that:
-/// - Select all streams initially
-/// - Exclude attached pictures
-/// - Exclude Russian audio tracks
-/// - Exclude Russian subtitle tracks
+/// - Select all video streams except attached pictures
+/// - Select the audio and subtitle tracks chosen by the track policy, explicitly by index,
+///   mapping transcoded audio twice when the audio policy keeps the original and
+///   leaving out subtitles `container` cannot hold
+/// - Keep attachments (fonts) and data streams when present
 /// - Preserve chapters and metadata
-pub fn stream_mapping_flags() -> Vec<String> {
-    vec![
-        "-map".to_string(),
-        "0".to_string(),
-        "-map".to_string(),
-        "-0:v:m:attached_pic".to_string(),
-        "-map".to_string(),
-        "-0:a:m:language:ru".to_string(),
-        "-map".to_string(),
-        "-0:a:m:language:rus".to_string(),
+pub fn stream_mapping_flags(
+    probe: &ProbeResult,
+    policy: &TrackPolicy,
+    audio_policy: &AudioPolicy,
+    container: OutputContainer,
+) -> Vec<String> {
+    let audio = plan_audio(probe, policy, audio_policy);
+    let subtitles = plan_subtitles(probe, policy, container);
+
+    let mut flags = vec!["-map".to_string(), "0:V".to_string()];
+    for index in audio.iter().map(|output| output.source_index).chain(
+        subtitles
+            .iter()
+            .filter(|track| !track.is_dropped())
+            .map(|track| track.index),
+    ) {
+        flags.push("-map".to_string());
+        flags.push(format!("0:{}", index));
+    }
+    flags.extend([
         "-map".to_string(),
-        "-0:s:m:language:ru".to_string(),
+        "0:t?".to_string(),
         "-map".to_string(),
-        "-0:s:m:language:rus".to_string(),
+        "0:d?".to_string(),
         "-map_chapters".to_string(),
         "0".to_string(),
         "-map_metadata".to_string(),
         "0".to_string(),
-    ]
+    ]);
+
+    flags
+}
+
+/// Returns output flags that carry the source colour description into the AV1
+/// sequence header and the container's colour elements.
+///
+/// Mastering display and content light level side data are passed on by ffmpeg
+/// itself; SVT-AV1 additionally gets them as encoder parameters. Dolby Vision
+/// configuration records need `-strict unofficial` to be written to MP4.
+pub fn color_flags(color: &ColorInfo) -> Vec<String> {
+    let mut flags = Vec::new();
+    let fields = [
+        ("-color_primaries", &color.primaries),
+        ("-color_trc", &color.transfer),
+        ("-colorspace", &color.matrix),
+        ("-color_range", &color.range),
+    ];
+    for (flag, value) in fields {
+        if let Some(value) = value {
+            flags.push(flag.to_string());
+            flags.push(value.clone());
+        }
+    }
+
+    if color.dolby_vision.is_some() {
+        flags.push("-strict".to_string());
+        flags.push("unofficial".to_string());
+    }
+
+    flags
+}
+
+/// Maximum keyframe interval from the encoding profile, for any encoder
+pub fn keyint_flags(keyint: Option<u32>) -> Vec<String> {
+    match keyint {
+        Some(keyint) => vec!["-g".to_string(), keyint.to_string()],
+        None => Vec::new(),
+    }
 }
 
 /// Returns WebSafe input flags for web sources to handle timestamp issues
@@ -57,3 +115,64 @@ pub fn pad_filter(width: i32, height: i32, is_web_like: bool) -> Option<String>
 pub fn pad_filter_value() -> String {
     "pad=ceil(iw/2)*2:ceil(ih/2)*2,setsar=1".to_string()
 }
+
+/// Filters applied to the source video before encoding: deinterlacing, then
+/// cropping. Comparisons against the source apply them to the source too.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceFilters {
+    pub deinterlace: Option<String>,
+    pub crop: Option<CropRect>,
+}
+
+impl SourceFilters {
+    /// Filters decided for the job by interlace and crop detection
+    pub fn for_job(job: &Job) -> Self {
+        Self {
+            deinterlace: job.interlace.as_ref().and_then(|d| d.filter.clone()),
+            crop: job.crop_detection.as_ref().and_then(|d| d.crop),
+        }
+    }
+
+    /// Filter chain entries in the order they are applied
+    pub fn chain(&self) -> Vec<String> {
+        self.deinterlace
+            .iter()
+            .cloned()
+            .chain(self.crop.map(|c| c.filter()))
+            .collect()
+    }
+
+    /// The chain followed by a comma, to put in front of further filters
+    pub fn prefix(&self) -> String {
+        self.chain()
+            .into_iter()
+            .map(|filter| filter + ",")
+            .collect()
+    }
+
+    /// Frame size after filtering a `width` x `height` source
+    pub fn output_size(&self, width: i32, height: i32) -> (i32, i32) {
+        self.crop.map_or((width, height), |c| (c.width, c.height))
+    }
+}
+
+/// Returns `-vf` with the job's deinterlace and crop filters and the pad
+/// filter, when any applies.
+///
+/// `width` and `height` are the source dimensions; padding is decided on the
+/// cropped size.
+pub fn video_filter_flags(job: &Job, width: i32, height: i32) -> Vec<String> {
+    let source_filters = SourceFilters::for_job(job);
+    let (width, height) = source_filters.output_size(width, height);
+
+    let mut filters = source_filters.chain();
+    if pad_filter(width, height, job.is_web_like).is_some() {
+        filters.push(pad_filter_value());
+    }
+
+    if filters.is_empty() {
+        Vec::new()
+    } else {
+        vec!["-vf".to_string(), filters.join(",")]
+    }
+}
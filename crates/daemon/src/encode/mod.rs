@@ -3,44 +3,209 @@ pub mod common;
 pub mod rav1e;
 pub mod svt;
 
-use crate::config::{DaemonConfig, QualityTier};
+use crate::commands::JobInterrupt;
+use crate::config::DaemonConfig;
 use crate::jobs::{save_job, Job, JobStage};
-use crate::startup::SelectedEncoder;
-use anyhow::Result;
+use crate::probe::ProbeResult;
+use crate::profiles::{table_value, EncodingProfile};
+use crate::size_gate::{EarlyAbortPolicy, SizeGateResult};
+use crate::startup::AvailableEncoder;
+use anyhow::{Context, Result};
 use chrono::{Duration as ChronoDuration, Utc};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::Semaphore;
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+
+/// Error returned when an encode was stopped by a command before ffmpeg finished
+#[derive(Debug, thiserror::Error)]
+#[error("Encode interrupted by {0:?} command")]
+pub struct EncodeInterrupted(pub JobInterrupt);
+
+/// Error returned when an encode was stopped because its projected size fails the size gate
+#[derive(Debug, thiserror::Error)]
+#[error("Encode aborted early at {progress:.1}%: projected output fails the size gate")]
+pub struct EncodeAbortedEarly {
+    pub progress: f64,
+    pub projected_bytes: u64,
+    pub threshold_bytes: u64,
+}
+
+/// Error returned when ffmpeg exits unsuccessfully
+#[derive(Debug, thiserror::Error)]
+#[error("FFmpeg failed with exit code: {exit_code:?}\nStderr:\n{stderr}")]
+pub struct FfmpegFailed {
+    /// `None` when ffmpeg was killed by a signal
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+/// CRF used if a table is somehow empty
+const DEFAULT_CRF: u8 = 20;
+
+/// How a job's video is encoded, apart from the CRF
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoSettings {
+    pub encoder: AvailableEncoder,
+    /// SVT-AV1 `-preset`, libaom `-cpu-used` or rav1e `-speed`
+    pub preset: Option<u8>,
+    /// Extra `-svtav1-params` entries
+    pub svt_params: Vec<String>,
+    pub film_grain: Option<u8>,
+    /// Whether the encoder denoises before synthesising grain; `None` keeps its default
+    pub film_grain_denoise: Option<bool>,
+    pub keyint: Option<u32>,
+}
+
+impl VideoSettings {
+    /// Settings for a source of `height` from the config and the job's encoding profile
+    pub fn new(
+        config: &DaemonConfig,
+        encoder: AvailableEncoder,
+        profile: &EncodingProfile,
+        height: i32,
+    ) -> Self {
+        Self {
+            encoder,
+            preset: profile.preset(config, encoder, height),
+            svt_params: profile.svt_params.clone(),
+            film_grain: profile.film_grain,
+            film_grain_denoise: None,
+            keyint: profile.keyint,
+        }
+    }
+}
 
 pub fn build_command(
     job: &Job,
-    encoder: &SelectedEncoder,
+    probe: &ProbeResult,
+    video: &VideoSettings,
     config: &DaemonConfig,
+    crf: u8,
     output_path: &str,
 ) -> Vec<String> {
-    use crate::startup::AvailableEncoder;
-
-    // Calculate CRF based on video height and bitrate
-    let height = job.video_height.unwrap_or(1080);
-    let bitrate = job.video_bitrate;
-    let crf = select_crf(height, bitrate, config.quality_tier);
+    let track_policy = &config.track_policy;
+    let audio_policy = &config.audio_policy;
 
     // Build command based on encoder type
-    match encoder.encoder {
-        AvailableEncoder::SvtAv1 => {
-            let preset = select_preset(height, config.quality_tier);
-            svt::build_svt_command(job, crf, preset, output_path)
-        }
-        AvailableEncoder::LibaomAv1 => aom::build_aom_command(job, crf, output_path),
-        AvailableEncoder::Librav1e => rav1e::build_rav1e_command(job, crf, output_path),
+    match video.encoder {
+        AvailableEncoder::SvtAv1 => svt::build_svt_command(
+            job,
+            probe,
+            track_policy,
+            audio_policy,
+            video,
+            crf,
+            output_path,
+        ),
+        AvailableEncoder::LibaomAv1 => aom::build_aom_command(
+            job,
+            probe,
+            track_policy,
+            audio_policy,
+            video,
+            crf,
+            output_path,
+        ),
+        AvailableEncoder::Librav1e => rav1e::build_rav1e_command(
+            job,
+            probe,
+            track_policy,
+            audio_policy,
+            video,
+            crf,
+            output_path,
+        ),
     }
 }
 
+/// Video encoder arguments only, with the same settings `build_command` uses
+pub fn video_encoder_args(
+    video: &VideoSettings,
+    probe: &ProbeResult,
+    height: i32,
+    crf: u8,
+) -> Vec<String> {
+    match video.encoder {
+        AvailableEncoder::SvtAv1 => svt::svt_video_args(probe, video, crf),
+        AvailableEncoder::LibaomAv1 => aom::aom_video_args(probe, video, crf, height),
+        AvailableEncoder::Librav1e => rav1e::rav1e_video_args(probe, video, crf),
+    }
+}
+
+/// Encode `length` seconds of the source's main video, starting at `offset`,
+/// with `video_args` from [`video_encoder_args`]. Audio, subtitles and data
+/// streams are left out.
+pub async fn encode_sample(
+    ffmpeg: &str,
+    job: &Job,
+    probe: &ProbeResult,
+    video_args: &[String],
+    (offset, length): (f64, f64),
+    output_path: &Path,
+) -> Result<()> {
+    let video = probe
+        .main_video_stream()
+        .context("Source has no video stream to sample")?;
+    let width = job.video_width.unwrap_or(video.width);
+    let height = job.video_height.unwrap_or(video.height);
+
+    let mut command = tokio::process::Command::new(ffmpeg);
+    command
+        .arg("-hide_banner")
+        .arg("-nostats")
+        .arg("-y")
+        .arg("-ss")
+        .arg(format!("{:.3}", offset))
+        .arg("-t")
+        .arg(format!("{:.3}", length))
+        .arg("-i")
+        .arg(&job.source_path)
+        .arg("-map")
+        .arg(format!("0:{}", video.index))
+        .arg("-an")
+        .arg("-sn")
+        .arg("-dn");
+    command.args(common::video_filter_flags(job, width, height));
+    command.args(video_args).arg(output_path);
+
+    let output = command
+        .output()
+        .await
+        .context("Failed to execute ffmpeg for sample encode")?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(output_path);
+        anyhow::bail!(
+            "Sample encode at {:.0}s failed: {}",
+            offset,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
 pub async fn execute_encode(
     job: &mut Job,
     command: Vec<String>,
     job_state_dir: &std::path::Path,
+) -> Result<PathBuf> {
+    let (_interrupt_tx, interrupt_rx) = watch::channel(None);
+    execute_encode_interruptible(job, command, job_state_dir, interrupt_rx, None).await
+}
+
+/// Run an encode that can be stopped through `interrupt`.
+///
+/// When an interrupt arrives, ffmpeg is killed, the partial output is removed and
+/// an [`EncodeInterrupted`] error is returned. With an `early_abort` policy, the
+/// same happens with an [`EncodeAbortedEarly`] error once the projected output
+/// size clearly fails the size gate.
+pub async fn execute_encode_interruptible(
+    job: &mut Job,
+    command: Vec<String>,
+    job_state_dir: &std::path::Path,
+    mut interrupt: watch::Receiver<Option<JobInterrupt>>,
+    early_abort: Option<EarlyAbortPolicy>,
 ) -> Result<PathBuf> {
     use tokio::io::{AsyncBufReadExt, BufReader};
     use tokio::process::Command;
@@ -52,10 +217,10 @@ pub async fn execute_encode(
         .clone();
 
     // Build the command with progress reporting
-    let mut cmd = Command::new("ffmpeg");
+    let mut cmd = Command::new(&command[0]);
     for arg in ["-progress", "pipe:1", "-nostats"]
         .iter()
-        .map(|s| *s)
+        .copied()
         .chain(command[1..].iter().map(|s| s.as_str()))
     {
         cmd.arg(arg);
@@ -101,7 +266,23 @@ pub async fn execute_encode(
     job.stage = Some(JobStage::Encoding);
     save_job(job, job_state_dir)?;
 
-    while let Some(line) = reader.next_line().await? {
+    loop {
+        let line = tokio::select! {
+            line = reader.next_line() => line?,
+            Ok(()) = interrupt.changed() => {
+                let Some(kind) = *interrupt.borrow() else {
+                    continue;
+                };
+                child.kill().await.ok();
+                stderr_task.abort();
+                std::fs::remove_file(&output_path).ok();
+                return Err(EncodeInterrupted(kind).into());
+            }
+        };
+        let Some(line) = line else {
+            break;
+        };
+
         let trimmed = line.trim();
         if trimmed.is_empty() {
             continue;
@@ -113,10 +294,8 @@ pub async fn execute_encode(
                         out_time_secs = Some(ms as f64 / 1_000_000.0);
                     }
                 }
-                "out_time" => {
-                    if out_time_secs.is_none() {
-                        out_time_secs = parse_out_time(v);
-                    }
+                "out_time" if out_time_secs.is_none() => {
+                    out_time_secs = parse_out_time(v);
                 }
                 "total_size" => {
                     if let Ok(sz) = v.parse::<u64>() {
@@ -137,6 +316,20 @@ pub async fn execute_encode(
         if last_save.elapsed() >= Duration::from_millis(750) {
             update_job_progress(job, out_time_secs, total_size_bytes, speed_x, job_state_dir)?;
             last_save = Instant::now();
+
+            if let Some((projected_bytes, threshold_bytes)) =
+                early_abort.and_then(|policy| projected_size_failure(job, &policy))
+            {
+                child.kill().await.ok();
+                stderr_task.abort();
+                std::fs::remove_file(&output_path).ok();
+                return Err(EncodeAbortedEarly {
+                    progress: job.progress.unwrap_or(0.0),
+                    projected_bytes,
+                    threshold_bytes,
+                }
+                .into());
+            }
         }
     }
 
@@ -155,52 +348,40 @@ pub async fn execute_encode(
         .map_err(|e| anyhow::anyhow!("Failed to read stderr: {}", e))?;
 
     if !status.success() {
-        let error_msg = format!(
-            "FFmpeg failed with exit code: {:?}\nStderr:\n{}",
-            status.code(),
-            stderr_lines.join("\n")
-        );
-        return Err(anyhow::anyhow!(error_msg));
+        return Err(FfmpegFailed {
+            exit_code: status.code(),
+            stderr: stderr_lines.join("\n"),
+        }
+        .into());
     }
 
     Ok(PathBuf::from(output_path))
 }
 
-pub fn select_crf(height: i32, _bitrate: Option<u64>, quality_tier: QualityTier) -> u8 {
-    // Quality-first defaults; lower CRF = higher quality (18 is near-lossless, 23 is high quality)
-    let base_crf: u8 = match height {
-        h if h >= 2160 => 18, // 4K: CRF 18 (near-lossless)
-        h if h >= 1440 => 19, // 1440p: CRF 19
-        h if h >= 1080 => 20, // 1080p: CRF 20
-        _ => 21,              // 720p and below: CRF 21
-    };
-
-    let crf = match quality_tier {
-        QualityTier::High => base_crf,
-        // Maximum quality: 2 steps lower CRF
-        QualityTier::VeryHigh => base_crf.saturating_sub(2),
-    };
-
-    // Don't increase CRF for low bitrate sources - maintain quality
-    // The user wants quality, not size optimization
-    crf
+/// CRF from `crf_tables` for `encoder` and the quality tier, adjusted for
+/// the source bitrate when `bitrate_adjustment` is enabled.
+///
+/// Lower CRF = higher quality and larger output.
+pub fn select_crf(
+    config: &DaemonConfig,
+    encoder: AvailableEncoder,
+    height: i32,
+    bitrate: Option<u64>,
+) -> u8 {
+    let table = config.crf_tables.get(encoder).get(config.quality_tier);
+    // Validation keeps the CRF tables non-empty
+    let crf = table_value(table, height).unwrap_or(DEFAULT_CRF);
+    config.bitrate_adjustment.adjust(crf, height, bitrate)
 }
 
-pub fn select_preset(height: i32, quality_tier: QualityTier) -> u8 {
-    // Ultra-slow presets for maximum quality
-    // Lower preset = slower but higher quality (0 is slowest/best, 13 is fastest/worst)
-    let base_preset = match height {
-        h if h >= 2160 => 1, // 4K: Preset 1 (extremely slow, max quality)
-        h if h >= 1440 => 2, // 1440p: Preset 2
-        h if h >= 1080 => 2, // 1080p: Preset 2
-        _ => 3,              // 720p and below: Preset 3
-    };
-
-    match quality_tier {
-        QualityTier::High => base_preset,
-        // Maximum quality: 2 steps slower for VeryHigh (clamped at 0)
-        QualityTier::VeryHigh => base_preset.saturating_sub(2),
-    }
+/// Speed preset from `preset_tables`; `None` leaves the encoder's default.
+///
+/// Lower presets are slower and give better quality for the same size.
+pub fn select_preset(config: &DaemonConfig, encoder: AvailableEncoder, height: i32) -> Option<u8> {
+    table_value(
+        config.preset_tables.get(encoder).get(config.quality_tier),
+        height,
+    )
 }
 
 fn parse_out_time(val: &str) -> Option<f64> {
@@ -208,12 +389,25 @@ fn parse_out_time(val: &str) -> Option<f64> {
     if parts.len() != 3 {
         return None;
     }
-    let h = parts.get(0)?.parse::<f64>().ok()?;
+    let h = parts.first()?.parse::<f64>().ok()?;
     let m = parts.get(1)?.parse::<f64>().ok()?;
     let s = parts.get(2)?.parse::<f64>().ok()?;
     Some(h * 3600.0 + m * 60.0 + s)
 }
 
+/// Check the job's live size projection against the early-abort policy
+///
+/// Returns the projected size and the threshold it fails.
+fn projected_size_failure(job: &Job, policy: &EarlyAbortPolicy) -> Option<(u64, u64)> {
+    match policy.check(job.progress?, job.encoded_duration?, job.output_est_bytes?)? {
+        SizeGateResult::Fail {
+            new_bytes,
+            threshold_bytes,
+        } => Some((new_bytes, threshold_bytes)),
+        SizeGateResult::Pass { .. } => None,
+    }
+}
+
 fn update_job_progress(
     job: &mut Job,
     out_time_secs: Option<f64>,
@@ -289,6 +483,15 @@ impl JobExecutor {
         self.semaphore.available_permits()
     }
 
+    /// Wait for a free slot and hold it until the returned permit is dropped
+    pub async fn acquire_slot(&self) -> Result<OwnedSemaphorePermit> {
+        self.semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to acquire semaphore permit: {}", e))
+    }
+
     /// Execute a job with concurrency limiting
     /// This will wait until a slot is available before executing
     pub async fn execute_job<F, Fut>(&self, job_fn: F) -> Result<PathBuf>
@@ -317,15 +520,8 @@ impl JobExecutor {
         command: Vec<String>,
         job_state_dir: &std::path::Path,
     ) -> Result<PathBuf> {
-        let command_clone = command.clone();
-        let job_clone = job.clone();
-        let state_dir = job_state_dir.to_path_buf();
-
-        self.execute_job(|| async move {
-            let mut job_mut = job_clone;
-            execute_encode(&mut job_mut, command_clone, &state_dir).await
-        })
-        .await
+        self.execute_job(|| execute_encode(job, command, job_state_dir))
+            .await
     }
 }
 
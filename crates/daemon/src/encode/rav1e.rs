@@ -1,9 +1,27 @@
 // librav1e encoder command builder
 
-use super::common::{pad_filter, pad_filter_value, stream_mapping_flags, websafe_input_flags};
+use super::common::{
+    color_flags, keyint_flags, stream_mapping_flags, video_filter_flags, websafe_input_flags,
+};
+use super::VideoSettings;
+use crate::audio::{audio_codec_flags, plan_audio};
+use crate::config::{AudioPolicy, TrackPolicy};
+use crate::container::OutputContainer;
 use crate::jobs::Job;
+use crate::probe::ProbeResult;
+use crate::subtitles::{plan_subtitles, subtitle_codec_flags};
+use std::path::Path;
 
-pub fn build_rav1e_command(job: &Job, crf: u8, output_path: &str) -> Vec<String> {
+pub fn build_rav1e_command(
+    job: &Job,
+    probe: &ProbeResult,
+    track_policy: &TrackPolicy,
+    audio_policy: &AudioPolicy,
+    video: &VideoSettings,
+    crf: u8,
+    output_path: &str,
+) -> Vec<String> {
+    let container = OutputContainer::from_path(Path::new(output_path));
     let mut command = vec![
         "ffmpeg".to_string(),
         "-hide_banner".to_string(),
@@ -20,27 +38,32 @@ pub fn build_rav1e_command(job: &Job, crf: u8, output_path: &str) -> Vec<String>
     command.push(job.source_path.to_string_lossy().to_string());
 
     // Add stream mapping flags
-    command.extend(stream_mapping_flags());
+    command.extend(stream_mapping_flags(
+        probe,
+        track_policy,
+        audio_policy,
+        container,
+    ));
 
-    // Add pad filter if needed
+    // Add crop and pad filters if needed
     let width = job.video_width.unwrap_or(1920);
     let height = job.video_height.unwrap_or(1080);
-    if let Some(filter_flag) = pad_filter(width, height, job.is_web_like) {
-        command.push(filter_flag);
-        command.push(pad_filter_value());
-    }
+    command.extend(video_filter_flags(job, width, height));
 
     // Add librav1e encoder parameters (fallback, basic settings)
-    command.push("-c:v".to_string());
-    command.push("librav1e".to_string());
-    command.push("-qp".to_string());
-    command.push(crf.to_string());
+    command.extend(rav1e_video_args(probe, video, crf));
 
-    // Copy audio and subtitle streams
-    command.push("-c:a".to_string());
-    command.push("copy".to_string());
-    command.push("-c:s".to_string());
-    command.push("copy".to_string());
+    // Copy or transcode each audio track, and copy or convert each subtitle track
+    command.extend(audio_codec_flags(&plan_audio(
+        probe,
+        track_policy,
+        audio_policy,
+    )));
+    command.extend(subtitle_codec_flags(&plan_subtitles(
+        probe,
+        track_policy,
+        container,
+    )));
 
     // Add max muxing queue size
     command.push("-max_muxing_queue_size".to_string());
@@ -51,3 +74,25 @@ pub fn build_rav1e_command(job: &Job, crf: u8, output_path: &str) -> Vec<String>
 
     command
 }
+
+/// Video encoder arguments for librav1e, including colour metadata
+pub fn rav1e_video_args(probe: &ProbeResult, video: &VideoSettings, crf: u8) -> Vec<String> {
+    let mut args = vec![
+        "-c:v".to_string(),
+        "librav1e".to_string(),
+        "-qp".to_string(),
+        crf.to_string(),
+    ];
+    if let Some(speed) = video.preset {
+        args.push("-speed".to_string());
+        args.push(speed.to_string());
+    }
+    args.extend(keyint_flags(video.keyint));
+
+    // Carry the colour description and HDR metadata
+    if let Some(stream) = probe.main_video_stream() {
+        args.extend(color_flags(&stream.color));
+    }
+
+    args
+}
@@ -1,9 +1,27 @@
 // SVT-AV1 encoder command builder
 
-use super::common::{pad_filter, pad_filter_value, stream_mapping_flags, websafe_input_flags};
+use super::common::{
+    color_flags, keyint_flags, stream_mapping_flags, video_filter_flags, websafe_input_flags,
+};
+use super::VideoSettings;
+use crate::audio::{audio_codec_flags, plan_audio};
+use crate::config::{AudioPolicy, TrackPolicy};
+use crate::container::OutputContainer;
 use crate::jobs::Job;
+use crate::probe::{ColorInfo, DynamicRange, ProbeResult};
+use crate::subtitles::{plan_subtitles, subtitle_codec_flags};
+use std::path::Path;
 
-pub fn build_svt_command(job: &Job, crf: u8, preset: u8, output_path: &str) -> Vec<String> {
+pub fn build_svt_command(
+    job: &Job,
+    probe: &ProbeResult,
+    track_policy: &TrackPolicy,
+    audio_policy: &AudioPolicy,
+    video: &VideoSettings,
+    crf: u8,
+    output_path: &str,
+) -> Vec<String> {
+    let container = OutputContainer::from_path(Path::new(output_path));
     let mut command = vec![
         "ffmpeg".to_string(),
         "-hide_banner".to_string(),
@@ -20,33 +38,32 @@ pub fn build_svt_command(job: &Job, crf: u8, preset: u8, output_path: &str) -> V
     command.push(job.source_path.to_string_lossy().to_string());
 
     // Add stream mapping flags
-    command.extend(stream_mapping_flags());
+    command.extend(stream_mapping_flags(
+        probe,
+        track_policy,
+        audio_policy,
+        container,
+    ));
 
-    // Add pad filter if needed
+    // Add crop and pad filters if needed
     let width = job.video_width.unwrap_or(1920);
     let height = job.video_height.unwrap_or(1080);
-    if let Some(filter_flag) = pad_filter(width, height, job.is_web_like) {
-        command.push(filter_flag);
-        command.push(pad_filter_value());
-    }
+    command.extend(video_filter_flags(job, width, height));
 
     // Add SVT-AV1 encoder parameters
-    command.push("-c:v".to_string());
-    command.push("libsvtav1".to_string());
-    command.push("-crf".to_string());
-    command.push(crf.to_string());
-    command.push("-preset".to_string());
-    command.push(preset.to_string());
-    command.push("-threads".to_string());
-    command.push("0".to_string());
-    command.push("-svtav1-params".to_string());
-    command.push("lp=0".to_string());
-
-    // Copy audio and subtitle streams
-    command.push("-c:a".to_string());
-    command.push("copy".to_string());
-    command.push("-c:s".to_string());
-    command.push("copy".to_string());
+    command.extend(svt_video_args(probe, video, crf));
+
+    // Copy or transcode each audio track, and copy or convert each subtitle track
+    command.extend(audio_codec_flags(&plan_audio(
+        probe,
+        track_policy,
+        audio_policy,
+    )));
+    command.extend(subtitle_codec_flags(&plan_subtitles(
+        probe,
+        track_policy,
+        container,
+    )));
 
     // Add max muxing queue size
     command.push("-max_muxing_queue_size".to_string());
@@ -57,3 +74,67 @@ pub fn build_svt_command(job: &Job, crf: u8, preset: u8, output_path: &str) -> V
 
     command
 }
+
+/// Video encoder arguments for SVT-AV1, including colour and HDR metadata
+pub fn svt_video_args(probe: &ProbeResult, video: &VideoSettings, crf: u8) -> Vec<String> {
+    let mut args = vec![
+        "-c:v".to_string(),
+        "libsvtav1".to_string(),
+        "-crf".to_string(),
+        crf.to_string(),
+    ];
+    if let Some(preset) = video.preset {
+        args.push("-preset".to_string());
+        args.push(preset.to_string());
+    }
+    args.extend([
+        "-threads".to_string(),
+        "0".to_string(),
+        "-svtav1-params".to_string(),
+    ]);
+    let mut svt_params = vec!["lp=0".to_string()];
+    if let Some(stream) = probe.main_video_stream() {
+        svt_params.extend(svt_hdr_params(&stream.color));
+    }
+    if let Some(film_grain) = video.film_grain {
+        svt_params.push(format!("film-grain={}", film_grain));
+        if let Some(denoise) = video.film_grain_denoise {
+            svt_params.push(format!("film-grain-denoise={}", u8::from(denoise)));
+        }
+    }
+    svt_params.extend(video.svt_params.iter().cloned());
+    args.push(svt_params.join(":"));
+    args.extend(keyint_flags(video.keyint));
+
+    // Carry the colour description and HDR metadata
+    if let Some(stream) = probe.main_video_stream() {
+        args.extend(color_flags(&stream.color));
+    }
+
+    args
+}
+
+/// SVT-AV1 parameters that write HDR10 static metadata into the bitstream
+pub fn svt_hdr_params(color: &ColorInfo) -> Vec<String> {
+    if color.dynamic_range() == DynamicRange::Sdr {
+        return Vec::new();
+    }
+
+    let mut params = vec!["enable-hdr=1".to_string()];
+    if let Some(md) = &color.mastering_display {
+        let point = |(x, y): (f64, f64)| format!("({:.4},{:.4})", x, y);
+        params.push(format!(
+            "mastering-display=G{}B{}R{}WP{}L({:.4},{:.4})",
+            point(md.green),
+            point(md.blue),
+            point(md.red),
+            point(md.white_point),
+            md.max_luminance,
+            md.min_luminance
+        ));
+    }
+    if let Some(cll) = &color.content_light_level {
+        params.push(format!("content-light={},{}", cll.max_cll, cll.max_fall));
+    }
+    params
+}
@@ -2,14 +2,15 @@ use crate::config::DaemonConfig;
 use crate::probe::ProbeResult;
 use crate::scan::CandidateFile;
 use crate::sidecars::has_skip_marker;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum GateResult {
     Pass,
     Skip(SkipReason),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SkipReason {
     NoVideo,
     TooSmall,
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::config::GrainDetectionConfig;
+use crate::encode::common::SourceFilters;
+use crate::encode::VideoSettings;
+use crate::probe::ProbeResult;
+use crate::quality::sample_offsets;
+
+/// Grain measured in the source and the film grain synthesis chosen for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrainAnalysis {
+    pub samples: u32,
+    /// Mean RMS luma difference between the source and a denoised copy, on an 8-bit scale
+    pub noise_level: f64,
+    /// Film grain synthesis strength used; 0 when the source is clean
+    pub film_grain: u8,
+    /// Whether the encoder denoised the source before synthesising grain
+    pub denoise: bool,
+}
+
+impl GrainAnalysis {
+    pub fn new(noise_level: f64, samples: u32, config: &GrainDetectionConfig) -> Self {
+        let film_grain = grain_strength(noise_level, config);
+        Self {
+            samples,
+            noise_level,
+            film_grain,
+            denoise: film_grain > 0 && config.denoise,
+        }
+    }
+
+    /// Turn on grain synthesis in `video` when the source is grainy
+    pub fn apply(&self, video: &mut VideoSettings) {
+        if self.film_grain > 0 {
+            video.film_grain = Some(self.film_grain);
+            video.film_grain_denoise = Some(self.denoise);
+        }
+    }
+}
+
+/// Film grain strength for a noise level: 0 below `min_noise`, otherwise
+/// `noise_level * strength_per_noise` between 1 and `max_film_grain`
+pub fn grain_strength(noise_level: f64, config: &GrainDetectionConfig) -> u8 {
+    if noise_level < config.min_noise {
+        return 0;
+    }
+    (noise_level * config.strength_per_noise)
+        .round()
+        .clamp(1.0, config.max_film_grain.max(1) as f64) as u8
+}
+
+/// RMS difference on an 8-bit scale for the PSNR between two 8-bit images
+pub fn noise_from_psnr(psnr: f64) -> f64 {
+    if psnr.is_infinite() {
+        return 0.0;
+    }
+    255.0 / 10_f64.powf(psnr / 20.0)
+}
+
+/// PSNR from ffmpeg's end-of-run `psnr` filter line; identical frames report `inf`
+pub fn parse_noise_psnr(stderr: &str) -> Option<f64> {
+    let psnr_re = Regex::new(r"PSNR .*average:([0-9.]+|inf)").unwrap();
+    match &psnr_re.captures(stderr)?[1] {
+        "inf" => Some(f64::INFINITY),
+        value => value.parse::<f64>().ok(),
+    }
+}
+
+/// Estimate the grain of evenly spaced samples of `source`.
+///
+/// Each sample's luma is compared against an `hqdn3d` denoised copy; film grain
+/// and sensor noise are what the denoiser removes. `source_filters` are applied
+/// first, so combing does not count as noise and black bars do not dilute it.
+pub async fn detect_grain(
+    ffmpeg: &str,
+    source: &Path,
+    probe: &ProbeResult,
+    source_filters: &SourceFilters,
+    config: &GrainDetectionConfig,
+) -> Result<GrainAnalysis> {
+    let video = probe
+        .main_video_stream()
+        .context("Source has no video stream to analyse")?;
+    let duration = probe
+        .format
+        .duration
+        .context("Source duration is unknown")?;
+    let filter = format!(
+        "[0:{}]{}format=gray,split[src][ref];[ref]hqdn3d[den];[src][den]psnr",
+        video.index,
+        source_filters.prefix()
+    );
+
+    let sample_secs = config.sample_secs as f64;
+    let mut levels = Vec::new();
+    for offset in sample_offsets(duration, config.samples, sample_secs) {
+        let result = Command::new(ffmpeg)
+            .arg("-hide_banner")
+            .arg("-nostats")
+            .arg("-ss")
+            .arg(format!("{:.3}", offset))
+            .arg("-t")
+            .arg(format!("{:.3}", sample_secs))
+            .arg("-i")
+            .arg(source)
+            .arg("-lavfi")
+            .arg(&filter)
+            .arg("-f")
+            .arg("null")
+            .arg("-")
+            .output()
+            .await
+            .context("Failed to execute ffmpeg for grain detection")?;
+
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        if !result.status.success() {
+            anyhow::bail!(
+                "Grain detection ffmpeg failed at {:.0}s: {}",
+                offset,
+                stderr.trim()
+            );
+        }
+        let psnr = parse_noise_psnr(&stderr)
+            .with_context(|| format!("No noise measurement for the sample at {:.0}s", offset))?;
+        levels.push(noise_from_psnr(psnr));
+    }
+
+    let noise_level = levels.iter().sum::<f64>() / levels.len() as f64;
+    Ok(GrainAnalysis::new(noise_level, levels.len() as u32, config))
+}
@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::config::DeinterlaceConfig;
+use crate::probe::ProbeResult;
+use crate::quality::sample_offsets;
+
+/// Deinterlacer for interlaced video, keeping one frame per frame
+pub const DEINTERLACE_FILTER: &str = "bwdif=mode=send_frame:parity=auto:deint=all";
+
+/// Inverse telecine: rebuild film frames from matching fields, deinterlace any
+/// that do not match, then drop the duplicate frame of each 3:2 cycle
+pub const INVERSE_TELECINE_FILTER: &str =
+    "fieldmatch,bwdif=mode=send_frame:parity=auto:deint=interlaced,decimate";
+
+/// How the source's frames were scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanType {
+    Progressive,
+    Interlaced,
+    /// Progressive film spread over interlaced fields with 3:2 pulldown
+    Telecined,
+}
+
+impl ScanType {
+    /// Filter chain that makes the source progressive again
+    pub fn filter(self) -> Option<&'static str> {
+        match self {
+            ScanType::Progressive => None,
+            ScanType::Interlaced => Some(DEINTERLACE_FILTER),
+            ScanType::Telecined => Some(INVERSE_TELECINE_FILTER),
+        }
+    }
+}
+
+/// Frame counts from ffmpeg's `idet` filter, summed over the sampled segments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdetCounts {
+    /// Multi-frame detection results
+    pub tff: u64,
+    pub bff: u64,
+    pub progressive: u64,
+    pub undetermined: u64,
+    /// Frames with a repeated top or bottom field
+    pub repeated: u64,
+    /// Frames the repeated field detection looked at
+    pub frames: u64,
+}
+
+impl IdetCounts {
+    /// Fraction of decided frames that are interlaced
+    pub fn interlaced_ratio(&self) -> f64 {
+        let decided = self.tff + self.bff + self.progressive;
+        if decided == 0 {
+            return 0.0;
+        }
+        (self.tff + self.bff) as f64 / decided as f64
+    }
+
+    /// Fraction of frames with a repeated field
+    pub fn repeated_ratio(&self) -> f64 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+        self.repeated as f64 / self.frames as f64
+    }
+
+    pub fn add(&mut self, other: &IdetCounts) {
+        self.tff += other.tff;
+        self.bff += other.bff;
+        self.progressive += other.progressive;
+        self.undetermined += other.undetermined;
+        self.repeated += other.repeated;
+        self.frames += other.frames;
+    }
+
+    /// Interlaced, telecined or progressive, by the thresholds in `config`
+    pub fn scan_type(&self, config: &DeinterlaceConfig) -> ScanType {
+        if self.interlaced_ratio() < config.min_interlaced_ratio {
+            ScanType::Progressive
+        } else if self.repeated_ratio() >= config.min_repeated_ratio {
+            ScanType::Telecined
+        } else {
+            ScanType::Interlaced
+        }
+    }
+}
+
+/// Whether the source is treated as interlaced, and the filter applied for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterlaceDecision {
+    /// Field order ffprobe reported for the main video stream
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field_order: Option<String>,
+    /// `idet` results, when the detection pass ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idet: Option<IdetCounts>,
+    pub scan_type: ScanType,
+    /// Filter chain applied before encoding; `None` for progressive content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+impl InterlaceDecision {
+    /// Decide from the `idet` results when there are any, otherwise from the field order
+    pub fn new(
+        field_order: Option<&str>,
+        idet: Option<IdetCounts>,
+        config: &DeinterlaceConfig,
+    ) -> Self {
+        let scan_type = match &idet {
+            Some(counts) => counts.scan_type(config),
+            None if is_interlaced_field_order(field_order) => ScanType::Interlaced,
+            None => ScanType::Progressive,
+        };
+        Self {
+            field_order: field_order.map(String::from),
+            idet,
+            scan_type,
+            filter: scan_type.filter().map(String::from),
+        }
+    }
+}
+
+/// ffprobe field orders of interlaced video: top or bottom field first, coded
+/// in either order
+pub fn is_interlaced_field_order(field_order: Option<&str>) -> bool {
+    matches!(field_order, Some("tt" | "bb" | "tb" | "bt"))
+}
+
+/// Counts from ffmpeg's end-of-run `idet` lines
+pub fn parse_idet(stderr: &str) -> Option<IdetCounts> {
+    let multi_re = Regex::new(
+        r"Multi frame detection: TFF:\s*(\d+)\s+BFF:\s*(\d+)\s+Progressive:\s*(\d+)\s+Undetermined:\s*(\d+)",
+    )
+    .unwrap();
+    let repeated_re =
+        Regex::new(r"Repeated Fields: Neither:\s*(\d+)\s+Top:\s*(\d+)\s+Bottom:\s*(\d+)").unwrap();
+
+    let multi = multi_re.captures_iter(stderr).last()?;
+    let count = |caps: &regex::Captures, i: usize| caps[i].parse::<u64>().ok();
+    let (neither, top, bottom) = match repeated_re.captures_iter(stderr).last() {
+        Some(caps) => (count(&caps, 1)?, count(&caps, 2)?, count(&caps, 3)?),
+        None => (0, 0, 0),
+    };
+
+    Some(IdetCounts {
+        tff: count(&multi, 1)?,
+        bff: count(&multi, 2)?,
+        progressive: count(&multi, 3)?,
+        undetermined: count(&multi, 4)?,
+        repeated: top + bottom,
+        frames: neither + top + bottom,
+    })
+}
+
+/// Run `idet` over evenly spaced samples of `source` and sum the counts
+pub async fn detect_interlacing(
+    ffmpeg: &str,
+    source: &Path,
+    probe: &ProbeResult,
+    config: &DeinterlaceConfig,
+) -> Result<IdetCounts> {
+    let video = probe
+        .main_video_stream()
+        .context("Source has no video stream to analyse")?;
+    let duration = probe
+        .format
+        .duration
+        .context("Source duration is unknown")?;
+    let filter = format!("[0:{}]idet", video.index);
+
+    let sample_secs = config.sample_secs as f64;
+    let mut total = IdetCounts::default();
+    for offset in sample_offsets(duration, config.samples, sample_secs) {
+        let result = Command::new(ffmpeg)
+            .arg("-hide_banner")
+            .arg("-nostats")
+            .arg("-ss")
+            .arg(format!("{:.3}", offset))
+            .arg("-t")
+            .arg(format!("{:.3}", sample_secs))
+            .arg("-i")
+            .arg(source)
+            .arg("-lavfi")
+            .arg(&filter)
+            .arg("-f")
+            .arg("null")
+            .arg("-")
+            .output()
+            .await
+            .context("Failed to execute ffmpeg for interlace detection")?;
+
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        if !result.status.success() {
+            anyhow::bail!(
+                "Interlace detection ffmpeg failed at {:.0}s: {}",
+                offset,
+                stderr.trim()
+            );
+        }
+        let counts = parse_idet(&stderr)
+            .with_context(|| format!("No idet results for the sample at {:.0}s", offset))?;
+        total.add(&counts);
+    }
+
+    Ok(total)
+}
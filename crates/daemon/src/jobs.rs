@@ -5,8 +5,16 @@ use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 use crate::classify::SourceClassification;
+use crate::crf_search::CrfSearchTrace;
+use crate::crop::CropDetection;
+use crate::grain::GrainAnalysis;
+use crate::interlace::InterlaceDecision;
+use crate::overrides::EffectiveConfig;
+use crate::predict::SavingsPrediction;
 use crate::probe::ProbeResult;
+use crate::quality::QualityScores;
 use crate::scan::CandidateFile;
+use crate::subtitles::SubtitleTrack;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
@@ -42,6 +50,9 @@ pub struct Job {
     pub crf_used: Option<u8>,
     pub preset_used: Option<u8>,
     pub encoder_used: Option<String>,
+    // Encoding profile picked by profile_rules (none for the built-in settings)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_profile: Option<String>,
 
     // Additional metadata
     pub source_bit_depth: Option<u8>,
@@ -80,6 +91,42 @@ pub struct Job {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub original_duration: Option<f64>,
+
+    // Scheduling (higher runs first; set via the command directory)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+
+    // Sampled quality of the output against the source (when the quality check is enabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality_scores: Option<QualityScores>,
+
+    // Candidates tried by the CRF search; the chosen value is also in crf_used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crf_search: Option<CrfSearchTrace>,
+
+    // Output size predicted from sample encodes, to compare with new_bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub savings_prediction: Option<SavingsPrediction>,
+
+    // Settings the job was prepared with, after per-directory overrides
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_config: Option<EffectiveConfig>,
+
+    // Source noise and the film grain synthesis it selected (when grain detection is enabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grain_analysis: Option<GrainAnalysis>,
+
+    // Black bars found by crop detection and the crop applied to the encode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crop_detection: Option<CropDetection>,
+
+    // Whether the source was treated as interlaced or telecined, and the filter applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interlace: Option<InterlaceDecision>,
+
+    // How each kept subtitle track was written, dropped or extracted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitles: Option<Vec<SubtitleTrack>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -111,15 +158,8 @@ pub fn create_job(
     // Extract metadata from the main video stream
     let main_video = probe.main_video_stream();
 
-    // Detect HDR from pixel format
-    let is_hdr = main_video.and_then(|v| v.pix_fmt.as_ref()).map(|fmt| {
-        fmt.contains("p010")
-            || fmt.contains("p016")
-            || fmt.contains("yuv420p10")
-            || fmt.contains("yuv422p10")
-            || fmt.contains("yuv444p10")
-            || fmt.contains("yuv420p12")
-    });
+    // HDR is signalled by the transfer function or a Dolby Vision record, not the bit depth
+    let is_hdr = main_video.map(|v| v.color.hdr_format().is_some());
 
     Job {
         id: Uuid::new_v4().to_string(),
@@ -144,6 +184,7 @@ pub fn create_job(
         crf_used: None,
         preset_used: None,
         encoder_used: None,
+        encoding_profile: None,
         source_bit_depth: main_video.and_then(|v| v.bit_depth),
         source_pix_fmt: main_video.and_then(|v| v.pix_fmt.clone()),
         is_hdr,
@@ -161,6 +202,15 @@ pub fn create_job(
         output_est_bytes: None,
         speed_bps: None,
         original_duration: probe.format.duration,
+        priority: None,
+        quality_scores: None,
+        crf_search: None,
+        savings_prediction: None,
+        effective_config: None,
+        grain_analysis: None,
+        crop_detection: None,
+        interlace: None,
+        subtitles: None,
     }
 }
 
@@ -190,6 +240,16 @@ pub fn save_job(job: &Job, state_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Load the job with `job_id`, if its file exists
+pub fn load_job(state_dir: &Path, job_id: &str) -> Result<Option<Job>> {
+    let job_file = state_dir.join(format!("{}.json", job_id));
+    if !job_file.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(&job_file)?;
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
 pub fn load_all_jobs(state_dir: &Path) -> Result<Vec<Job>> {
     use std::fs;
 
@@ -262,3 +322,25 @@ pub fn update_job_status(job: &mut Job, status: JobStatus, state_dir: &Path) ->
 
     Ok(())
 }
+
+/// Reset a job so it is picked up again on the next scan cycle.
+///
+/// Clears run results and live progress but keeps identity, metadata and priority.
+pub fn reset_for_requeue(job: &mut Job) {
+    job.status = JobStatus::Pending;
+    job.reason = None;
+    job.started_at = None;
+    job.finished_at = None;
+    job.output_path = None;
+    job.new_bytes = None;
+    job.stage = None;
+    job.encoded_bytes = None;
+    job.encoded_duration = None;
+    job.progress = None;
+    job.eta = None;
+    job.output_est_bytes = None;
+    job.speed_bps = None;
+    job.quality_scores = None;
+    job.crf_search = None;
+    job.savings_prediction = None;
+}
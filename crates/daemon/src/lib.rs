@@ -1,19 +1,37 @@
 // Core daemon library modules
 
+pub mod audio;
 pub mod classify;
+pub mod commands;
 pub mod config;
+pub mod container;
+pub mod crf_search;
+pub mod crop;
 pub mod daemon_loop;
 pub mod encode;
 pub mod gates;
+pub mod grain;
+pub mod interlace;
 pub mod jobs;
+pub mod overrides;
+pub mod predict;
 pub mod probe;
+pub mod profiles;
+pub mod quality;
 pub mod replace;
+pub mod retry;
 pub mod scan;
+pub mod scan_cache;
+pub mod scan_filter;
 pub mod sidecars;
 pub mod size_gate;
 pub mod stable;
 pub mod startup;
+pub mod subtitles;
+pub mod tracks;
 pub mod validate;
+pub mod verify;
+pub mod watch;
 
 // Re-export commonly used types
 pub use config::DaemonConfig;
@@ -0,0 +1,287 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::{
+    validate_config, CrfTables, DaemonConfig, EncoderPreference, PresetTables, QualityTier,
+    TierTables, TrackPolicy,
+};
+use crate::profiles::ResolutionStep;
+
+/// Name of the per-directory override file
+pub const OVERRIDE_FILE_NAME: &str = ".av1d.toml";
+
+/// Settings an `.av1d.toml` file changes for every file below its directory.
+///
+/// Deeper files win over shallower ones, setting by setting. A `[track_policy]`
+/// table replaces the inherited policy as a whole; `[crf_tables]` and
+/// `[preset_tables]` replace single tables, per encoder and tier.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DirectoryOverrides {
+    /// Never touch files below this directory (no jobs, markers or sidecars)
+    pub skip: Option<bool>,
+    pub quality_tier: Option<QualityTier>,
+    pub prefer_encoder: Option<EncoderPreference>,
+    pub min_bytes: Option<u64>,
+    pub max_size_ratio: Option<f64>,
+    pub track_policy: Option<TrackPolicy>,
+    pub crf_tables: EncoderTableOverrides,
+    pub preset_tables: EncoderTableOverrides,
+}
+
+/// `[crf_tables]` or `[preset_tables]` of an override file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncoderTableOverrides {
+    pub svt: TierTableOverrides,
+    pub aom: TierTableOverrides,
+    pub rav1e: TierTableOverrides,
+}
+
+impl EncoderTableOverrides {
+    fn merge(&mut self, inner: EncoderTableOverrides) {
+        self.svt.merge(inner.svt);
+        self.aom.merge(inner.aom);
+        self.rav1e.merge(inner.rav1e);
+    }
+
+    fn apply(&self, svt: &mut TierTables, aom: &mut TierTables, rav1e: &mut TierTables) {
+        self.svt.apply(svt);
+        self.aom.apply(aom);
+        self.rav1e.apply(rav1e);
+    }
+}
+
+/// Tables of one encoder; a tier without a table keeps the inherited one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TierTableOverrides {
+    pub high: Option<Vec<ResolutionStep>>,
+    pub very_high: Option<Vec<ResolutionStep>>,
+}
+
+impl TierTableOverrides {
+    fn merge(&mut self, inner: TierTableOverrides) {
+        if inner.high.is_some() {
+            self.high = inner.high;
+        }
+        if inner.very_high.is_some() {
+            self.very_high = inner.very_high;
+        }
+    }
+
+    fn apply(&self, tables: &mut TierTables) {
+        if let Some(high) = &self.high {
+            tables.high = high.clone();
+        }
+        if let Some(very_high) = &self.very_high {
+            tables.very_high = very_high.clone();
+        }
+    }
+}
+
+impl DirectoryOverrides {
+    /// Layer the overrides of a deeper directory on top of these
+    pub fn merge(&mut self, inner: DirectoryOverrides) {
+        self.skip = inner.skip.or(self.skip);
+        self.quality_tier = inner.quality_tier.or(self.quality_tier);
+        self.prefer_encoder = inner.prefer_encoder.or(self.prefer_encoder);
+        self.min_bytes = inner.min_bytes.or(self.min_bytes);
+        self.max_size_ratio = inner.max_size_ratio.or(self.max_size_ratio);
+        if inner.track_policy.is_some() {
+            self.track_policy = inner.track_policy;
+        }
+        self.crf_tables.merge(inner.crf_tables);
+        self.preset_tables.merge(inner.preset_tables);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `config` with these overrides applied
+    pub fn apply(&self, config: &DaemonConfig) -> DaemonConfig {
+        let mut config = config.clone();
+        if let Some(quality_tier) = self.quality_tier {
+            config.quality_tier = quality_tier;
+        }
+        if let Some(prefer_encoder) = self.prefer_encoder {
+            config.prefer_encoder = prefer_encoder;
+        }
+        if let Some(min_bytes) = self.min_bytes {
+            config.min_bytes = min_bytes;
+        }
+        if let Some(max_size_ratio) = self.max_size_ratio {
+            config.max_size_ratio = max_size_ratio;
+        }
+        if let Some(track_policy) = &self.track_policy {
+            config.track_policy = track_policy.clone();
+        }
+        let crf = &mut config.crf_tables;
+        self.crf_tables
+            .apply(&mut crf.svt, &mut crf.aom, &mut crf.rav1e);
+        let preset = &mut config.preset_tables;
+        self.preset_tables
+            .apply(&mut preset.svt, &mut preset.aom, &mut preset.rav1e);
+        config
+    }
+}
+
+/// Read the override file in `dir`, if there is one
+pub fn load_overrides(dir: &Path) -> Result<Option<DirectoryOverrides>> {
+    let path = dir.join(OVERRIDE_FILE_NAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let overrides =
+        toml::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(overrides))
+}
+
+/// The config that applies to one file
+#[derive(Debug, Clone)]
+pub struct FileConfig {
+    pub config: Arc<DaemonConfig>,
+    /// Files below a `skip = true` override are left alone
+    pub skip: bool,
+    /// Override files applied, outermost first
+    pub override_files: Vec<PathBuf>,
+}
+
+/// Settings a job was prepared with, after directory overrides
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectiveConfig {
+    pub quality_tier: QualityTier,
+    pub prefer_encoder: EncoderPreference,
+    pub min_bytes: u64,
+    pub max_size_ratio: f64,
+    pub track_policy: TrackPolicy,
+    #[serde(default)]
+    pub crf_tables: CrfTables,
+    #[serde(default)]
+    pub preset_tables: PresetTables,
+    /// `.av1d.toml` files applied, outermost first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub override_files: Vec<PathBuf>,
+}
+
+impl EffectiveConfig {
+    pub fn new(file_config: &FileConfig) -> Self {
+        let config = file_config.config.as_ref();
+        Self {
+            quality_tier: config.quality_tier,
+            prefer_encoder: config.prefer_encoder,
+            min_bytes: config.min_bytes,
+            max_size_ratio: config.max_size_ratio,
+            track_policy: config.track_policy.clone(),
+            crf_tables: config.crf_tables.clone(),
+            preset_tables: config.preset_tables.clone(),
+            override_files: file_config.override_files.clone(),
+        }
+    }
+}
+
+/// Resolves the config for files under the library roots.
+///
+/// Each directory is resolved once per resolver, so a resolver should live for
+/// one scan and pick up edits to override files on the next.
+pub struct OverrideResolver {
+    base: Arc<DaemonConfig>,
+    /// Parsed override file per directory; errors are kept as messages
+    files: HashMap<PathBuf, std::result::Result<Option<DirectoryOverrides>, String>>,
+    /// Resolved config per directory
+    dirs: HashMap<PathBuf, std::result::Result<FileConfig, String>>,
+}
+
+impl OverrideResolver {
+    pub fn new(base: Arc<DaemonConfig>) -> Self {
+        Self {
+            base,
+            files: HashMap::new(),
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Config for `path`, with the override files from its library root down to
+    /// its directory applied
+    pub fn resolve(&mut self, path: &Path) -> Result<FileConfig> {
+        let dir = path.parent().unwrap_or(path).to_path_buf();
+        if !self.dirs.contains_key(&dir) {
+            let resolved = self.resolve_dir(&dir).map_err(|e| format!("{:#}", e));
+            self.dirs.insert(dir.clone(), resolved);
+        }
+        match &self.dirs[&dir] {
+            Ok(file_config) => Ok(file_config.clone()),
+            Err(e) => anyhow::bail!("{}", e),
+        }
+    }
+
+    fn resolve_dir(&mut self, dir: &Path) -> Result<FileConfig> {
+        let root = self
+            .base
+            .library_roots
+            .iter()
+            .filter(|root| dir.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .cloned();
+        let mut dirs: Vec<PathBuf> = dir
+            .ancestors()
+            .take_while(|dir| root.as_ref().is_none_or(|root| dir.starts_with(root)))
+            .map(Path::to_path_buf)
+            .collect();
+        if root.is_none() {
+            dirs.truncate(1);
+        }
+        dirs.reverse();
+
+        let mut merged = DirectoryOverrides::default();
+        let mut override_files = Vec::new();
+        for ancestor in dirs {
+            let loaded = self
+                .files
+                .entry(ancestor.clone())
+                .or_insert_with(|| load_overrides(&ancestor).map_err(|e| format!("{:#}", e)));
+            match loaded {
+                Ok(Some(overrides)) => {
+                    merged.merge(overrides.clone());
+                    override_files.push(ancestor.join(OVERRIDE_FILE_NAME));
+                }
+                Ok(None) => {}
+                Err(e) => anyhow::bail!("{}", e),
+            }
+        }
+
+        let config = if merged.is_empty() {
+            self.base.clone()
+        } else {
+            let config = merged.apply(&self.base);
+            validate_config(&config).with_context(|| {
+                format!(
+                    "Invalid settings in {}",
+                    override_files_list(&override_files)
+                )
+            })?;
+            Arc::new(config)
+        };
+
+        Ok(FileConfig {
+            config,
+            skip: merged.skip.unwrap_or(false),
+            override_files,
+        })
+    }
+}
+
+fn override_files_list(files: &[PathBuf]) -> String {
+    files
+        .iter()
+        .map(|f| f.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::debug;
+
+use crate::audio::AudioOutput;
+use crate::config::SavingsPredictionConfig;
+use crate::encode::encode_sample;
+use crate::jobs::Job;
+use crate::probe::ProbeResult;
+use crate::quality::sample_offsets;
+
+/// Output size projected from sample encodes before the full encode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavingsPrediction {
+    pub samples: u32,
+    /// Seconds of video encoded across all samples
+    pub sampled_secs: f64,
+    pub sample_bytes: u64,
+    /// Bytes outside the video stream: the source's, with audio tracks the audio
+    /// plan transcodes or drops swapped for their planned size. Zero when the
+    /// source does not report its video bitrate.
+    pub other_bytes: u64,
+    pub predicted_bytes: u64,
+    /// Predicted output size as a fraction of the original
+    pub predicted_ratio: f64,
+}
+
+impl SavingsPrediction {
+    /// Skip reason when the prediction reaches `max_size_ratio * margin`
+    pub fn gate_failure(&self, max_size_ratio: f64, margin: f64) -> Option<String> {
+        let threshold = max_size_ratio * margin;
+        (self.predicted_ratio >= threshold).then(|| {
+            format!(
+                "Size gate predicted to fail: {} samples ({:.0}s of video) encoded to {} bytes, \
+                 projecting {} bytes ({:.1}% of original, {} bytes of audio/subtitles/other); \
+                 limit is {:.1}% (max_size_ratio {} x margin {})",
+                self.samples,
+                self.sampled_secs,
+                self.sample_bytes,
+                self.predicted_bytes,
+                self.predicted_ratio * 100.0,
+                self.other_bytes,
+                threshold * 100.0,
+                max_size_ratio,
+                margin
+            )
+        })
+    }
+}
+
+/// Project the full output size from `(seconds, bytes)` of each sample encode.
+///
+/// The sampled video bitrate is applied to the whole duration. Everything in
+/// the source that is not video is added back, except that audio tracks with a
+/// known bitrate are counted as `audio` writes them: transcoded tracks at their
+/// target bitrate, dropped tracks not at all. Tracks without a bitrate, and
+/// subtitles, are assumed to be copied unchanged.
+pub fn extrapolate(
+    probe: &ProbeResult,
+    audio: &[AudioOutput],
+    samples: &[(f64, u64)],
+) -> Result<SavingsPrediction> {
+    let duration = probe
+        .format
+        .duration
+        .context("Source duration is unknown")?;
+    let original_bytes = probe.format.size;
+    let sampled_secs: f64 = samples.iter().map(|(secs, _)| secs).sum();
+    let sample_bytes: u64 = samples.iter().map(|(_, bytes)| bytes).sum();
+    if sampled_secs <= 0.0 || original_bytes == 0 {
+        anyhow::bail!("Nothing to extrapolate from");
+    }
+
+    let stream_bytes = |bitrate: u64| (bitrate as f64 * duration / 8.0) as u64;
+    let source_video_bytes = probe
+        .main_video_stream()
+        .and_then(|v| v.bitrate)
+        .map(stream_bytes);
+    let other_bytes = source_video_bytes
+        .map(|video| {
+            let mut other = original_bytes.saturating_sub(video);
+            for stream in &probe.audio_streams {
+                let Some(bitrate) = stream.bitrate else {
+                    continue;
+                };
+                let planned: u64 = audio
+                    .iter()
+                    .filter(|output| output.source_index == stream.index)
+                    .map(|output| match output.transcode {
+                        Some(transcode) => stream_bytes(transcode.bitrate_kbps as u64 * 1000),
+                        None => stream_bytes(bitrate),
+                    })
+                    .sum();
+                other = other.saturating_sub(stream_bytes(bitrate)) + planned;
+            }
+            other
+        })
+        .unwrap_or(0);
+
+    let predicted_video = sample_bytes as f64 / sampled_secs * duration;
+    let predicted_bytes = other_bytes + predicted_video as u64;
+
+    Ok(SavingsPrediction {
+        samples: samples.len() as u32,
+        sampled_secs,
+        sample_bytes,
+        other_bytes,
+        predicted_bytes,
+        predicted_ratio: predicted_bytes as f64 / original_bytes as f64,
+    })
+}
+
+/// Encode evenly spaced samples with `video_args` and predict the output size.
+///
+/// Sample encodes are written to `work_dir` as `<job id>.predict.<i>.mkv` and
+/// removed once measured.
+pub async fn predict_savings(
+    ffmpeg: &str,
+    job: &Job,
+    probe: &ProbeResult,
+    audio: &[AudioOutput],
+    video_args: &[String],
+    config: &SavingsPredictionConfig,
+    work_dir: &Path,
+) -> Result<SavingsPrediction> {
+    let duration = probe
+        .format
+        .duration
+        .context("Source duration is unknown")?;
+    let sample_secs = config.sample_secs as f64;
+
+    let mut samples = Vec::new();
+    for (i, offset) in sample_offsets(duration, config.samples, sample_secs)
+        .into_iter()
+        .enumerate()
+    {
+        let sample_path = work_dir.join(format!("{}.predict.{}.mkv", job.id, i));
+        let encoded = encode_sample(
+            ffmpeg,
+            job,
+            probe,
+            video_args,
+            (offset, sample_secs),
+            &sample_path,
+        )
+        .await;
+        let size = std::fs::metadata(&sample_path).map(|m| m.len());
+        let _ = std::fs::remove_file(&sample_path);
+        encoded?;
+
+        let bytes = size.context("Sample encode produced no output")?;
+        let secs = sample_secs.min(duration - offset);
+        debug!(
+            "Job {}: sample {} ({:.0}s at {:.0}s) encoded to {} bytes",
+            job.id, i, secs, offset, bytes
+        );
+        samples.push((secs, bytes));
+    }
+
+    extrapolate(probe, audio, &samples)
+}
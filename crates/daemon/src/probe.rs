@@ -3,6 +3,13 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 use tokio::process::Command;
 
+/// Version of what `ProbeResult` records; stored probe results with another
+/// version are probed again.
+///
+/// Bump this whenever `ProbeResult` or the types inside it gain, lose or
+/// reinterpret a field, even one with a serde default.
+pub const PROBE_SCHEMA_VERSION: u32 = 1;
+
 // Public API types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ProbeResult {
@@ -10,6 +17,11 @@ pub struct ProbeResult {
     pub video_streams: Vec<VideoStream>,
     pub audio_streams: Vec<AudioStream>,
     pub subtitle_streams: Vec<SubtitleStream>,
+    #[serde(default)]
+    pub chapters: usize,
+    /// Attached files, e.g. fonts for ASS subtitles in Matroska
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
 }
 
 impl ProbeResult {
@@ -37,6 +49,144 @@ pub struct VideoStream {
     pub pix_fmt: Option<String>,
     pub bit_depth: Option<u8>,
     pub is_default: bool,
+    #[serde(default)]
+    pub color: ColorInfo,
+    /// "progressive", or "tt"/"bb"/"tb"/"bt" for interlaced video; `None` when unknown
+    #[serde(default)]
+    pub field_order: Option<String>,
+    /// Frame count from the container (`nb_frames` or Matroska statistics tags)
+    #[serde(default)]
+    pub frame_count: Option<u64>,
+}
+
+/// Colour description and HDR metadata of a video stream
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ColorInfo {
+    /// ffmpeg names, e.g. "bt2020"
+    pub primaries: Option<String>,
+    /// e.g. "smpte2084" (PQ) or "arib-std-b67" (HLG)
+    pub transfer: Option<String>,
+    /// Matrix coefficients, e.g. "bt2020nc"
+    pub matrix: Option<String>,
+    /// "tv" (limited) or "pc" (full)
+    pub range: Option<String>,
+    pub mastering_display: Option<MasteringDisplay>,
+    pub content_light_level: Option<ContentLightLevel>,
+    pub dolby_vision: Option<DolbyVisionConfig>,
+}
+
+/// SMPTE ST 2086 mastering display colour volume
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MasteringDisplay {
+    /// CIE 1931 xy chromaticity coordinates
+    pub red: (f64, f64),
+    pub green: (f64, f64),
+    pub blue: (f64, f64),
+    pub white_point: (f64, f64),
+    /// cd/m²
+    pub min_luminance: f64,
+    pub max_luminance: f64,
+}
+
+/// MaxCLL / MaxFALL in cd/m²
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContentLightLevel {
+    pub max_cll: u32,
+    pub max_fall: u32,
+}
+
+/// Dolby Vision decoder configuration record (dvcC/dvvC)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DolbyVisionConfig {
+    pub profile: u8,
+    pub level: u8,
+    pub rpu_present: bool,
+    pub el_present: bool,
+    pub bl_present: bool,
+    /// 0 = none (profile 5), 1 = HDR10, 2 = SDR, 4 = HLG, 6 = Blu-ray HDR10
+    pub bl_signal_compatibility_id: u8,
+}
+
+impl DolbyVisionConfig {
+    /// Whether players without Dolby Vision can show the base layer correctly
+    pub fn has_compatible_base_layer(&self) -> bool {
+        self.bl_present && self.bl_signal_compatibility_id != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HdrFormat {
+    Hdr10,
+    Hlg,
+    DolbyVision,
+}
+
+/// Transfer function family, which decides how a player maps the picture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynamicRange {
+    Sdr,
+    Pq,
+    Hlg,
+}
+
+impl ColorInfo {
+    pub fn dynamic_range(&self) -> DynamicRange {
+        match self.transfer.as_deref() {
+            Some("smpte2084") => DynamicRange::Pq,
+            Some("arib-std-b67") => DynamicRange::Hlg,
+            _ => DynamicRange::Sdr,
+        }
+    }
+
+    pub fn hdr_format(&self) -> Option<HdrFormat> {
+        if self.dolby_vision.is_some() {
+            return Some(HdrFormat::DolbyVision);
+        }
+        match self.dynamic_range() {
+            DynamicRange::Pq => Some(HdrFormat::Hdr10),
+            DynamicRange::Hlg => Some(HdrFormat::Hlg),
+            DynamicRange::Sdr => None,
+        }
+    }
+
+    /// Short description for logs and validation errors, e.g. "HDR10 (bt2020/smpte2084/bt2020nc)"
+    pub fn describe(&self) -> String {
+        let kind = match self.hdr_format() {
+            Some(HdrFormat::DolbyVision) => match self.dolby_vision {
+                Some(dv) => format!("Dolby Vision profile {}", dv.profile),
+                None => "Dolby Vision".to_string(),
+            },
+            Some(HdrFormat::Hdr10) => "HDR10".to_string(),
+            Some(HdrFormat::Hlg) => "HLG".to_string(),
+            None => "SDR".to_string(),
+        };
+        let field = |v: &Option<String>| v.clone().unwrap_or_else(|| "unknown".to_string());
+        format!(
+            "{} ({}/{}/{})",
+            kind,
+            field(&self.primaries),
+            field(&self.transfer),
+            field(&self.matrix)
+        )
+    }
+
+    /// Fill in metadata carried as side data (stream or first-frame level)
+    fn apply_side_data(&mut self, side_data: &[FfprobeSideData]) {
+        for entry in side_data {
+            match entry.side_data_type.as_str() {
+                "DOVI configuration record" if self.dolby_vision.is_none() => {
+                    self.dolby_vision = entry.dolby_vision();
+                }
+                "Mastering display metadata" if self.mastering_display.is_none() => {
+                    self.mastering_display = entry.mastering_display();
+                }
+                "Content light level metadata" if self.content_light_level.is_none() => {
+                    self.content_light_level = entry.content_light_level();
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -44,6 +194,17 @@ pub struct AudioStream {
     pub index: usize,
     pub codec_name: String,
     pub language: Option<String>,
+    pub title: Option<String>,
+    pub is_default: bool,
+    pub is_commentary: bool,
+    #[serde(default)]
+    pub channels: Option<u32>,
+    /// ffprobe channel layout name, e.g. `5.1(side)`
+    #[serde(default)]
+    pub channel_layout: Option<String>,
+    /// Bits per second; often missing for Matroska sources
+    #[serde(default)]
+    pub bitrate: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -51,6 +212,33 @@ pub struct SubtitleStream {
     pub index: usize,
     pub codec_name: String,
     pub language: Option<String>,
+    pub title: Option<String>,
+    pub is_default: bool,
+    pub is_commentary: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Attachment {
+    pub index: usize,
+    pub filename: Option<String>,
+    pub mimetype: Option<String>,
+}
+
+impl Attachment {
+    /// Whether the attachment is a font, by MIME type or file extension
+    pub fn is_font(&self) -> bool {
+        let mimetype = self.mimetype.as_deref().unwrap_or_default().to_lowercase();
+        let extension = self
+            .filename
+            .as_deref()
+            .and_then(|f| Path::new(f).extension())
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        mimetype.contains("font")
+            || mimetype.contains("truetype")
+            || mimetype.contains("opentype")
+            || matches!(extension.as_str(), "ttf" | "otf" | "ttc" | "woff" | "woff2")
+    }
 }
 
 // Internal FFprobe JSON structures
@@ -58,6 +246,7 @@ pub struct SubtitleStream {
 struct FfprobeOutput {
     format: Option<FfprobeFormat>,
     streams: Option<Vec<FfprobeStream>>,
+    chapters: Option<Vec<serde::de::IgnoredAny>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,6 +260,8 @@ struct FfprobeFormat {
 struct FfprobeStream {
     index: usize,
     codec_type: String,
+    // Missing for attachments of unknown type
+    #[serde(default)]
     codec_name: String,
     width: Option<i32>,
     height: Option<i32>,
@@ -78,18 +269,108 @@ struct FfprobeStream {
     r_frame_rate: Option<String>,
     pix_fmt: Option<String>,
     bits_per_raw_sample: Option<String>,
+    color_range: Option<String>,
+    color_space: Option<String>,
+    color_transfer: Option<String>,
+    color_primaries: Option<String>,
+    field_order: Option<String>,
+    nb_frames: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    side_data_list: Option<Vec<FfprobeSideData>>,
     disposition: Option<FfprobeDisposition>,
     tags: Option<FfprobeTags>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FfprobeSideData {
+    side_data_type: String,
+    // DOVI configuration record
+    dv_profile: Option<u8>,
+    dv_level: Option<u8>,
+    rpu_present_flag: Option<u8>,
+    el_present_flag: Option<u8>,
+    bl_present_flag: Option<u8>,
+    dv_bl_signal_compatibility_id: Option<u8>,
+    // Mastering display metadata (rationals such as "34000/50000")
+    red_x: Option<String>,
+    red_y: Option<String>,
+    green_x: Option<String>,
+    green_y: Option<String>,
+    blue_x: Option<String>,
+    blue_y: Option<String>,
+    white_point_x: Option<String>,
+    white_point_y: Option<String>,
+    min_luminance: Option<String>,
+    max_luminance: Option<String>,
+    // Content light level metadata
+    max_content: Option<u32>,
+    max_average: Option<u32>,
+}
+
+impl FfprobeSideData {
+    fn dolby_vision(&self) -> Option<DolbyVisionConfig> {
+        Some(DolbyVisionConfig {
+            profile: self.dv_profile?,
+            level: self.dv_level.unwrap_or(0),
+            rpu_present: self.rpu_present_flag == Some(1),
+            el_present: self.el_present_flag == Some(1),
+            bl_present: self.bl_present_flag == Some(1),
+            bl_signal_compatibility_id: self.dv_bl_signal_compatibility_id.unwrap_or(0),
+        })
+    }
+
+    fn mastering_display(&self) -> Option<MasteringDisplay> {
+        let point = |x: &Option<String>, y: &Option<String>| {
+            Some((
+                parse_rational(x.as_deref()?)?,
+                parse_rational(y.as_deref()?)?,
+            ))
+        };
+        Some(MasteringDisplay {
+            red: point(&self.red_x, &self.red_y)?,
+            green: point(&self.green_x, &self.green_y)?,
+            blue: point(&self.blue_x, &self.blue_y)?,
+            white_point: point(&self.white_point_x, &self.white_point_y)?,
+            min_luminance: parse_rational(self.min_luminance.as_deref()?)?,
+            max_luminance: parse_rational(self.max_luminance.as_deref()?)?,
+        })
+    }
+
+    fn content_light_level(&self) -> Option<ContentLightLevel> {
+        Some(ContentLightLevel {
+            max_cll: self.max_content?,
+            max_fall: self.max_average?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeFrames {
+    frames: Option<Vec<FfprobeFrame>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeFrame {
+    side_data_list: Option<Vec<FfprobeSideData>>,
+}
+
 #[derive(Debug, Deserialize)]
 struct FfprobeDisposition {
     default: Option<i32>,
+    comment: Option<i32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct FfprobeTags {
     language: Option<String>,
+    title: Option<String>,
+    filename: Option<String>,
+    mimetype: Option<String>,
+    // Written by mkvmerge
+    #[serde(rename = "NUMBER_OF_FRAMES", alias = "NUMBER_OF_FRAMES-eng")]
+    number_of_frames: Option<String>,
 }
 
 /// Execute ffprobe on a file and parse the JSON output
@@ -102,6 +383,7 @@ pub async fn probe_file(path: &Path) -> Result<ProbeResult> {
         .arg("json")
         .arg("-show_format")
         .arg("-show_streams")
+        .arg("-show_chapters")
         .arg(path)
         .output()
         .await
@@ -115,14 +397,110 @@ pub async fn probe_file(path: &Path) -> Result<ProbeResult> {
 
     // Parse JSON output
     let stdout = String::from_utf8(output.stdout).context("ffprobe output is not valid UTF-8")?;
+    let mut probe = parse_probe_json(&stdout)?;
 
+    // HDR10 static metadata is often only in the first frame's SEI (MP4, TS)
+    let frame_side_data_needed = probe
+        .main_video_stream()
+        .map(|v| {
+            v.color.dynamic_range() != DynamicRange::Sdr
+                && (v.color.mastering_display.is_none() || v.color.content_light_level.is_none())
+        })
+        .unwrap_or(false);
+    if frame_side_data_needed {
+        if let Err(e) = apply_first_frame_side_data(path, &mut probe).await {
+            tracing::debug!("Could not read frame side data for {:?}: {}", path, e);
+        }
+    }
+
+    Ok(probe)
+}
+
+/// Parse `ffprobe -print_format json -show_format -show_streams -show_chapters` output
+pub fn parse_probe_json(json: &str) -> Result<ProbeResult> {
     let ffprobe_output: FfprobeOutput =
-        serde_json::from_str(&stdout).context("Failed to parse ffprobe JSON output")?;
+        serde_json::from_str(json).context("Failed to parse ffprobe JSON output")?;
 
     // Convert to our internal format
     parse_ffprobe_output(ffprobe_output)
 }
 
+/// Count the packets of the first video stream by reading the whole file.
+///
+/// Unlike `frame_count`, this works for outputs whose container does not
+/// record a frame count.
+pub async fn count_video_frames(path: &Path) -> Result<u64> {
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("v:0")
+        .arg("-count_packets")
+        .arg("-show_entries")
+        .arg("stream=nb_read_packets")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(path)
+        .output()
+        .await
+        .context("Failed to execute ffprobe")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "ffprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .trim()
+        .parse()
+        .with_context(|| format!("Unexpected ffprobe packet count {:?}", stdout.trim()))
+}
+
+/// Merge HDR side data from the first frame of the main video stream
+async fn apply_first_frame_side_data(path: &Path, probe: &mut ProbeResult) -> Result<()> {
+    let Some(index) = probe.main_video_stream().map(|v| v.index) else {
+        return Ok(());
+    };
+
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("quiet")
+        .arg("-print_format")
+        .arg("json")
+        .arg("-select_streams")
+        .arg(index.to_string())
+        .arg("-read_intervals")
+        .arg("%+#1")
+        .arg("-show_entries")
+        .arg("frame=side_data_list")
+        .arg(path)
+        .output()
+        .await
+        .context("Failed to execute ffprobe")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "ffprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let frames: FfprobeFrames = serde_json::from_slice(&output.stdout)
+        .context("Failed to parse ffprobe frame JSON output")?;
+    let side_data: Vec<FfprobeSideData> = frames
+        .frames
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|f| f.side_data_list.unwrap_or_default())
+        .collect();
+
+    if let Some(stream) = probe.video_streams.iter_mut().find(|v| v.index == index) {
+        stream.color.apply_side_data(&side_data);
+    }
+    Ok(())
+}
+
 /// Parse FFprobe output into our ProbeResult structure
 fn parse_ffprobe_output(output: FfprobeOutput) -> Result<ProbeResult> {
     // Parse format information
@@ -145,11 +523,41 @@ fn parse_ffprobe_output(output: FfprobeOutput) -> Result<ProbeResult> {
     let mut video_streams = Vec::new();
     let mut audio_streams = Vec::new();
     let mut subtitle_streams = Vec::new();
+    let mut attachments = Vec::new();
 
     for stream in streams {
+        let is_default = stream
+            .disposition
+            .as_ref()
+            .and_then(|d| d.default)
+            .map(|v| v == 1)
+            .unwrap_or(false);
+        let tags = stream.tags.unwrap_or_default();
+        let (language, title) = (tags.language, tags.title);
+        // Commentary tracks are flagged by disposition or, more often, only by title
+        let is_commentary = stream
+            .disposition
+            .as_ref()
+            .and_then(|d| d.comment)
+            .map(|v| v == 1)
+            .unwrap_or(false)
+            || title
+                .as_deref()
+                .map(|t| t.to_lowercase().contains("commentary"))
+                .unwrap_or(false);
+
         match stream.codec_type.as_str() {
             "video" => {
                 if let (Some(width), Some(height)) = (stream.width, stream.height) {
+                    let mut color = ColorInfo {
+                        primaries: known_color_value(stream.color_primaries),
+                        transfer: known_color_value(stream.color_transfer),
+                        matrix: known_color_value(stream.color_space),
+                        range: known_color_value(stream.color_range),
+                        ..Default::default()
+                    };
+                    color.apply_side_data(stream.side_data_list.as_deref().unwrap_or_default());
+
                     video_streams.push(VideoStream {
                         index: stream.index,
                         codec_name: stream.codec_name.clone(),
@@ -161,11 +569,14 @@ fn parse_ffprobe_output(output: FfprobeOutput) -> Result<ProbeResult> {
                         bit_depth: stream
                             .bits_per_raw_sample
                             .and_then(|b| b.parse::<u8>().ok()),
-                        is_default: stream
-                            .disposition
-                            .and_then(|d| d.default)
-                            .map(|v| v == 1)
-                            .unwrap_or(false),
+                        is_default,
+                        color,
+                        field_order: known_color_value(stream.field_order),
+                        frame_count: stream
+                            .nb_frames
+                            .or(tags.number_of_frames)
+                            .and_then(|n| n.parse::<u64>().ok())
+                            .filter(|&n| n > 0),
                     });
                 }
             }
@@ -173,18 +584,34 @@ fn parse_ffprobe_output(output: FfprobeOutput) -> Result<ProbeResult> {
                 audio_streams.push(AudioStream {
                     index: stream.index,
                     codec_name: stream.codec_name.clone(),
-                    language: stream.tags.and_then(|t| t.language),
+                    language,
+                    title,
+                    is_default,
+                    is_commentary,
+                    channels: stream.channels,
+                    channel_layout: stream.channel_layout.clone(),
+                    bitrate: stream.bit_rate.and_then(|b| b.parse::<u64>().ok()),
                 });
             }
             "subtitle" => {
                 subtitle_streams.push(SubtitleStream {
                     index: stream.index,
                     codec_name: stream.codec_name.clone(),
-                    language: stream.tags.and_then(|t| t.language),
+                    language,
+                    title,
+                    is_default,
+                    is_commentary,
+                });
+            }
+            "attachment" => {
+                attachments.push(Attachment {
+                    index: stream.index,
+                    filename: tags.filename,
+                    mimetype: tags.mimetype,
                 });
             }
             _ => {
-                // Ignore other stream types (data, attachment, etc.)
+                // Ignore other stream types (data, etc.)
             }
         }
     }
@@ -194,9 +621,27 @@ fn parse_ffprobe_output(output: FfprobeOutput) -> Result<ProbeResult> {
         video_streams,
         audio_streams,
         subtitle_streams,
+        chapters: output.chapters.map(|c| c.len()).unwrap_or(0),
+        attachments,
     })
 }
 
+/// ffprobe reports unset colour and field order values as "unknown" or similar
+fn known_color_value(value: Option<String>) -> Option<String> {
+    value.filter(|v| !matches!(v.as_str(), "" | "unknown" | "unspecified" | "reserved"))
+}
+
+/// Parse an ffprobe rational such as "34000/50000" (or a plain number)
+fn parse_rational(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((num, den)) => {
+            let den: f64 = den.trim().parse().ok()?;
+            (den != 0.0).then_some(num.trim().parse::<f64>().ok()? / den)
+        }
+        None => value.trim().parse().ok(),
+    }
+}
+
 /// Select the main video stream from a list of video streams
 /// Prefers stream with default disposition, falls back to first stream
 pub fn select_main_video_stream(streams: &[VideoStream]) -> Option<&VideoStream> {
@@ -0,0 +1,227 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::classify::{SourceClassification, SourceType};
+use crate::config::{DaemonConfig, EncoderPreference};
+use crate::encode::{select_crf, select_preset};
+use crate::probe::{DynamicRange, ProbeResult};
+use crate::startup::AvailableEncoder;
+
+/// Highest CRF `encoder` accepts; for rav1e this is `-qp`
+pub fn max_crf(encoder: AvailableEncoder) -> u8 {
+    match encoder {
+        AvailableEncoder::Librav1e => 255,
+        AvailableEncoder::SvtAv1 | AvailableEncoder::LibaomAv1 => 63,
+    }
+}
+
+/// One row of a resolution table: `value` applies from `min_height` upwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolutionStep {
+    pub min_height: i32,
+    pub value: u8,
+}
+
+/// Value for `height` from a table ordered by descending `min_height`.
+///
+/// Heights below the last row use the last row. Returns `None` for an empty table.
+pub fn table_value(table: &[ResolutionStep], height: i32) -> Option<u8> {
+    table
+        .iter()
+        .find(|step| height >= step.min_height)
+        .or(table.last())
+        .map(|step| step.value)
+}
+
+/// Named encoder settings, chosen per file by [`ProfileRule`]s.
+///
+/// Unset fields keep the daemon's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncodingProfile {
+    /// Encoder to use instead of `prefer_encoder`, when it is available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoder: Option<EncoderPreference>,
+    /// CRF by resolution, used as written for every quality tier and encoder
+    pub crf: Vec<ResolutionStep>,
+    /// Speed preset by resolution, used as written for every quality tier and encoder
+    pub preset: Vec<ResolutionStep>,
+    /// Extra `-svtav1-params` entries, e.g. `"tune=0"`
+    pub svt_params: Vec<String>,
+    /// Film grain synthesis strength (SVT-AV1 `film-grain`, libaom `denoise-noise-level`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub film_grain: Option<u8>,
+    /// Maximum keyframe interval in frames
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyint: Option<u32>,
+    /// Crop black bars; overrides `crop_detection.enabled`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crop: Option<bool>,
+}
+
+impl EncodingProfile {
+    /// CRF from the profile's table, or from `crf_tables` when it has none.
+    ///
+    /// A value `encoder` does not accept, i.e. a rav1e `-qp` above 63 when
+    /// another encoder had to be used, also falls back to `crf_tables`.
+    pub fn crf(
+        &self,
+        config: &DaemonConfig,
+        encoder: AvailableEncoder,
+        height: i32,
+        bitrate: Option<u64>,
+    ) -> u8 {
+        match table_value(&self.crf, height).filter(|&crf| crf <= max_crf(encoder)) {
+            Some(crf) => config.bitrate_adjustment.adjust(crf, height, bitrate),
+            None => select_crf(config, encoder, height, bitrate),
+        }
+    }
+
+    /// Preset from the profile's table, or from `preset_tables` when it has none
+    pub fn preset(
+        &self,
+        config: &DaemonConfig,
+        encoder: AvailableEncoder,
+        height: i32,
+    ) -> Option<u8> {
+        table_value(&self.preset, height).or_else(|| select_preset(config, encoder, height))
+    }
+}
+
+/// Picks `profile` for files matching every condition that is set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileRule {
+    /// Name of an entry in `profiles`
+    pub profile: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_type: Option<SourceType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<i32>,
+    /// Source video codecs, e.g. `["h264", "mpeg2video"]`
+    pub codecs: Vec<String>,
+    /// Video bitrate in bits per second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_bitrate: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bitrate: Option<u64>,
+    /// Match only HDR (PQ or HLG) or only SDR sources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdr: Option<bool>,
+    /// Globs matched case-insensitively against the full path; any may match
+    pub paths: Vec<String>,
+}
+
+fn path_glob(pattern: &str) -> Result<GlobMatcher> {
+    Ok(GlobBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .with_context(|| format!("Invalid profile rule path {:?}", pattern))?
+        .compile_matcher())
+}
+
+impl ProfileRule {
+    pub fn matches(
+        &self,
+        path: &Path,
+        probe: &ProbeResult,
+        classification: &SourceClassification,
+    ) -> bool {
+        let video = probe.main_video_stream();
+        let height = video.map(|v| v.height);
+        let bitrate = video.and_then(|v| v.bitrate).or(probe.format.bitrate);
+        let is_hdr = video.is_some_and(|v| v.color.dynamic_range() != DynamicRange::Sdr);
+
+        self.source_type
+            .is_none_or(|t| t == classification.source_type)
+            && self
+                .min_height
+                .is_none_or(|min| height.is_some_and(|h| h >= min))
+            && self
+                .max_height
+                .is_none_or(|max| height.is_some_and(|h| h <= max))
+            && (self.codecs.is_empty()
+                || video.is_some_and(|v| {
+                    self.codecs
+                        .iter()
+                        .any(|c| c.eq_ignore_ascii_case(&v.codec_name))
+                }))
+            && self
+                .min_bitrate
+                .is_none_or(|min| bitrate.is_some_and(|b| b >= min))
+            && self
+                .max_bitrate
+                .is_none_or(|max| bitrate.is_some_and(|b| b <= max))
+            && self.hdr.is_none_or(|hdr| hdr == is_hdr)
+            && (self.paths.is_empty()
+                || self
+                    .paths
+                    .iter()
+                    .filter_map(|p| path_glob(p).ok())
+                    .any(|glob| glob.is_match(path)))
+    }
+}
+
+/// Name of the profile for a file: the first matching rule wins, and files
+/// no rule matches use the built-in settings
+pub fn select_profile<'a>(
+    config: &'a DaemonConfig,
+    path: &Path,
+    probe: &ProbeResult,
+    classification: &SourceClassification,
+) -> Option<&'a str> {
+    config
+        .profile_rules
+        .iter()
+        .find(|rule| rule.matches(path, probe, classification))
+        .map(|rule| rule.profile.as_str())
+}
+
+pub(crate) fn validate_table(table: &[ResolutionStep], max: u8, what: &str) -> Result<()> {
+    if table.windows(2).any(|w| w[0].min_height <= w[1].min_height) {
+        anyhow::bail!("{} must be ordered by descending min_height", what);
+    }
+    if let Some(step) = table.iter().find(|step| step.value > max) {
+        anyhow::bail!("{} value {} is above {}", what, step.value, max);
+    }
+    Ok(())
+}
+
+/// Check the profiles and that every rule names one of them
+pub fn validate_profiles(config: &DaemonConfig) -> Result<()> {
+    for (name, profile) in &config.profiles {
+        // The CRF scale is the one of the encoder the profile asks for
+        let encoder = match profile.encoder.unwrap_or(config.prefer_encoder) {
+            EncoderPreference::Svt => AvailableEncoder::SvtAv1,
+            EncoderPreference::Aom => AvailableEncoder::LibaomAv1,
+            EncoderPreference::Rav1e => AvailableEncoder::Librav1e,
+        };
+        validate_table(
+            &profile.crf,
+            max_crf(encoder),
+            &format!("profiles.{}.crf", name),
+        )?;
+        validate_table(&profile.preset, 13, &format!("profiles.{}.preset", name))?;
+        if profile.keyint == Some(0) {
+            anyhow::bail!("profiles.{}.keyint must be at least 1", name);
+        }
+        if profile.film_grain.is_some_and(|grain| grain > 50) {
+            anyhow::bail!("profiles.{}.film_grain must be between 0 and 50", name);
+        }
+    }
+
+    for rule in &config.profile_rules {
+        if !config.profiles.contains_key(&rule.profile) {
+            anyhow::bail!("profile_rules refers to unknown profile {:?}", rule.profile);
+        }
+        for pattern in &rule.paths {
+            path_glob(pattern)?;
+        }
+    }
+
+    Ok(())
+}
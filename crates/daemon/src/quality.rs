@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::config::{QualityCheckConfig, QualityMetric};
+use crate::encode::common::SourceFilters;
+use crate::probe::{ProbeResult, VideoStream};
+use crate::validate::ValidationError;
+
+/// PSNR of identical frames is infinite; it is reported as this value instead
+pub const PSNR_IDENTICAL: f64 = 100.0;
+
+/// Worst per-sample scores of an encode against its source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityScores {
+    pub ssim: f64,
+    pub psnr: f64,
+    /// Only measured when `min_vmaf` is set and ffmpeg has libvmaf
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vmaf: Option<f64>,
+    pub samples: u32,
+}
+
+/// Scores of one compared segment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleScores {
+    pub ssim: f64,
+    pub psnr: f64,
+    pub vmaf: Option<f64>,
+}
+
+impl SampleScores {
+    pub fn get(&self, metric: QualityMetric) -> Option<f64> {
+        match metric {
+            QualityMetric::Ssim => Some(self.ssim),
+            QualityMetric::Psnr => Some(self.psnr),
+            QualityMetric::Vmaf => self.vmaf,
+        }
+    }
+}
+
+/// Start times of `count` evenly spaced samples of `sample_secs` each.
+///
+/// Samples are centred in equal slices of the file, so the first and last
+/// seconds (logos, credits) carry less weight. Short files get one sample.
+pub fn sample_offsets(duration: f64, count: u32, sample_secs: f64) -> Vec<f64> {
+    if duration <= sample_secs || count <= 1 {
+        let start = ((duration - sample_secs) / 2.0).max(0.0);
+        return vec![start];
+    }
+
+    let slice = duration / count as f64;
+    (0..count)
+        .map(|i| {
+            let centre = slice * (i as f64 + 0.5);
+            (centre - sample_secs / 2.0).clamp(0.0, duration - sample_secs)
+        })
+        .collect()
+}
+
+/// Whether this ffmpeg build has the libvmaf filter
+pub async fn vmaf_available(ffmpeg: &str) -> bool {
+    match Command::new(ffmpeg)
+        .arg("-hide_banner")
+        .arg("-filters")
+        .output()
+        .await
+    {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .any(|word| word == "libvmaf"),
+        Err(_) => false,
+    }
+}
+
+/// Compare evenly spaced samples of `output` against `source`.
+///
+/// The output is scaled to the source dimensions so padding to even sizes
+/// does not break the comparison. The source gets the filters the encode was
+/// made with first, such as deinterlacing and cropping.
+pub async fn measure_quality(
+    ffmpeg: &str,
+    source: &Path,
+    output: &Path,
+    source_probe: &ProbeResult,
+    source_filters: &SourceFilters,
+    config: &QualityCheckConfig,
+) -> Result<QualityScores> {
+    let video = source_probe
+        .main_video_stream()
+        .context("Source has no video stream to compare")?;
+    let duration = source_probe
+        .format
+        .duration
+        .context("Source duration is unknown")?;
+    let use_vmaf = config.min_vmaf > 0.0 && vmaf_available(ffmpeg).await;
+
+    let sample_secs = config.sample_secs as f64;
+    let mut worst: Option<QualityScores> = None;
+    for offset in sample_offsets(duration, config.samples, sample_secs) {
+        let sample = compare_segment(
+            ffmpeg,
+            (output, offset),
+            (source, offset),
+            sample_secs,
+            video,
+            source_filters,
+            use_vmaf,
+        )
+        .await?;
+        worst = Some(match worst {
+            None => QualityScores {
+                ssim: sample.ssim,
+                psnr: sample.psnr,
+                vmaf: sample.vmaf,
+                samples: 1,
+            },
+            Some(w) => QualityScores {
+                ssim: w.ssim.min(sample.ssim),
+                psnr: w.psnr.min(sample.psnr),
+                vmaf: match (w.vmaf, sample.vmaf) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                },
+                samples: w.samples + 1,
+            },
+        });
+    }
+
+    worst.context("No samples were compared")
+}
+
+/// Compare `length` seconds of `distorted` against `reference`, each starting
+/// at its own offset, and return the scores ffmpeg reports.
+///
+/// `video` is the main video stream of the reference; it is filtered by
+/// `source_filters` and the distorted video is scaled to the resulting size.
+pub async fn compare_segment(
+    ffmpeg: &str,
+    (distorted, distorted_offset): (&Path, f64),
+    (reference, reference_offset): (&Path, f64),
+    length: f64,
+    video: &VideoStream,
+    source_filters: &SourceFilters,
+    use_vmaf: bool,
+) -> Result<SampleScores> {
+    let pix_fmt = if video.bit_depth.unwrap_or(8) > 8 {
+        "yuv420p10le"
+    } else {
+        "yuv420p"
+    };
+    let filter = comparison_filter(video, source_filters, pix_fmt, use_vmaf);
+
+    let result = Command::new(ffmpeg)
+        .arg("-hide_banner")
+        .arg("-nostats")
+        .arg("-ss")
+        .arg(format!("{:.3}", distorted_offset))
+        .arg("-t")
+        .arg(format!("{:.3}", length))
+        .arg("-i")
+        .arg(distorted)
+        .arg("-ss")
+        .arg(format!("{:.3}", reference_offset))
+        .arg("-t")
+        .arg(format!("{:.3}", length))
+        .arg("-i")
+        .arg(reference)
+        .arg("-lavfi")
+        .arg(&filter)
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .output()
+        .await
+        .context("Failed to execute ffmpeg for quality check")?;
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    if !result.status.success() {
+        anyhow::bail!(
+            "Quality check ffmpeg failed at {:.0}s: {}",
+            reference_offset,
+            stderr.trim()
+        );
+    }
+
+    parse_sample_scores(&stderr, use_vmaf).with_context(|| {
+        format!(
+            "No quality scores for the sample at {:.0}s",
+            reference_offset
+        )
+    })
+}
+
+/// Filter graph comparing input 0 (encoded) against the main video of input 1 (source)
+fn comparison_filter(
+    video: &VideoStream,
+    source_filters: &SourceFilters,
+    pix_fmt: &str,
+    use_vmaf: bool,
+) -> String {
+    let outputs = if use_vmaf { 3 } else { 2 };
+    let (width, height) = source_filters.output_size(video.width, video.height);
+    let mut filter = format!(
+        "[0:v:0]scale={w}:{h}:flags=bicubic,format={f},setpts=PTS-STARTPTS,split={n}[d0][d1]{d2};\
+         [1:{i}]{c}format={f},setpts=PTS-STARTPTS,split={n}[r0][r1]{r2};\
+         [d0][r0]ssim;[d1][r1]psnr",
+        w = width,
+        h = height,
+        f = pix_fmt,
+        n = outputs,
+        i = video.index,
+        c = source_filters.prefix(),
+        d2 = if use_vmaf { "[d2]" } else { "" },
+        r2 = if use_vmaf { "[r2]" } else { "" },
+    );
+    if use_vmaf {
+        filter.push_str(";[d2][r2]libvmaf");
+    }
+    filter
+}
+
+/// Extract SSIM, PSNR and (optionally) VMAF from ffmpeg's end-of-run log lines
+pub fn parse_sample_scores(stderr: &str, expect_vmaf: bool) -> Option<SampleScores> {
+    let ssim_re = Regex::new(r"SSIM .*All:([0-9.]+)").unwrap();
+    let psnr_re = Regex::new(r"PSNR .*average:([0-9.]+|inf)").unwrap();
+    let vmaf_re = Regex::new(r"VMAF score: ([0-9.]+)").unwrap();
+
+    let ssim = ssim_re.captures(stderr)?[1].parse::<f64>().ok()?;
+    let psnr = match &psnr_re.captures(stderr)?[1] {
+        "inf" => PSNR_IDENTICAL,
+        value => value.parse::<f64>().ok()?.min(PSNR_IDENTICAL),
+    };
+    let vmaf = if expect_vmaf {
+        Some(vmaf_re.captures(stderr)?[1].parse::<f64>().ok()?)
+    } else {
+        None
+    };
+
+    Some(SampleScores { ssim, psnr, vmaf })
+}
+
+/// Check scores against the configured thresholds (0 disables a metric)
+pub fn check_quality(
+    scores: &QualityScores,
+    config: &QualityCheckConfig,
+) -> Option<ValidationError> {
+    let below = |metric: &str, score: f64, threshold: f64| {
+        (threshold > 0.0 && score < threshold).then(|| ValidationError::QualityBelowThreshold {
+            metric: metric.to_string(),
+            score,
+            threshold,
+        })
+    };
+
+    below("SSIM", scores.ssim, config.min_ssim)
+        .or_else(|| below("PSNR", scores.psnr, config.min_psnr))
+        .or_else(|| {
+            scores
+                .vmaf
+                .and_then(|vmaf| below("VMAF", vmaf, config.min_vmaf))
+        })
+}
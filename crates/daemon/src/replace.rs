@@ -19,6 +19,21 @@ use std::path::{Path, PathBuf};
 /// * `Ok(())` on success
 /// * `Err` if any operation fails, with attempted rollback
 pub fn atomic_replace(original: &Path, new: &Path, keep_original: bool) -> Result<()> {
+    atomic_replace_to(original, new, original, keep_original)
+}
+
+/// Atomically replace the original file with the new file stored at `target`.
+///
+/// Same as [`atomic_replace`], but the new file ends up at `target`, which may
+/// differ from `original` (for example when the container, and so the extension,
+/// changes). The original is backed up and removed as usual; on failure it is
+/// restored at its own path.
+pub fn atomic_replace_to(
+    original: &Path,
+    new: &Path,
+    target: &Path,
+    keep_original: bool,
+) -> Result<()> {
     // Validate inputs
     if !new.exists() {
         anyhow::bail!("New file does not exist: {:?}", new);
@@ -28,6 +43,10 @@ pub fn atomic_replace(original: &Path, new: &Path, keep_original: bool) -> Resul
         anyhow::bail!("Original file does not exist: {:?}", original);
     }
 
+    if target != original && target.exists() {
+        anyhow::bail!("Target file already exists: {:?}", target);
+    }
+
     // Generate temporary name with timestamp for uniqueness
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -119,7 +138,7 @@ pub fn atomic_replace(original: &Path, new: &Path, keep_original: bool) -> Resul
 
         eprintln!("No backup possible - using safe two-step replacement");
 
-        let temp_in_place = original.with_extension("av1tmp");
+        let temp_in_place = target.with_extension("av1tmp");
 
         // Step 2a: Copy new file to temp location in target directory
         eprintln!("  Step 1: Copying new file to temp location");
@@ -141,16 +160,16 @@ pub fn atomic_replace(original: &Path, new: &Path, keep_original: bool) -> Resul
             ));
         }
 
-        // Step 2c: Rename temp to original name
-        eprintln!("  Step 3: Renaming temp to original location");
-        if let Err(_e) = fs::rename(&temp_in_place, original) {
+        // Step 2c: Rename temp to target name
+        eprintln!("  Step 3: Renaming temp to target location");
+        if let Err(_e) = fs::rename(&temp_in_place, target) {
             // This is bad - original is deleted but rename failed
             // Try to recover by copying instead
             eprintln!("  WARNING: Rename failed, trying copy as fallback");
-            if let Err(copy_err) = fs::copy(&temp_in_place, original) {
+            if let Err(copy_err) = fs::copy(&temp_in_place, target) {
                 return Err(copy_err).context(format!(
-                    "CRITICAL: Failed to rename/copy temp {:?} to original {:?}. Original was deleted! Temp file preserved at {:?}",
-                    temp_in_place, original, temp_in_place
+                    "CRITICAL: Failed to rename/copy temp {:?} to target {:?}. Original was deleted! Temp file preserved at {:?}",
+                    temp_in_place, target, temp_in_place
                 ));
             }
             fs::remove_file(&temp_in_place).ok();
@@ -168,8 +187,8 @@ pub fn atomic_replace(original: &Path, new: &Path, keep_original: bool) -> Resul
         return Ok(());
     }
 
-    // Step 3: Normal path - backup exists, copy new to original name
-    match fs::copy(new, original) {
+    // Step 3: Normal path - backup exists, copy new to target name
+    match fs::copy(new, target) {
         Ok(_) => {
             // Successfully copied, now delete the source temp file
             if let Err(e) = fs::remove_file(new) {
@@ -192,9 +211,9 @@ pub fn atomic_replace(original: &Path, new: &Path, keep_original: bool) -> Resul
         Err(e) => {
             // Copy failed - attempt rollback
             let error_kind = e.kind();
-            eprintln!("ERROR: Failed to copy new file to original location");
+            eprintln!("ERROR: Failed to copy new file to target location");
             eprintln!("  New file: {:?} (exists: {})", new, new.exists());
-            eprintln!("  Original: {:?}", original);
+            eprintln!("  Target: {:?}", target);
             eprintln!("  Error kind: {:?}", error_kind);
             eprintln!("  Error: {}", e);
 
@@ -203,12 +222,17 @@ pub fn atomic_replace(original: &Path, new: &Path, keep_original: bool) -> Resul
                 eprintln!("  New file size: {} bytes", metadata.len());
                 eprintln!("  New file permissions: {:?}", metadata.permissions());
             }
-            if let Some(parent) = original.parent() {
+            if let Some(parent) = target.parent() {
                 if let Ok(metadata) = fs::metadata(parent) {
                     eprintln!("  Target dir permissions: {:?}", metadata.permissions());
                 }
             }
 
+            // Remove any partial copy left at a renamed target
+            if target != original {
+                fs::remove_file(target).ok();
+            }
+
             eprintln!(
                 "Attempting to restore original from backup {:?}",
                 orig_backup
@@ -249,3 +273,74 @@ fn generate_backup_path(original: &Path, timestamp: u64) -> PathBuf {
         PathBuf::from(backup_name)
     }
 }
+
+/// Sibling extensions that belong to a video and follow it when it is renamed
+const SIBLING_EXTENSIONS: &[&str] = &[
+    "srt", "ass", "ssa", "sub", "idx", "vtt", "sup", "nfo", "jpg", "jpeg", "png",
+];
+
+/// Rename sibling files (subtitles, `.nfo`, `-poster.jpg`, ...) of `old_video`
+/// so they keep matching `new_video`.
+///
+/// Siblings are files in the same directory whose name starts with the old file
+/// name (`movie.mp4.srt`) or its stem (`movie.en.srt`, `movie-poster.jpg`) and
+/// that have a known sidecar extension. Siblings that already match the new name
+/// are left alone. Returns the `(from, to)` pairs that were moved.
+pub fn move_sibling_files(old_video: &Path, new_video: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let (Some(dir), Some(old_name), Some(new_name), Some(old_stem), Some(new_stem)) = (
+        old_video.parent(),
+        old_video.file_name().and_then(|n| n.to_str()),
+        new_video.file_name().and_then(|n| n.to_str()),
+        old_video.file_stem().and_then(|n| n.to_str()),
+        new_video.file_stem().and_then(|n| n.to_str()),
+    ) else {
+        return Ok(Vec::new());
+    };
+
+    let mut moved = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {:?}", dir))? {
+        let path = entry?.path();
+        if !path.is_file() || path == old_video || path == new_video {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let is_sidecar = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| SIBLING_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+            .unwrap_or(false);
+        if !is_sidecar {
+            continue;
+        }
+
+        let renamed = if let Some(rest) = name.strip_prefix(old_name) {
+            format!("{}{}", new_name, rest)
+        } else if let Some(rest) = name
+            .strip_prefix(old_stem)
+            .filter(|rest| rest.starts_with('.') || rest.starts_with('-'))
+        {
+            format!("{}{}", new_stem, rest)
+        } else {
+            continue;
+        };
+        if renamed == name {
+            continue;
+        }
+
+        let destination = dir.join(&renamed);
+        if destination.exists() {
+            eprintln!(
+                "Warning: Not moving sibling {:?}: {:?} already exists",
+                path, destination
+            );
+            continue;
+        }
+        fs::rename(&path, &destination)
+            .with_context(|| format!("Failed to move sibling {:?} to {:?}", path, destination))?;
+        moved.push((path, destination));
+    }
+
+    Ok(moved)
+}
@@ -0,0 +1,254 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::DaemonConfig;
+use crate::encode::FfmpegFailed;
+
+/// Serializes read-modify-write cycles on the ledger file within this process
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+
+/// Longest reason kept per attempt in the ledger and in `.why.txt` summaries
+const MAX_REASON_CHARS: usize = 300;
+
+/// Whether a failed attempt is worth retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// I/O errors, killed processes: may succeed on a later attempt
+    Transient,
+    /// Bad streams, validation mismatches: will fail the same way again
+    Permanent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryAttempt {
+    pub failed_at: DateTime<Utc>,
+    pub kind: FailureKind,
+    pub reason: String,
+}
+
+/// Failure history for one source file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryEntry {
+    pub source_path: PathBuf,
+    pub attempts: Vec<RetryAttempt>,
+    /// Earliest time the file may be picked up again
+    pub next_retry_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetryLedger {
+    pub entries: Vec<RetryEntry>,
+}
+
+/// What happens to a file after a failed attempt has been recorded
+#[derive(Debug, Clone, PartialEq)]
+pub enum RetryDecision {
+    /// Try again once the backoff has elapsed
+    RetryAfter(DateTime<Utc>),
+    /// Stop retrying; the summary lists every attempt
+    GiveUp { summary: String },
+}
+
+impl RetryLedger {
+    pub fn entry(&self, source_path: &Path) -> Option<&RetryEntry> {
+        self.entries.iter().find(|e| e.source_path == source_path)
+    }
+
+    /// Time until which `source_path` is backing off, if it is still in the future
+    pub fn backoff_until(&self, source_path: &Path, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.entry(source_path)
+            .and_then(|e| e.next_retry_at)
+            .filter(|at| *at > now)
+    }
+
+    /// Record a failed attempt and decide whether the file gets another one
+    pub fn record_failure(
+        &mut self,
+        source_path: &Path,
+        kind: FailureKind,
+        reason: &str,
+        now: DateTime<Utc>,
+        config: &DaemonConfig,
+    ) -> RetryDecision {
+        let index = match self
+            .entries
+            .iter()
+            .position(|e| e.source_path == source_path)
+        {
+            Some(index) => index,
+            None => {
+                self.entries.push(RetryEntry {
+                    source_path: source_path.to_path_buf(),
+                    attempts: Vec::new(),
+                    next_retry_at: None,
+                });
+                self.entries.len() - 1
+            }
+        };
+        let entry = &mut self.entries[index];
+
+        entry.attempts.push(RetryAttempt {
+            failed_at: now,
+            kind,
+            reason: shorten_reason(reason),
+        });
+
+        let attempts = entry.attempts.len() as u32;
+        if kind == FailureKind::Permanent || attempts >= config.max_attempts {
+            entry.next_retry_at = None;
+            return RetryDecision::GiveUp {
+                summary: summarize_attempts(entry),
+            };
+        }
+
+        let retry_at = now + chrono::Duration::seconds(backoff_secs(attempts, config) as i64);
+        entry.next_retry_at = Some(retry_at);
+        RetryDecision::RetryAfter(retry_at)
+    }
+
+    /// Forget the history for `source_path` (after a success or a manual retry)
+    pub fn clear(&mut self, source_path: &Path) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.source_path != source_path);
+        self.entries.len() != before
+    }
+}
+
+/// Backoff before the next attempt: base * 2^(attempts - 1), capped at the max delay
+pub fn backoff_secs(attempts: u32, config: &DaemonConfig) -> u64 {
+    let exponent = attempts.saturating_sub(1).min(32);
+    config
+        .retry_base_delay_secs
+        .saturating_mul(1u64 << exponent)
+        .min(config.retry_max_delay_secs)
+}
+
+/// Decide whether an error from the encode pipeline is worth retrying
+pub fn classify_failure(error: &anyhow::Error) -> FailureKind {
+    if error
+        .chain()
+        .any(|cause| cause.downcast_ref::<std::io::Error>().is_some())
+    {
+        return FailureKind::Transient;
+    }
+
+    if let Some(failed) = error.downcast_ref::<FfmpegFailed>() {
+        // No exit code means ffmpeg was killed by a signal (OOM killer, shutdown)
+        if failed.exit_code.is_none() {
+            return FailureKind::Transient;
+        }
+        let stderr = failed.stderr.to_lowercase();
+        let resource_errors = [
+            "no space left on device",
+            "cannot allocate memory",
+            "resource temporarily unavailable",
+            "input/output error",
+        ];
+        if resource_errors.iter().any(|e| stderr.contains(e)) {
+            return FailureKind::Transient;
+        }
+    }
+
+    FailureKind::Permanent
+}
+
+/// Human-readable list of attempts for the `.why.txt` sidecar
+pub fn summarize_attempts(entry: &RetryEntry) -> String {
+    let mut summary = match entry.attempts.last() {
+        Some(last) if last.kind == FailureKind::Permanent => format!(
+            "Giving up after a permanent failure on attempt {}:\n",
+            entry.attempts.len()
+        ),
+        _ => format!(
+            "Giving up after {} failed attempts:\n",
+            entry.attempts.len()
+        ),
+    };
+
+    for (i, attempt) in entry.attempts.iter().enumerate() {
+        let kind = match attempt.kind {
+            FailureKind::Transient => "transient",
+            FailureKind::Permanent => "permanent",
+        };
+        summary.push_str(&format!(
+            "  {}. {} [{}] {}\n",
+            i + 1,
+            attempt.failed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            kind,
+            attempt.reason
+        ));
+    }
+
+    summary
+}
+
+/// Keep the first line and the last non-empty line (usually ffmpeg's actual error)
+fn shorten_reason(reason: &str) -> String {
+    let lines: Vec<&str> = reason
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    let short = match lines.as_slice() {
+        [] => String::new(),
+        [only] => only.to_string(),
+        [first, .., last] => format!("{} ... {}", first, last),
+    };
+
+    if short.chars().count() > MAX_REASON_CHARS {
+        let truncated: String = short.chars().take(MAX_REASON_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        short
+    }
+}
+
+/// Load the ledger, treating a missing file as empty
+pub fn load_ledger(path: &Path) -> Result<RetryLedger> {
+    if !path.exists() {
+        return Ok(RetryLedger::default());
+    }
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read retry ledger at {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse retry ledger at {}", path.display()))
+}
+
+/// Save the ledger atomically (temp file + rename)
+pub fn save_ledger(ledger: &RetryLedger, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let json = serde_json::to_string_pretty(ledger)?;
+    let temp_file = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&temp_file)?;
+    file.write_all(json.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temp_file, path)?;
+
+    Ok(())
+}
+
+/// Load, modify and save the ledger configured for this daemon
+pub fn update_ledger<T>(
+    config: &DaemonConfig,
+    update: impl FnOnce(&mut RetryLedger) -> T,
+) -> Result<T> {
+    let _guard = LEDGER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = config.retry_ledger_path();
+    let mut ledger = load_ledger(&path)?;
+    let before = ledger.clone();
+    let result = update(&mut ledger);
+    if ledger != before {
+        save_ledger(&ledger, &path)?;
+    }
+    Ok(result)
+}
@@ -3,12 +3,13 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use tracing::{debug, warn};
-use walkdir::WalkDir;
 
+use crate::scan_filter::ScanFilter;
 use crate::sidecars::has_skip_marker;
 
-/// Allowed video file extensions
-const VIDEO_EXTENSIONS: &[&str] = &[".mkv", ".mp4", ".avi", ".mov", ".m4v", ".ts", ".m2ts"];
+/// Default video file extensions
+pub(crate) const VIDEO_EXTENSIONS: &[&str] =
+    &[".mkv", ".mp4", ".avi", ".mov", ".m4v", ".ts", ".m2ts"];
 
 #[derive(Debug, Clone)]
 pub struct CandidateFile {
@@ -19,6 +20,11 @@ pub struct CandidateFile {
 
 /// Recursively scan library directories for video files
 pub fn scan_libraries(roots: &[PathBuf]) -> Result<Vec<CandidateFile>> {
+    scan_libraries_with(roots, &ScanFilter::default())
+}
+
+/// Recursively scan library directories for the video files `filter` admits
+pub fn scan_libraries_with(roots: &[PathBuf], filter: &ScanFilter) -> Result<Vec<CandidateFile>> {
     let mut candidates = Vec::new();
 
     for root in roots {
@@ -35,22 +41,8 @@ pub fn scan_libraries(roots: &[PathBuf]) -> Result<Vec<CandidateFile>> {
             continue;
         }
 
-        // Walk directory tree
-        for entry in WalkDir::new(root)
-            .follow_links(false)
-            .into_iter()
-            .filter_entry(|e| {
-                // Skip hidden directories (starting with .), but not the root itself
-                if e.file_type().is_dir() && e.path() != root {
-                    !e.file_name()
-                        .to_str()
-                        .map(|s| s.starts_with('.'))
-                        .unwrap_or(false)
-                } else {
-                    true
-                }
-            })
-        {
+        // Walk directory tree; the filter drops non-video and excluded files
+        for entry in filter.walk(root, root) {
             match entry {
                 Ok(entry) => {
                     // Only process files, not directories
@@ -60,11 +52,6 @@ pub fn scan_libraries(roots: &[PathBuf]) -> Result<Vec<CandidateFile>> {
 
                     let path = entry.path();
 
-                    // Check if it's a video file
-                    if !is_video_file(path) {
-                        continue;
-                    }
-
                     // Check for skip marker
                     if has_skip_marker(path) {
                         debug!("Skipping file with .av1skip marker: {}", path.display());
@@ -72,17 +59,8 @@ pub fn scan_libraries(roots: &[PathBuf]) -> Result<Vec<CandidateFile>> {
                     }
 
                     // Get file metadata
-                    match fs::metadata(path) {
-                        Ok(metadata) => {
-                            let candidate = CandidateFile {
-                                path: path.to_path_buf(),
-                                size_bytes: metadata.len(),
-                                modified_time: metadata
-                                    .modified()
-                                    .unwrap_or_else(|_| SystemTime::now()),
-                            };
-                            candidates.push(candidate);
-                        }
+                    match candidate_from_path(path) {
+                        Ok(candidate) => candidates.push(candidate),
                         Err(e) => {
                             warn!("Failed to get metadata for {}: {}", path.display(), e);
                             continue;
@@ -102,6 +80,16 @@ pub fn scan_libraries(roots: &[PathBuf]) -> Result<Vec<CandidateFile>> {
     Ok(candidates)
 }
 
+/// Build a candidate from the current metadata of `path`
+pub fn candidate_from_path(path: &Path) -> Result<CandidateFile> {
+    let metadata = fs::metadata(path)?;
+    Ok(CandidateFile {
+        path: path.to_path_buf(),
+        size_bytes: metadata.len(),
+        modified_time: metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+    })
+}
+
 /// Check if a file has a video extension
 pub fn is_video_file(path: &Path) -> bool {
     path.extension()
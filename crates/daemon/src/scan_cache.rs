@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::classify::SourceClassification;
+use crate::config::DaemonConfig;
+use crate::gates::GateResult;
+use crate::probe::{ProbeResult, PROBE_SCHEMA_VERSION};
+
+/// Layout of the cache file and of the results it holds; a cache written with
+/// another version is discarded on load.
+///
+/// Bump this whenever `ScanCacheEntry` or `SourceClassification` change:
+/// entries without the new fields would otherwise load with defaults and be
+/// used as if the file had been classified without them. Changes to
+/// `ProbeResult` bump `PROBE_SCHEMA_VERSION` instead.
+pub const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// Identity of a file's contents as far as the cache is concerned.
+///
+/// A replaced file gets a new inode even when size and mtime happen to match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    pub size_bytes: u64,
+    pub mtime_secs: i64,
+    pub mtime_nanos: i64,
+    pub inode: u64,
+}
+
+impl FileFingerprint {
+    pub fn of(path: &Path) -> std::io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self {
+            size_bytes: metadata.size(),
+            mtime_secs: metadata.mtime(),
+            mtime_nanos: metadata.mtime_nsec(),
+            inode: metadata.ino(),
+        })
+    }
+}
+
+/// Config values the cached gate results depend on; a change drops the cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GateSettings {
+    pub min_bytes: u64,
+}
+
+impl GateSettings {
+    pub fn from_config(config: &DaemonConfig) -> Self {
+        Self {
+            min_bytes: config.min_bytes,
+        }
+    }
+}
+
+/// What discovery learned about a file the last time it was stable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanCacheEntry {
+    pub fingerprint: FileFingerprint,
+    /// Settings `gate` was computed with, which directory overrides may change
+    pub gate_settings: GateSettings,
+    /// `PROBE_SCHEMA_VERSION` `probe` was recorded with; 0 for entries from
+    /// before it was recorded
+    #[serde(default)]
+    pub probe_schema: u32,
+    pub probe: ProbeResult,
+    pub classification: SourceClassification,
+    pub gate: GateResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanCache {
+    /// `CACHE_FORMAT_VERSION` the cache was written with; 0 for caches from
+    /// before it was recorded
+    #[serde(default)]
+    pub format_version: u32,
+    pub settings: GateSettings,
+    pub entries: HashMap<PathBuf, ScanCacheEntry>,
+    /// Changed since it was loaded or last saved
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl ScanCache {
+    pub fn new(settings: GateSettings) -> Self {
+        Self {
+            format_version: CACHE_FORMAT_VERSION,
+            settings,
+            entries: HashMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// Cached entry for `path`, if the file still has the same fingerprint and
+    /// its gates were checked with the same settings
+    pub fn lookup(
+        &self,
+        path: &Path,
+        fingerprint: &FileFingerprint,
+        gate_settings: &GateSettings,
+    ) -> Option<&ScanCacheEntry> {
+        self.entries.get(path).filter(|entry| {
+            entry.fingerprint == *fingerprint && entry.gate_settings == *gate_settings
+        })
+    }
+
+    pub fn insert(&mut self, path: PathBuf, entry: ScanCacheEntry) {
+        self.entries.insert(path, entry);
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let removed = self.entries.remove(path).is_some();
+        self.dirty |= removed;
+        removed
+    }
+
+    /// Drop entries for files a full scan no longer found
+    pub fn retain_paths<'a>(&mut self, paths: impl IntoIterator<Item = &'a Path>) {
+        let keep: HashSet<&Path> = paths.into_iter().collect();
+        let before = self.entries.len();
+        self.entries.retain(|path, _| keep.contains(path.as_path()));
+        self.dirty |= self.entries.len() != before;
+    }
+
+    /// Whether there is anything to save
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+}
+
+/// Load the cache, starting empty when it is missing, was written by another
+/// version or was built with different gate settings, and without entries
+/// probed with another `PROBE_SCHEMA_VERSION`
+pub fn load_scan_cache(path: &Path, settings: &GateSettings) -> Result<ScanCache> {
+    if !path.exists() {
+        return Ok(ScanCache::new(settings.clone()));
+    }
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read scan cache at {}", path.display()))?;
+    let mut cache: ScanCache = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse scan cache at {}", path.display()))?;
+
+    if cache.format_version != CACHE_FORMAT_VERSION {
+        info!(
+            "Scan cache format changed ({} -> {}), discarding the scan cache",
+            cache.format_version, CACHE_FORMAT_VERSION
+        );
+        return Ok(ScanCache::new(settings.clone()));
+    }
+    if cache.settings != *settings {
+        info!("Gate settings changed, discarding the scan cache");
+        return Ok(ScanCache::new(settings.clone()));
+    }
+
+    let before = cache.entries.len();
+    cache
+        .entries
+        .retain(|_, entry| entry.probe_schema == PROBE_SCHEMA_VERSION);
+    let discarded = before - cache.entries.len();
+    if discarded > 0 {
+        info!(
+            "Discarding {} scan cache entries probed with an older probe schema",
+            discarded
+        );
+        cache.dirty = true;
+    }
+    Ok(cache)
+}
+
+/// Save the cache atomically (temp file + rename)
+pub fn save_scan_cache(cache: &mut ScanCache, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let json = serde_json::to_string(cache)?;
+    let temp_file = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&temp_file)?;
+    file.write_all(json.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temp_file, path)?;
+    cache.dirty = false;
+
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::config::{DaemonConfig, ScanRules};
+
+/// One include or exclude pattern from the config
+#[derive(Debug, Clone)]
+enum Pattern {
+    Glob(String, GlobMatcher),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn glob(pattern: &str) -> Result<Self> {
+        let matcher = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .with_context(|| format!("Invalid scan glob {:?}", pattern))?
+            .compile_matcher();
+        Ok(Self::Glob(pattern.to_string(), matcher))
+    }
+
+    fn regex(pattern: &str) -> Result<Self> {
+        let regex =
+            Regex::new(pattern).with_context(|| format!("Invalid scan regex {:?}", pattern))?;
+        Ok(Self::Regex(regex))
+    }
+
+    /// `relative` is the path below the library root; directories also match
+    /// with a trailing slash so `**/Extras/**` prunes the `Extras` directory
+    fn matches(&self, relative: &Path, is_dir: bool) -> bool {
+        let text = relative.to_string_lossy();
+        let candidates = if is_dir {
+            vec![text.to_string(), format!("{}/", text)]
+        } else {
+            vec![text.to_string()]
+        };
+        candidates.iter().any(|c| match self {
+            Self::Glob(_, matcher) => matcher.is_match(c),
+            Self::Regex(regex) => regex.is_match(c),
+        })
+    }
+
+    fn describe(&self, kind: &str) -> String {
+        match self {
+            Self::Glob(pattern, _) => format!("{} {:?}", kind, pattern),
+            Self::Regex(regex) => format!("{}_regex {:?}", kind, regex.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRules {
+    root: Option<PathBuf>,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    max_depth: Option<usize>,
+}
+
+impl CompiledRules {
+    fn new(rules: &ScanRules) -> Result<Self> {
+        let globs = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| Pattern::glob(p))
+                .collect::<Result<Vec<_>>>()
+        };
+        let regexes = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| Pattern::regex(p))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        let mut include = globs(&rules.include)?;
+        include.extend(regexes(&rules.include_regex)?);
+        let mut exclude = globs(&rules.exclude)?;
+        exclude.extend(regexes(&rules.exclude_regex)?);
+
+        Ok(Self {
+            root: rules.root.clone(),
+            include,
+            exclude,
+            max_depth: rules.max_depth,
+        })
+    }
+
+    fn applies_to(&self, root: &Path) -> bool {
+        self.root.as_deref().is_none_or(|r| r == root)
+    }
+}
+
+/// Decides which files under the library roots are scanned, built from
+/// `video_extensions` and `scan_rules`
+#[derive(Debug, Clone)]
+pub struct ScanFilter {
+    /// Lowercase, without the dot
+    extensions: Vec<String>,
+    rules: Vec<CompiledRules>,
+    /// Excluded paths already logged at info level; shared between clones so
+    /// the scanner and the watcher report each path once
+    reported: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl Default for ScanFilter {
+    fn default() -> Self {
+        Self::new(&DaemonConfig::default()).expect("default scan config is valid")
+    }
+}
+
+impl ScanFilter {
+    /// Compile the scan settings of `config`, failing on an invalid pattern
+    pub fn new(config: &DaemonConfig) -> Result<Self> {
+        Ok(Self {
+            extensions: config
+                .video_extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+            rules: config
+                .scan_rules
+                .iter()
+                .map(CompiledRules::new)
+                .collect::<Result<_>>()?,
+            reported: Arc::default(),
+        })
+    }
+
+    /// Check if a file has one of the configured video extensions
+    pub fn is_video_file(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()))
+    }
+
+    /// The rule that excludes `path` (a file or directory under `root`), if any
+    pub fn exclusion(&self, root: &Path, path: &Path, is_dir: bool) -> Option<String> {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let depth = relative.components().count();
+
+        for rules in self.rules.iter().filter(|r| r.applies_to(root)) {
+            if let Some(max_depth) = rules.max_depth {
+                // A directory at max_depth can only hold files that are too deep
+                let too_deep = if is_dir {
+                    depth >= max_depth
+                } else {
+                    depth > max_depth
+                };
+                if too_deep {
+                    return Some(format!("max_depth {}", max_depth));
+                }
+            }
+            if let Some(pattern) = rules.exclude.iter().find(|p| p.matches(relative, is_dir)) {
+                return Some(pattern.describe("exclude"));
+            }
+            // Directories are walked so files further down can still be included
+            if !is_dir
+                && !rules.include.is_empty()
+                && !rules.include.iter().any(|p| p.matches(relative, false))
+            {
+                return Some("no include pattern matched".to_string());
+            }
+        }
+
+        None
+    }
+
+    /// Whether the rules exclude `path`. The matching rule is logged at info
+    /// level the first time `path` is excluded and at debug level after that.
+    pub fn is_excluded(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let Some(rule) = self.exclusion(root, path, is_dir) else {
+            return false;
+        };
+        if self.reported.lock().unwrap().insert(path.to_path_buf()) {
+            info!("Excluded by {}: {}", rule, path.display());
+        } else {
+            debug!("Excluded by {}: {}", rule, path.display());
+        }
+        true
+    }
+
+    /// Walk `dir` (`root` itself or a directory below it), skipping hidden
+    /// directories, files without a video extension and anything the rules exclude
+    pub fn walk<'a>(
+        &'a self,
+        root: &'a Path,
+        dir: &Path,
+    ) -> impl Iterator<Item = walkdir::Result<DirEntry>> + 'a {
+        let start = dir.to_path_buf();
+        WalkDir::new(dir)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(move |e| {
+                let path = e.path();
+                if path == start {
+                    return true;
+                }
+                let is_dir = e.file_type().is_dir();
+                if is_dir {
+                    let hidden = e.file_name().to_str().is_some_and(|s| s.starts_with('.'));
+                    if hidden {
+                        return false;
+                    }
+                } else if !self.is_video_file(path) {
+                    return false;
+                }
+
+                !self.is_excluded(root, path, is_dir)
+            })
+    }
+}
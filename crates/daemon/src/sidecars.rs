@@ -27,6 +27,21 @@ pub fn write_why_file(video_path: &Path, reason: &str) -> Result<()> {
     Ok(())
 }
 
+/// Remove the .av1skip marker and .why.txt file for a video file, if present
+pub fn remove_skip_marker(video_path: &Path) -> Result<()> {
+    for sidecar in [
+        get_skip_marker_path(video_path),
+        get_why_file_path(video_path),
+    ] {
+        if sidecar.exists() {
+            fs::remove_file(&sidecar)
+                .with_context(|| format!("Failed to remove sidecar at {}", sidecar.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Check if a video file has a .av1skip marker
 pub fn has_skip_marker(video_path: &Path) -> bool {
     let skip_marker_path = get_skip_marker_path(video_path);
@@ -1,3 +1,5 @@
+use crate::config::DaemonConfig;
+
 #[derive(Debug)]
 pub enum SizeGateResult {
     Pass {
@@ -26,3 +28,53 @@ pub fn check_size_gate(original_bytes: u64, new_bytes: u64, max_ratio: f64) -> S
         }
     }
 }
+
+/// Settings for stopping an encode whose projected output size will fail the gate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EarlyAbortPolicy {
+    pub original_bytes: u64,
+    pub max_ratio: f64,
+    /// Only trust the projection after this much of the file is encoded
+    pub min_percent: f64,
+    pub min_encoded_secs: f64,
+    /// How far above the threshold the projection must be (1.1 = 10% above)
+    pub margin: f64,
+}
+
+impl EarlyAbortPolicy {
+    /// Policy for a source of `original_bytes`, or `None` when early abort is disabled
+    pub fn from_config(config: &DaemonConfig, original_bytes: u64) -> Option<Self> {
+        if !config.early_abort || original_bytes == 0 {
+            return None;
+        }
+        Some(Self {
+            original_bytes,
+            max_ratio: config.max_size_ratio,
+            min_percent: config.early_abort_min_percent,
+            min_encoded_secs: config.early_abort_min_encoded_secs as f64,
+            margin: config.early_abort_margin,
+        })
+    }
+
+    /// Return a failing gate result when the projection is clearly over the threshold
+    pub fn check(
+        &self,
+        progress_pct: f64,
+        encoded_secs: f64,
+        projected_bytes: u64,
+    ) -> Option<SizeGateResult> {
+        if progress_pct < self.min_percent || encoded_secs < self.min_encoded_secs {
+            return None;
+        }
+
+        let threshold = (self.original_bytes as f64 * self.max_ratio) as u64;
+        if projected_bytes as f64 >= threshold as f64 * self.margin {
+            Some(SizeGateResult::Fail {
+                new_bytes: projected_bytes,
+                threshold_bytes: threshold,
+            })
+        } else {
+            None
+        }
+    }
+}
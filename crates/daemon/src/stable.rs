@@ -1,7 +1,9 @@
 use crate::scan::CandidateFile;
 use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use tokio::time::sleep;
 use tracing::debug;
 
@@ -57,3 +59,141 @@ pub async fn check_stability(file: &CandidateFile, duration: Duration) -> Result
 
     Ok(is_stable)
 }
+
+/// Size and mtime of a file at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileSnapshot {
+    size_bytes: u64,
+    modified: SystemTime,
+}
+
+impl FileSnapshot {
+    fn of(path: &Path) -> std::io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self {
+            size_bytes: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+}
+
+/// Check a batch of files for stability, returning the paths that are not stable.
+///
+/// A file is stable when its mtime is at least `quiet_period` old, its size and
+/// mtime do not change across one `recheck_interval` (shared by the whole
+/// batch), and no process has it open for writing.
+pub async fn find_unstable(
+    files: &[&CandidateFile],
+    quiet_period: Duration,
+    recheck_interval: Duration,
+) -> HashSet<PathBuf> {
+    let now = SystemTime::now();
+    let mut unstable = HashSet::new();
+    let mut snapshots = Vec::new();
+
+    for file in files {
+        match FileSnapshot::of(&file.path) {
+            Ok(snapshot) => {
+                // An mtime in the future (clock skew on a NAS) is left to the re-check
+                let age = now
+                    .duration_since(snapshot.modified)
+                    .unwrap_or(quiet_period);
+                if age < quiet_period {
+                    debug!(
+                        "File modified {}s ago, not yet quiet: {}",
+                        age.as_secs(),
+                        file.path.display()
+                    );
+                    unstable.insert(file.path.clone());
+                } else {
+                    snapshots.push((file.path.clone(), snapshot));
+                }
+            }
+            Err(e) => {
+                debug!("Failed to get metadata for {}: {}", file.path.display(), e);
+                unstable.insert(file.path.clone());
+            }
+        }
+    }
+
+    if snapshots.is_empty() {
+        return unstable;
+    }
+
+    debug!(
+        "Re-checking {} files in {} seconds",
+        snapshots.len(),
+        recheck_interval.as_secs()
+    );
+    sleep(recheck_interval).await;
+
+    let writing = open_for_writing(snapshots.iter().map(|(path, _)| path.as_path()));
+    for (path, before) in snapshots {
+        let changed = FileSnapshot::of(&path).map_or(true, |after| after != before);
+        if changed {
+            debug!("File changed during the re-check: {}", path.display());
+            unstable.insert(path);
+        } else if writing.contains(&path) {
+            debug!("File is open for writing: {}", path.display());
+            unstable.insert(path);
+        }
+    }
+
+    unstable
+}
+
+/// Paths among `paths` that some process has open for writing.
+///
+/// Reads `/proc/*/fd`; processes whose descriptors cannot be read (other users
+/// when not running as root) are not seen.
+pub fn open_for_writing<'a>(paths: impl IntoIterator<Item = &'a Path>) -> HashSet<PathBuf> {
+    // /proc reports resolved paths; map them back to the paths we were given
+    let wanted: HashMap<PathBuf, &Path> = paths
+        .into_iter()
+        .filter_map(|path| fs::canonicalize(path).ok().map(|real| (real, path)))
+        .collect();
+    let mut found = HashSet::new();
+    if wanted.is_empty() {
+        return found;
+    }
+
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return found;
+    };
+    for process in processes.flatten() {
+        let pid_dir = process.path();
+        let is_pid = process
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit()));
+        if !is_pid {
+            continue;
+        }
+        let Ok(fds) = fs::read_dir(pid_dir.join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(path) = wanted.get(&target) else {
+                continue;
+            };
+            let fdinfo = pid_dir.join("fdinfo").join(fd.file_name());
+            if fs::read_to_string(fdinfo).is_ok_and(|info| is_write_mode(&info)) {
+                found.insert(path.to_path_buf());
+            }
+        }
+    }
+
+    found
+}
+
+/// Whether the `flags:` line of an fdinfo file has O_WRONLY or O_RDWR set
+fn is_write_mode(fdinfo: &str) -> bool {
+    fdinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|flags| u32::from_str_radix(flags.trim(), 8).ok())
+        .is_some_and(|flags| flags & 0o3 != 0)
+}
@@ -1,7 +1,11 @@
-use crate::config::EncoderPreference;
+use crate::config::{DaemonConfig, EncoderPreference, InterruptedJobPolicy};
+use crate::jobs::{load_all_jobs, reset_for_requeue, save_job, update_job_status, Job, JobStatus};
+use crate::sidecars::{create_skip_marker, write_why_file};
 use anyhow::{anyhow, Context, Result};
 use regex::Regex;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use tracing::{info, warn};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AvailableEncoder {
@@ -134,6 +138,156 @@ fn encoder_to_selected(encoder: AvailableEncoder) -> SelectedEncoder {
     }
 }
 
+/// Reconcile jobs left Running or Pending by a previous daemon run.
+///
+/// Jobs whose temp output is still being written by a live ffmpeg process are
+/// left alone; `recover_orphaned_jobs` picks them up once it exits. Every other
+/// unfinished job gets its partial temp outputs deleted, an "Interrupted"
+/// reason, and is then requeued or failed according to `interrupted_job_policy`.
+/// Returns the jobs that were recovered.
+pub fn recover_interrupted_jobs(config: &DaemonConfig) -> Result<Vec<Job>> {
+    recover_jobs(config, |job| {
+        matches!(job.status, JobStatus::Running | JobStatus::Pending)
+    })
+}
+
+/// Reconcile `Running` jobs whose encoder outlived the previous daemon run,
+/// once that encoder has exited.
+///
+/// Meant to run on every scan. Jobs `is_active` reports as handled by this
+/// daemon are left alone, and so are jobs whose encoder is still alive.
+pub fn recover_orphaned_jobs(
+    config: &DaemonConfig,
+    is_active: impl Fn(&Job) -> bool,
+) -> Result<Vec<Job>> {
+    recover_jobs(config, |job| {
+        job.status == JobStatus::Running && !is_active(job)
+    })
+}
+
+fn recover_jobs(config: &DaemonConfig, unfinished: impl Fn(&Job) -> bool) -> Result<Vec<Job>> {
+    let mut recovered = Vec::new();
+
+    for mut job in load_all_jobs(&config.job_state_dir)? {
+        if !unfinished(&job) {
+            continue;
+        }
+        let was_running = job.status == JobStatus::Running;
+
+        let temp_outputs = temp_outputs_for_job(&job, &config.temp_output_dir);
+        if let Some(pid) = find_process_using(&temp_outputs) {
+            warn!(
+                "Job {} still has a live encoder (pid {}), leaving it as {:?}",
+                job.id, pid, job.status
+            );
+            continue;
+        }
+
+        for partial in &temp_outputs {
+            match std::fs::remove_file(partial) {
+                Ok(()) => info!("Removed partial output {:?} for job {}", partial, job.id),
+                Err(e) => warn!("Failed to remove partial output {:?}: {}", partial, e),
+            }
+        }
+
+        let reason = if was_running {
+            "Interrupted: daemon stopped during encoding"
+        } else {
+            "Interrupted: daemon stopped while job was queued"
+        };
+
+        let requeue = match config.interrupted_job_policy {
+            InterruptedJobPolicy::Requeue => true,
+            InterruptedJobPolicy::RequeueQueued => !was_running,
+            InterruptedJobPolicy::Fail => false,
+        };
+
+        if requeue {
+            info!(
+                "Requeueing interrupted job {} for {:?}",
+                job.id, job.source_path
+            );
+            reset_for_requeue(&mut job);
+            job.reason = Some(format!("{}; requeued", reason));
+            save_job(&job, &config.job_state_dir)?;
+        } else {
+            info!(
+                "Failing interrupted job {} for {:?}",
+                job.id, job.source_path
+            );
+            job.reason = Some(reason.to_string());
+            update_job_status(&mut job, JobStatus::Failed, &config.job_state_dir)?;
+
+            // Keep the scanner from starting over; retry from the TUI clears this
+            if job.source_path.exists() {
+                create_skip_marker(&job.source_path)?;
+                if config.write_why_sidecars {
+                    write_why_file(&job.source_path, reason)?;
+                }
+            }
+        }
+
+        recovered.push(job);
+    }
+
+    Ok(recovered)
+}
+
+/// Temp files belonging to a job: its recorded output path plus any
+/// `<job id>.*` file in the temp output directory.
+fn temp_outputs_for_job(job: &Job, temp_output_dir: &Path) -> Vec<PathBuf> {
+    let prefix = format!("{}.", job.id);
+    let mut outputs: Vec<PathBuf> = std::fs::read_dir(temp_output_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        .map(|entry| entry.path())
+        .collect();
+
+    if let Some(output_path) = &job.output_path {
+        if output_path.exists() && !outputs.contains(output_path) {
+            outputs.push(output_path.clone());
+        }
+    }
+
+    outputs
+}
+
+/// Find a running process whose command line references one of `paths`
+fn find_process_using(paths: &[PathBuf]) -> Option<u32> {
+    if paths.is_empty() {
+        return None;
+    }
+
+    let own_pid = std::process::id();
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        let Ok(cmdline) = std::fs::read(entry.path().join("cmdline")) else {
+            continue;
+        };
+        let references_output = cmdline.split(|b| *b == 0).any(|arg| {
+            paths
+                .iter()
+                .any(|p| p.as_os_str().as_encoded_bytes() == arg)
+        });
+        if references_output {
+            return Some(pid);
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,8 +343,8 @@ mod tests {
             ("ffmpeg version 9.0.0", true),
         ];
 
+        let re = Regex::new(r"ffmpeg version[^\d]*(\d+)\.(\d+)\.(\d+)").unwrap();
         for (version_str, should_accept) in test_cases {
-            let re = Regex::new(r"ffmpeg version[^\d]*(\d+)\.(\d+)\.(\d+)").unwrap();
             if let Some(caps) = re.captures(version_str) {
                 let major: u32 = caps[1].parse().unwrap();
                 let is_valid = major >= 8;
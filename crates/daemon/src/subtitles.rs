@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::config::{SubtitleExtraction, TrackPolicy};
+use crate::container::OutputContainer;
+use crate::probe::ProbeResult;
+use crate::tracks::select_tracks;
+
+/// Text subtitle codecs ffmpeg can convert between
+const TEXT_CODECS: &[&str] = &["subrip", "srt", "ass", "ssa", "mov_text", "webvtt", "text"];
+
+const MATROSKA_SUBTITLE_CODECS: &[&str] = &[
+    "subrip",
+    "srt",
+    "ass",
+    "ssa",
+    "webvtt",
+    "hdmv_pgs_subtitle",
+    "dvd_subtitle",
+    "dvb_subtitle",
+];
+const MP4_SUBTITLE_CODECS: &[&str] = &["mov_text", "dvd_subtitle"];
+const WEBM_SUBTITLE_CODECS: &[&str] = &["webvtt"];
+
+/// What happens to a kept subtitle track in the output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SubtitleAction {
+    Copy,
+    /// Re-encoded with the ffmpeg subtitle encoder `codec`
+    Convert {
+        codec: String,
+    },
+    /// Left out because the output container cannot hold it
+    Drop {
+        reason: String,
+    },
+}
+
+/// A subtitle track kept by the track policy and how it is written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubtitleTrack {
+    pub index: usize,
+    pub codec_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(flatten)]
+    pub action: SubtitleAction,
+    /// Sidecar file the track was extracted to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sidecar: Option<PathBuf>,
+}
+
+impl SubtitleTrack {
+    pub fn is_dropped(&self) -> bool {
+        matches!(self.action, SubtitleAction::Drop { .. })
+    }
+
+    /// Codec name ffprobe reports for the track in the output; `None` when dropped
+    pub fn output_codec_name(&self) -> Option<&str> {
+        match &self.action {
+            SubtitleAction::Copy => Some(&self.codec_name),
+            SubtitleAction::Convert { codec } if codec == "srt" => Some("subrip"),
+            SubtitleAction::Convert { codec } => Some(codec),
+            SubtitleAction::Drop { .. } => None,
+        }
+    }
+}
+
+/// How a subtitle track in `codec_name` is written to `container`
+pub fn subtitle_action(codec_name: &str, container: OutputContainer) -> SubtitleAction {
+    let (supported, text_target) = match container {
+        OutputContainer::Matroska => (MATROSKA_SUBTITLE_CODECS, "srt"),
+        OutputContainer::Mp4 => (MP4_SUBTITLE_CODECS, "mov_text"),
+        OutputContainer::WebM => (WEBM_SUBTITLE_CODECS, "webvtt"),
+    };
+
+    if supported.contains(&codec_name) {
+        SubtitleAction::Copy
+    } else if TEXT_CODECS.contains(&codec_name) {
+        SubtitleAction::Convert {
+            codec: text_target.to_string(),
+        }
+    } else {
+        SubtitleAction::Drop {
+            reason: format!(
+                "{} subtitles cannot be written to .{}",
+                codec_name,
+                container.extension()
+            ),
+        }
+    }
+}
+
+/// Subtitle tracks `track_policy` keeps, in output order, with how each is written
+pub fn plan_subtitles(
+    probe: &ProbeResult,
+    track_policy: &TrackPolicy,
+    container: OutputContainer,
+) -> Vec<SubtitleTrack> {
+    select_tracks(probe, track_policy)
+        .subtitles
+        .into_iter()
+        .filter_map(|index| probe.subtitle_streams.iter().find(|s| s.index == index))
+        .map(|stream| SubtitleTrack {
+            index: stream.index,
+            codec_name: stream.codec_name.clone(),
+            language: stream.language.clone(),
+            action: subtitle_action(&stream.codec_name, container),
+            sidecar: None,
+        })
+        .collect()
+}
+
+/// Per-track `-c:s:N` arguments for the tracks of `plan` that are not dropped
+pub fn subtitle_codec_flags(plan: &[SubtitleTrack]) -> Vec<String> {
+    plan.iter()
+        .filter(|track| !track.is_dropped())
+        .enumerate()
+        .flat_map(|(n, track)| {
+            let codec = match &track.action {
+                SubtitleAction::Convert { codec } => codec.clone(),
+                _ => "copy".to_string(),
+            };
+            [format!("-c:s:{}", n), codec]
+        })
+        .collect()
+}
+
+/// Sidecar extension and ffmpeg encoder for a subtitle codec, if it can be extracted
+fn sidecar_format(codec_name: &str) -> Option<(&'static str, &'static str)> {
+    match codec_name {
+        "subrip" | "srt" => Some(("srt", "copy")),
+        "ass" | "ssa" => Some(("ass", "copy")),
+        "webvtt" => Some(("vtt", "copy")),
+        "mov_text" | "text" => Some(("srt", "srt")),
+        "hdmv_pgs_subtitle" => Some(("sup", "copy")),
+        _ => None,
+    }
+}
+
+/// Sidecar files to write next to `video` for the tracks `extract` selects, as
+/// `(position in plan, path)`.
+///
+/// Sidecars are named `<stem>.<language>.<ext>`; a second track with the same
+/// language and format gets its stream index, `<stem>.<language>.<index>.<ext>`.
+pub fn plan_sidecars(
+    video: &Path,
+    plan: &[SubtitleTrack],
+    extract: SubtitleExtraction,
+) -> Vec<(usize, PathBuf)> {
+    let stem = video
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut taken = HashSet::new();
+    let mut sidecars = Vec::new();
+    for (position, track) in plan.iter().enumerate() {
+        let wanted = match extract {
+            SubtitleExtraction::None => false,
+            SubtitleExtraction::Dropped => track.is_dropped(),
+            SubtitleExtraction::All => true,
+        };
+        let Some((extension, _)) = sidecar_format(&track.codec_name).filter(|_| wanted) else {
+            continue;
+        };
+
+        let language = track.language.as_deref().unwrap_or("und").to_lowercase();
+        let mut name = format!("{}.{}.{}", stem, language, extension);
+        if !taken.insert(name.clone()) {
+            name = format!("{}.{}.{}.{}", stem, language, track.index, extension);
+            taken.insert(name.clone());
+        }
+        sidecars.push((position, video.with_file_name(name)));
+    }
+    sidecars
+}
+
+/// Write subtitle stream `track` of `source` to `sidecar`, converting
+/// `mov_text` to SubRip
+pub async fn extract_sidecar(
+    ffmpeg: &str,
+    source: &Path,
+    track: &SubtitleTrack,
+    sidecar: &Path,
+) -> Result<()> {
+    let (_, encoder) = sidecar_format(&track.codec_name)
+        .with_context(|| format!("{} subtitles cannot be extracted", track.codec_name))?;
+    if sidecar.exists() {
+        anyhow::bail!("{:?} already exists", sidecar);
+    }
+
+    let output = Command::new(ffmpeg)
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .arg("-i")
+        .arg(source)
+        .arg("-map")
+        .arg(format!("0:{}", track.index))
+        .arg("-c:s")
+        .arg(encoder)
+        .arg(sidecar)
+        .output()
+        .await
+        .context("Failed to execute ffmpeg for subtitle extraction")?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(sidecar);
+        anyhow::bail!(
+            "Subtitle extraction of stream {} failed: {}",
+            track.index,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
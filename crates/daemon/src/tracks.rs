@@ -0,0 +1,161 @@
+use crate::config::TrackPolicy;
+use crate::probe::ProbeResult;
+
+/// Tracks chosen for the output, by input stream index
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackSelection {
+    pub audio: Vec<usize>,
+    pub subtitles: Vec<usize>,
+    pub dropped: Vec<DroppedTrack>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedTrack {
+    pub index: usize,
+    pub kind: TrackKind,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackKind {
+    Audio,
+    Subtitle,
+}
+
+/// Properties of a track that the policy looks at
+struct TrackInfo<'a> {
+    index: usize,
+    language: Option<&'a str>,
+    is_default: bool,
+    is_commentary: bool,
+}
+
+/// Apply `policy` to the audio and subtitle streams of `probe`.
+///
+/// Rules, in order:
+/// 1. Default tracks are always kept.
+/// 2. Commentary tracks are dropped when `drop_commentary` is set.
+/// 3. Tracks in the original language (that of the first audio track) are kept.
+/// 4. Tracks in a drop list, outside a non-empty keep list, or untagged/`und`
+///    with `drop_undetermined` are dropped.
+///
+/// If that would leave no audio, the first default (or else first) audio track is kept.
+pub fn select_tracks(probe: &ProbeResult, policy: &TrackPolicy) -> TrackSelection {
+    let original_language = probe
+        .audio_streams
+        .first()
+        .and_then(|s| s.language.as_deref())
+        .filter(|l| !is_undetermined(Some(l)))
+        .map(str::to_lowercase);
+
+    let audio: Vec<TrackInfo> = probe
+        .audio_streams
+        .iter()
+        .map(|s| TrackInfo {
+            index: s.index,
+            language: s.language.as_deref(),
+            is_default: s.is_default,
+            is_commentary: s.is_commentary,
+        })
+        .collect();
+    let subtitles: Vec<TrackInfo> = probe
+        .subtitle_streams
+        .iter()
+        .map(|s| TrackInfo {
+            index: s.index,
+            language: s.language.as_deref(),
+            is_default: s.is_default,
+            is_commentary: s.is_commentary,
+        })
+        .collect();
+
+    let mut selection = TrackSelection::default();
+
+    for track in &audio {
+        match drop_reason(
+            track,
+            original_language.as_deref(),
+            &policy.audio_keep_languages,
+            &policy.audio_drop_languages,
+            policy,
+        ) {
+            None => selection.audio.push(track.index),
+            Some(reason) => selection.dropped.push(DroppedTrack {
+                index: track.index,
+                kind: TrackKind::Audio,
+                reason,
+            }),
+        }
+    }
+
+    // Never produce a file without audio when the source had some
+    if selection.audio.is_empty() {
+        if let Some(keep) = audio.iter().find(|t| t.is_default).or(audio.first()) {
+            selection.audio.push(keep.index);
+            selection.dropped.retain(|d| d.index != keep.index);
+        }
+    }
+
+    for track in &subtitles {
+        match drop_reason(
+            track,
+            original_language.as_deref(),
+            &policy.subtitle_keep_languages,
+            &policy.subtitle_drop_languages,
+            policy,
+        ) {
+            None => selection.subtitles.push(track.index),
+            Some(reason) => selection.dropped.push(DroppedTrack {
+                index: track.index,
+                kind: TrackKind::Subtitle,
+                reason,
+            }),
+        }
+    }
+
+    selection
+}
+
+fn drop_reason(
+    track: &TrackInfo,
+    // Lowercased
+    original_language: Option<&str>,
+    keep_languages: &[String],
+    drop_languages: &[String],
+    policy: &TrackPolicy,
+) -> Option<String> {
+    if track.is_default {
+        return None;
+    }
+    if policy.drop_commentary && track.is_commentary {
+        return Some("commentary".to_string());
+    }
+
+    if is_undetermined(track.language) {
+        return policy
+            .drop_undetermined
+            .then(|| "undetermined language".to_string());
+    }
+
+    let language = track.language.unwrap_or_default().to_lowercase();
+    if Some(language.as_str()) == original_language {
+        return None;
+    }
+
+    let listed = |list: &[String]| list.iter().any(|l| l.eq_ignore_ascii_case(&language));
+    if listed(drop_languages) {
+        return Some(format!("language {} is in the drop list", language));
+    }
+    if !keep_languages.is_empty() && !listed(keep_languages) {
+        return Some(format!("language {} is not in the keep list", language));
+    }
+
+    None
+}
+
+fn is_undetermined(language: Option<&str>) -> bool {
+    match language {
+        None => true,
+        Some(l) => l.is_empty() || l.eq_ignore_ascii_case("und"),
+    }
+}
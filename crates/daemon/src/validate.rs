@@ -1,7 +1,19 @@
-use crate::probe::{probe_file, ProbeResult};
+use crate::audio::plan_audio;
+use crate::config::DaemonConfig;
+use crate::container::OutputContainer;
+use crate::interlace::ScanType;
+use crate::jobs::Job;
+use crate::probe::{count_video_frames, probe_file, ColorInfo, DynamicRange, ProbeResult};
+use crate::subtitles::plan_subtitles;
 use anyhow::Result;
 use std::path::Path;
 
+/// Allowed difference between the expected and the encoded frame count, as a
+/// fraction of the expected count
+const FRAME_COUNT_TOLERANCE: f64 = 0.01;
+/// ...but never less than this many frames
+const MIN_FRAME_COUNT_TOLERANCE: u64 = 2;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValidationResult {
     Valid(ProbeResult),
@@ -13,7 +25,146 @@ pub enum ValidationError {
     ProbeFailure(String),
     NoAv1Stream,
     MultipleAv1Streams,
-    DurationMismatch { expected: f64, actual: f64 },
+    DurationMismatch {
+        expected: f64,
+        actual: f64,
+    },
+    /// Output colour/HDR signalling differs from the source, e.g. an HDR10 source tagged as SDR
+    HdrMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// A sampled quality metric of the output fell below its configured minimum
+    QualityBelowThreshold {
+        metric: String,
+        score: f64,
+        threshold: f64,
+    },
+    /// Output audio tracks differ from the ones the track and audio policies keep,
+    /// listed as "codec (language)"
+    AudioTrackMismatch {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    /// Output subtitle tracks differ from the ones the track policy keeps
+    SubtitleTrackMismatch {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    ChapterCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// Font attachments of a Matroska source are missing from the output
+    MissingFontAttachments {
+        missing: Vec<String>,
+    },
+    FrameCountMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    /// The full decode of the output logged errors; `messages` holds the first few
+    DecodeErrors {
+        count: usize,
+        messages: Vec<String>,
+    },
+}
+
+/// An audio or subtitle track the output must contain
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpectedTrack {
+    /// Codec name as ffprobe reports it
+    pub codec_name: String,
+    pub language: Option<String>,
+}
+
+impl ExpectedTrack {
+    /// Whether `codec_name` and `language` of an output track match; an
+    /// untagged or undetermined source language matches any language
+    pub fn matches(&self, codec_name: &str, language: Option<&str>) -> bool {
+        let language_matches = match self.language.as_deref() {
+            None | Some("und") => true,
+            Some(expected) => language.is_some_and(|l| l.eq_ignore_ascii_case(expected)),
+        };
+        self.codec_name == codec_name && language_matches
+    }
+}
+
+/// What the output of a job must contain besides its AV1 stream
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExpectedStreams {
+    pub audio: Vec<ExpectedTrack>,
+    pub subtitles: Vec<ExpectedTrack>,
+    pub chapters: usize,
+    /// File names of the source's font attachments
+    pub fonts: Vec<String>,
+    /// Video frame count, when the source container records it
+    pub frames: Option<u64>,
+}
+
+impl ExpectedStreams {
+    /// Streams the encode of `job` to `output_path` was built to write, from the
+    /// same audio and subtitle plans as the encoder command
+    pub fn for_job(
+        job: &Job,
+        probe: &ProbeResult,
+        config: &DaemonConfig,
+        output_path: &Path,
+    ) -> Self {
+        let container = OutputContainer::from_path(output_path);
+        let audio = plan_audio(probe, &config.track_policy, &config.audio_policy)
+            .into_iter()
+            .filter_map(|output| {
+                let source = probe
+                    .audio_streams
+                    .iter()
+                    .find(|s| s.index == output.source_index)?;
+                Some(ExpectedTrack {
+                    codec_name: match output.transcode {
+                        Some(transcode) => transcode.codec.codec_name().to_string(),
+                        None => source.codec_name.clone(),
+                    },
+                    language: source.language.clone(),
+                })
+            })
+            .collect();
+        let subtitles = plan_subtitles(probe, &config.track_policy, container)
+            .iter()
+            .filter_map(|track| {
+                Some(ExpectedTrack {
+                    codec_name: track.output_codec_name()?.to_string(),
+                    language: track.language.clone(),
+                })
+            })
+            .collect();
+        // Only Matroska can carry attachments
+        let fonts = match container {
+            OutputContainer::Matroska => probe
+                .attachments
+                .iter()
+                .filter(|a| a.is_font())
+                .filter_map(|a| a.filename.clone())
+                .collect(),
+            _ => Vec::new(),
+        };
+        // Inverse telecine drops one frame in five
+        let telecined = job
+            .interlace
+            .as_ref()
+            .is_some_and(|d| d.scan_type == ScanType::Telecined);
+        let frames = probe
+            .main_video_stream()
+            .and_then(|v| v.frame_count)
+            .map(|n| if telecined { n * 4 / 5 } else { n });
+
+        Self {
+            audio,
+            subtitles,
+            chapters: probe.chapters,
+            fonts,
+            frames,
+        }
+    }
 }
 
 /// Validate encoded output file
@@ -22,9 +173,13 @@ pub enum ValidationError {
 /// 1. FFprobe can read the file
 /// 2. Exactly one AV1 video stream exists
 /// 3. Duration matches original within 2 seconds
+/// 4. HDR signalling matches the source (see `check_hdr_signalling`)
+/// 5. Audio, subtitle, chapter and font streams match `expected` (see `check_stream_parity`)
+/// 6. The video frame count matches `expected` within 1%, when the source's is known
 pub async fn validate_output(
     output_path: &Path,
     original_probe: &ProbeResult,
+    expected: &ExpectedStreams,
 ) -> Result<ValidationResult> {
     // Execute ffprobe on output file
     let output_probe = match probe_file(output_path).await {
@@ -68,6 +223,157 @@ pub async fn validate_output(
         }
     }
 
+    // Check HDR signalling survived the encode
+    if let Some(source_video) = original_probe.main_video_stream() {
+        if let Some(error) = check_hdr_signalling(&source_video.color, &av1_streams[0].color) {
+            return Ok(ValidationResult::Invalid(error));
+        }
+    }
+
+    if let Some(error) = check_stream_parity(expected, &output_probe) {
+        return Ok(ValidationResult::Invalid(error));
+    }
+
+    // Counting frames reads the whole file, so it goes last
+    if let Some(expected_frames) = expected.frames {
+        let actual = count_video_frames(output_path).await?;
+        if let Some(error) = check_frame_count(expected_frames, actual) {
+            return Ok(ValidationResult::Invalid(error));
+        }
+    }
+
     // All validation checks passed
     Ok(ValidationResult::Valid(output_probe))
 }
+
+/// Compare the colour signalling of the source and the encoded video stream.
+///
+/// The transfer function (SDR, PQ or HLG) must match, and for HDR sources so must
+/// the primaries. A Dolby Vision source whose base layer is not displayable on its
+/// own (profile 5) must keep its Dolby Vision configuration; otherwise dropping
+/// Dolby Vision is fine as long as the base layer signalling is intact.
+pub fn check_hdr_signalling(source: &ColorInfo, output: &ColorInfo) -> Option<ValidationError> {
+    let mismatch = || {
+        Some(ValidationError::HdrMismatch {
+            expected: source.describe(),
+            actual: output.describe(),
+        })
+    };
+
+    if let Some(dv) = &source.dolby_vision {
+        if !dv.has_compatible_base_layer() && output.dolby_vision.is_none() {
+            return mismatch();
+        }
+        // Profile 5 has no meaningful base layer signalling to compare
+        if !dv.has_compatible_base_layer() {
+            return None;
+        }
+    }
+
+    if source.dynamic_range() != output.dynamic_range() {
+        return mismatch();
+    }
+
+    if source.dynamic_range() != DynamicRange::Sdr
+        && source.primaries.is_some()
+        && source.primaries != output.primaries
+    {
+        return mismatch();
+    }
+
+    None
+}
+
+/// Compare the audio and subtitle tracks, chapters and font attachments of the
+/// output with what the job was built to write.
+///
+/// Tracks must appear in the same order with the same codec and, where the
+/// source tagged one, the same language. Extra attachments are fine.
+pub fn check_stream_parity(
+    expected: &ExpectedStreams,
+    output: &ProbeResult,
+) -> Option<ValidationError> {
+    let audio: Vec<(&str, Option<&str>)> = output
+        .audio_streams
+        .iter()
+        .map(|s| (s.codec_name.as_str(), s.language.as_deref()))
+        .collect();
+    if !tracks_match(&expected.audio, &audio) {
+        return Some(ValidationError::AudioTrackMismatch {
+            expected: describe_expected(&expected.audio),
+            actual: describe_actual(&audio),
+        });
+    }
+
+    let subtitles: Vec<(&str, Option<&str>)> = output
+        .subtitle_streams
+        .iter()
+        .map(|s| (s.codec_name.as_str(), s.language.as_deref()))
+        .collect();
+    if !tracks_match(&expected.subtitles, &subtitles) {
+        return Some(ValidationError::SubtitleTrackMismatch {
+            expected: describe_expected(&expected.subtitles),
+            actual: describe_actual(&subtitles),
+        });
+    }
+
+    if expected.chapters != output.chapters {
+        return Some(ValidationError::ChapterCountMismatch {
+            expected: expected.chapters,
+            actual: output.chapters,
+        });
+    }
+
+    let missing: Vec<String> = expected
+        .fonts
+        .iter()
+        .filter(|font| {
+            !output
+                .attachments
+                .iter()
+                .any(|a| a.filename.as_ref() == Some(*font))
+        })
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        return Some(ValidationError::MissingFontAttachments { missing });
+    }
+
+    None
+}
+
+/// Compare the encoded frame count with the expected one, allowing 1% (at
+/// least two frames) for encoder and container differences at the edges
+pub fn check_frame_count(expected: u64, actual: u64) -> Option<ValidationError> {
+    let tolerance =
+        ((expected as f64 * FRAME_COUNT_TOLERANCE) as u64).max(MIN_FRAME_COUNT_TOLERANCE);
+    (expected.abs_diff(actual) > tolerance)
+        .then_some(ValidationError::FrameCountMismatch { expected, actual })
+}
+
+fn tracks_match(expected: &[ExpectedTrack], actual: &[(&str, Option<&str>)]) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .iter()
+            .zip(actual)
+            .all(|(e, (codec, language))| e.matches(codec, *language))
+}
+
+fn describe_expected(tracks: &[ExpectedTrack]) -> Vec<String> {
+    tracks
+        .iter()
+        .map(|t| describe_track(&t.codec_name, t.language.as_deref()))
+        .collect()
+}
+
+fn describe_actual(tracks: &[(&str, Option<&str>)]) -> Vec<String> {
+    tracks
+        .iter()
+        .map(|(codec, language)| describe_track(codec, *language))
+        .collect()
+}
+
+/// "codec (language)", e.g. "opus (eng)"
+fn describe_track(codec_name: &str, language: Option<&str>) -> String {
+    format!("{} ({})", codec_name, language.unwrap_or("und"))
+}
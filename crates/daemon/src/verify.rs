@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use chrono::{Duration as ChronoDuration, Utc};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use crate::jobs::{save_job, Job, JobStage};
+use crate::validate::ValidationError;
+
+/// Decoder messages kept in a `DecodeErrors` validation error
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// Decode the video and audio of `path` in full and report any decoder error.
+///
+/// The job is moved to the `Verifying` stage and its live progress fields are
+/// reused for the decode: `progress` is the share of the duration decoded,
+/// `speed_bps` the bytes of the file decoded per second and `eta` the projected
+/// end. Failing to run ffmpeg at all is an `Err`.
+pub async fn verify_decode(
+    ffmpeg: &str,
+    path: &Path,
+    job: &mut Job,
+    job_state_dir: &Path,
+) -> Result<Option<ValidationError>> {
+    let file_bytes = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read {:?}", path))?
+        .len();
+
+    let mut child = Command::new(ffmpeg)
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .arg("-v")
+        .arg("error")
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats")
+        .arg("-i")
+        .arg(path)
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-map")
+        .arg("0:a?")
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute ffmpeg for decode verification")?;
+    let stdout = child.stdout.take().context("Failed to capture stdout")?;
+    let stderr = child.stderr.take().context("Failed to capture stderr")?;
+
+    // With `-v error`, everything on stderr is an error
+    let stderr_task = tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        let mut errors = Vec::new();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if !line.trim().is_empty() {
+                errors.push(line);
+            }
+        }
+        errors
+    });
+
+    job.stage = Some(JobStage::Verifying);
+    job.progress = Some(0.0);
+    job.eta = None;
+    job.speed_bps = None;
+    save_job(job, job_state_dir)?;
+
+    let started = Instant::now();
+    let mut last_save = Instant::now();
+    let mut lines = BufReader::new(stdout).lines();
+    while let Some(line) = lines.next_line().await? {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        // out_time_ms is in microseconds too
+        if !matches!(key, "out_time_us" | "out_time_ms") {
+            continue;
+        }
+        let Ok(micros) = value.parse::<u64>() else {
+            continue;
+        };
+        if last_save.elapsed() >= Duration::from_millis(750) {
+            record_verify_progress(
+                job,
+                micros as f64 / 1_000_000.0,
+                file_bytes,
+                started.elapsed(),
+            );
+            save_job(job, job_state_dir)?;
+            last_save = Instant::now();
+        }
+    }
+
+    let status = child.wait().await.context("Failed to wait for ffmpeg")?;
+    let mut errors = stderr_task.await.context("Failed to read ffmpeg output")?;
+
+    if !status.success() && errors.is_empty() {
+        errors.push(format!("ffmpeg exited with {}", status));
+    }
+    if errors.is_empty() {
+        job.progress = Some(100.0);
+        job.eta = None;
+        save_job(job, job_state_dir)?;
+        return Ok(None);
+    }
+
+    let count = errors.len();
+    errors.truncate(MAX_REPORTED_ERRORS);
+    Ok(Some(ValidationError::DecodeErrors {
+        count,
+        messages: errors,
+    }))
+}
+
+/// Set the job's progress, throughput and ETA after `decoded_secs` of a
+/// `file_bytes` output were decoded in `elapsed`
+pub fn record_verify_progress(
+    job: &mut Job,
+    decoded_secs: f64,
+    file_bytes: u64,
+    elapsed: Duration,
+) {
+    let Some(duration) = job.original_duration.filter(|&d| d > 0.0) else {
+        return;
+    };
+    let fraction = (decoded_secs / duration).clamp(0.0, 1.0);
+    job.progress = Some(fraction * 100.0);
+
+    let elapsed_secs = elapsed.as_secs_f64();
+    if fraction > 0.0 && elapsed_secs > 0.0 {
+        job.speed_bps = Some(file_bytes as f64 * fraction / elapsed_secs);
+        let remaining = elapsed_secs * (1.0 - fraction) / fraction;
+        job.eta = Some(Utc::now() + ChronoDuration::milliseconds((remaining * 1000.0) as i64));
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask, Watches};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::scan_filter::ScanFilter;
+
+/// Events watched on every library directory. Files are reported once they
+/// are closed after writing or moved in; new directories are watched as they
+/// appear.
+const DIR_MASK: WatchMask = WatchMask::CLOSE_WRITE
+    .union(WatchMask::MOVED_TO)
+    .union(WatchMask::CREATE)
+    .union(WatchMask::ONLYDIR);
+
+/// Something the library watcher noticed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A video file was closed after writing, or moved into a library
+    File(PathBuf),
+    /// The kernel dropped events; only a full rescan can catch up
+    Overflow,
+}
+
+/// inotify watches on every directory under the library roots.
+///
+/// Events are read on a dedicated thread and handed over through a channel.
+pub struct LibraryWatcher {
+    events: mpsc::UnboundedReceiver<WatchEvent>,
+}
+
+impl LibraryWatcher {
+    /// Watch every non-hidden directory under `roots`
+    pub fn start(roots: &[PathBuf]) -> Result<Self> {
+        Self::start_with_filter(roots, ScanFilter::default())
+    }
+
+    /// Watch the directories under `roots` that `filter` does not exclude,
+    /// reporting only the files it admits
+    pub fn start_with_filter(roots: &[PathBuf], filter: ScanFilter) -> Result<Self> {
+        let inotify = Inotify::init().context("Failed to initialise inotify")?;
+        let mut dirs = WatchedDirs {
+            watches: inotify.watches(),
+            paths: HashMap::new(),
+            filter,
+        };
+
+        for root in roots {
+            if root.is_dir() {
+                dirs.add_tree(root, root);
+            } else {
+                warn!(
+                    "Library root is not a directory, not watching: {}",
+                    root.display()
+                );
+            }
+        }
+        if dirs.paths.is_empty() {
+            anyhow::bail!("No library directory could be watched");
+        }
+        debug!("Watching {} directories", dirs.paths.len());
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        std::thread::Builder::new()
+            .name("av1d-watch".to_string())
+            .spawn(move || read_events(inotify, dirs, tx))
+            .context("Failed to start the watch thread")?;
+
+        Ok(Self { events: rx })
+    }
+
+    /// Next event, or `None` once the watch thread has stopped
+    pub async fn next_event(&mut self) -> Option<WatchEvent> {
+        self.events.recv().await
+    }
+}
+
+/// A watched directory and the library root it belongs to
+struct WatchedDir {
+    root: PathBuf,
+    path: PathBuf,
+}
+
+struct WatchedDirs {
+    watches: Watches,
+    paths: HashMap<WatchDescriptor, WatchedDir>,
+    filter: ScanFilter,
+}
+
+impl WatchedDirs {
+    /// Watch `dir` (under library root `root`) and every directory below it
+    /// the filter keeps, returning the video files already there
+    fn add_tree(&mut self, root: &Path, dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let entries: Vec<_> = self.filter.walk(root, dir).flatten().collect();
+        for entry in entries {
+            let path = entry.path();
+            if entry.file_type().is_dir() {
+                match self.watches.add(path, DIR_MASK) {
+                    Ok(wd) => {
+                        let watched = WatchedDir {
+                            root: root.to_path_buf(),
+                            path: path.to_path_buf(),
+                        };
+                        self.paths.insert(wd, watched);
+                    }
+                    Err(e) => warn!(
+                        "Failed to watch {} (fs.inotify.max_user_watches may be too low): {}",
+                        path.display(),
+                        e
+                    ),
+                }
+            } else if entry.file_type().is_file() {
+                files.push(path.to_path_buf());
+            }
+        }
+        files
+    }
+
+    fn translate(
+        &mut self,
+        wd: WatchDescriptor,
+        mask: EventMask,
+        name: Option<&OsStr>,
+    ) -> Vec<WatchEvent> {
+        if mask.contains(EventMask::Q_OVERFLOW) {
+            return vec![WatchEvent::Overflow];
+        }
+        if mask.contains(EventMask::IGNORED) {
+            self.paths.remove(&wd);
+            return Vec::new();
+        }
+        let (Some(dir), Some(name)) = (self.paths.get(&wd), name) else {
+            return Vec::new();
+        };
+        let root = dir.root.clone();
+        let path = dir.path.join(name);
+
+        if mask.contains(EventMask::ISDIR) {
+            let hidden = name.to_str().is_some_and(|n| n.starts_with('.'));
+            if hidden
+                || !mask.intersects(EventMask::CREATE | EventMask::MOVED_TO)
+                || self.filter.is_excluded(&root, &path, true)
+            {
+                return Vec::new();
+            }
+            // Files copied in before the watch was added would otherwise be missed
+            return self
+                .add_tree(&root, &path)
+                .into_iter()
+                .map(WatchEvent::File)
+                .collect();
+        }
+
+        let wanted = mask.intersects(EventMask::CLOSE_WRITE | EventMask::MOVED_TO)
+            && self.filter.is_video_file(&path)
+            && !self.filter.is_excluded(&root, &path, false);
+        if wanted {
+            vec![WatchEvent::File(path)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+fn read_events(
+    mut inotify: Inotify,
+    mut dirs: WatchedDirs,
+    events: mpsc::UnboundedSender<WatchEvent>,
+) {
+    let mut buffer = [0u8; 4096];
+    loop {
+        let batch = match inotify.read_events_blocking(&mut buffer) {
+            Ok(batch) => batch,
+            Err(e) => {
+                warn!("Failed to read inotify events: {}", e);
+                return;
+            }
+        };
+        for event in batch {
+            for watch_event in dirs.translate(event.wd, event.mask, event.name) {
+                if events.send(watch_event).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Holds paths until no event has arrived for them for `window`
+#[derive(Debug)]
+pub struct Debouncer {
+    window: Duration,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Record an event for `path`, restarting its quiet period
+    pub fn touch(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path, now);
+    }
+
+    /// When the next pending path settles
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().min().map(|last| *last + self.window)
+    }
+
+    /// Remove and return the paths that have been quiet for the whole window
+    pub fn take_ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        let mut ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, last)| now.duration_since(**last) >= self.window)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready.sort();
+        ready
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
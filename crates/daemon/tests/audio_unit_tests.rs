@@ -0,0 +1,179 @@
+use av1d_daemon::audio::{audio_codec_flags, plan_audio, transcode_for, AudioTranscode};
+use av1d_daemon::config::{AudioBitrates, AudioCodec, AudioPolicy, DaemonConfig, TrackPolicy};
+use av1d_daemon::container::OutputContainer;
+use av1d_daemon::encode::common::stream_mapping_flags;
+use av1d_daemon::probe::{parse_probe_json, AudioStream, FormatInfo, ProbeResult};
+
+/// (codec, channels, bitrate)
+type Track<'a> = (&'a str, Option<u32>, Option<u64>);
+
+fn probe_with(audio: &[Track]) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(7200.0),
+            size: 40_000_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![],
+        audio_streams: audio
+            .iter()
+            .enumerate()
+            .map(|(i, (codec, channels, bitrate))| AudioStream {
+                index: i + 1,
+                codec_name: codec.to_string(),
+                language: Some("eng".to_string()),
+                title: None,
+                is_default: i == 0,
+                is_commentary: false,
+                channels: *channels,
+                channel_layout: None,
+                bitrate: *bitrate,
+            })
+            .collect(),
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+fn enabled(codec: AudioCodec) -> AudioPolicy {
+    AudioPolicy {
+        enabled: true,
+        codec,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_only_listed_codecs_are_transcoded() {
+    let probe = probe_with(&[
+        ("truehd", Some(8), None),
+        ("ac3", Some(6), Some(640_000)),
+        ("pcm_s24le", Some(2), Some(4_608_000)),
+    ]);
+    let policy = enabled(AudioCodec::Opus);
+    let outputs = plan_audio(&probe, &TrackPolicy::default(), &policy);
+
+    assert_eq!(outputs.len(), 3);
+    assert_eq!(
+        outputs[0].transcode,
+        Some(AudioTranscode {
+            codec: AudioCodec::Opus,
+            bitrate_kbps: 448,
+            downmix: None,
+        })
+    );
+    assert_eq!(outputs[1].transcode, None);
+    assert_eq!(outputs[2].transcode.map(|t| t.bitrate_kbps), Some(128));
+
+    // Disabled by default
+    assert!(
+        plan_audio(&probe, &TrackPolicy::default(), &AudioPolicy::default())
+            .iter()
+            .all(|o| o.transcode.is_none())
+    );
+}
+
+#[test]
+fn test_transcode_bitrate_and_downmix() {
+    let stream = |codec: &str, channels, bitrate| AudioStream {
+        index: 1,
+        codec_name: codec.to_string(),
+        language: None,
+        title: None,
+        is_default: true,
+        is_commentary: false,
+        channels,
+        channel_layout: None,
+        bitrate,
+    };
+
+    // E-AC3 cannot hold 7.1, so it is downmixed to 5.1 at the surround rate
+    let eac3 = AudioPolicy {
+        bitrates: AudioBitrates {
+            surround: 640,
+            ..Default::default()
+        },
+        ..enabled(AudioCodec::Eac3)
+    };
+    assert_eq!(
+        transcode_for(&stream("truehd", Some(8), None), &eac3),
+        Some(AudioTranscode {
+            codec: AudioCodec::Eac3,
+            bitrate_kbps: 640,
+            downmix: Some(6),
+        })
+    );
+
+    // Mono, unknown layouts, and tracks already below the target bitrate
+    let opus = enabled(AudioCodec::Opus);
+    let mono = transcode_for(&stream("pcm_s16le", Some(1), None), &opus);
+    assert_eq!(mono.map(|t| t.bitrate_kbps), Some(64));
+    let unknown = transcode_for(&stream("dts", None, None), &opus);
+    assert_eq!(unknown.map(|t| t.bitrate_kbps), Some(128));
+    assert_eq!(
+        transcode_for(&stream("dts", Some(2), Some(96_000)), &opus),
+        None
+    );
+}
+
+#[test]
+fn test_keep_original_maps_the_source_track_twice() {
+    let probe = probe_with(&[("dts", Some(6), Some(3_000_000)), ("aac", Some(2), None)]);
+    let policy = AudioPolicy {
+        keep_original: true,
+        ..enabled(AudioCodec::Opus)
+    };
+
+    let mapping = stream_mapping_flags(
+        &probe,
+        &TrackPolicy::default(),
+        &policy,
+        OutputContainer::Matroska,
+    )
+    .join(" ");
+    assert!(
+        mapping.starts_with("-map 0:V -map 0:1 -map 0:1 -map 0:2 -map 0:t?"),
+        "{}",
+        mapping
+    );
+
+    let outputs = plan_audio(&probe, &TrackPolicy::default(), &policy);
+    assert_eq!(
+        audio_codec_flags(&outputs).join(" "),
+        "-c:a:0 libopus -b:a:0 320k -mapping_family:a:0 1 \
+         -c:a:1 copy -disposition:a:1 0 \
+         -c:a:2 copy"
+    );
+}
+
+#[test]
+fn test_probed_channels_and_bitrate_drive_the_plan() {
+    let probe = parse_probe_json(
+        r#"{
+        "format": { "duration": "7200.0", "size": "40000000000" },
+        "streams": [
+            { "index": 0, "codec_type": "video", "codec_name": "hevc", "width": 1920, "height": 1080 },
+            { "index": 1, "codec_type": "audio", "codec_name": "truehd", "channels": 8,
+              "channel_layout": "7.1", "disposition": { "default": 1 } },
+            { "index": 2, "codec_type": "audio", "codec_name": "ac3", "channels": 6,
+              "channel_layout": "5.1(side)", "bit_rate": "640000" }
+        ]
+    }"#,
+    )
+    .unwrap();
+    assert_eq!(probe.audio_streams[0].channels, Some(8));
+    assert_eq!(
+        probe.audio_streams[0].channel_layout.as_deref(),
+        Some("7.1")
+    );
+    assert_eq!(probe.audio_streams[1].bitrate, Some(640_000));
+
+    let config = DaemonConfig {
+        audio_policy: enabled(AudioCodec::Eac3),
+        ..Default::default()
+    };
+    let outputs = plan_audio(&probe, &config.track_policy, &config.audio_policy);
+    let flags = audio_codec_flags(&outputs).join(" ");
+    assert_eq!(flags, "-c:a:0 eac3 -b:a:0 320k -ac:a:0 6 -c:a:1 copy");
+}
@@ -50,9 +50,14 @@ fn property_source_classification_scoring() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: true,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             }],
             audio_streams: vec![],
             subtitle_streams: vec![],
+            chapters: 0,
+            attachments: vec![],
         };
 
         // Classify
@@ -191,10 +196,8 @@ fn calculate_expected_web_score(
     }
 
     // Bitrate scoring (only for 1080p and 2160p)
-    if *bitrate_type == BitrateType::Low {
-        if height >= 1080 {
-            score += 5;
-        }
+    if *bitrate_type == BitrateType::Low && height >= 1080 {
+        score += 5;
     }
 
     // Codec scoring
@@ -219,10 +222,8 @@ fn calculate_expected_disc_score(
     }
 
     // Bitrate scoring (only for 1080p and 2160p)
-    if *bitrate_type == BitrateType::High {
-        if height >= 1080 {
-            score += 5;
-        }
+    if *bitrate_type == BitrateType::High && height >= 1080 {
+        score += 5;
     }
 
     // File size scoring
@@ -313,9 +314,14 @@ fn test_vp9_codec_scoring() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let path = PathBuf::from("/media/video/movie.mkv");
@@ -347,9 +353,14 @@ fn test_large_file_size_scoring() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let path = PathBuf::from("/media/video/movie.mkv");
@@ -395,9 +406,14 @@ fn test_combined_disclike_scoring() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(10),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let path = PathBuf::from("/media/movies/Movie.BluRay.Remux.mkv");
@@ -420,6 +436,8 @@ fn test_no_video_streams() {
         video_streams: vec![],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let path = PathBuf::from("/media/shows/Show.WEBRip.mkv");
@@ -497,8 +515,13 @@ fn create_minimal_probe(
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     }
 }
@@ -0,0 +1,348 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::commands::{
+    process_pending_commands, CommandAck, CommandAction, JobInterrupt, RunningJobs, ACK_DIR_NAME,
+};
+use av1d_daemon::config::DaemonConfig;
+use av1d_daemon::jobs::{create_job, load_all_jobs, save_job, Job, JobStatus};
+use av1d_daemon::probe::{FormatInfo, ProbeResult, VideoStream};
+use av1d_daemon::retry::{load_ledger, update_ledger, FailureKind};
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::sidecars::{create_skip_marker, has_skip_marker};
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn test_config(temp_dir: &TempDir) -> DaemonConfig {
+    DaemonConfig {
+        library_roots: vec![temp_dir.path().join("media")],
+        job_state_dir: temp_dir.path().join("jobs"),
+        temp_output_dir: temp_dir.path().join("temp"),
+        ..Default::default()
+    }
+}
+
+fn create_test_job(source_path: &Path, status: JobStatus) -> Job {
+    let candidate = CandidateFile {
+        path: source_path.to_path_buf(),
+        size_bytes: 5_000_000_000,
+        modified_time: std::time::SystemTime::now(),
+    };
+
+    let probe = ProbeResult {
+        format: FormatInfo {
+            duration: Some(7200.0),
+            size: 5_000_000_000,
+            bitrate: Some(8_000_000),
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            bitrate: Some(8_000_000),
+            frame_rate: Some("24/1".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+            bit_depth: Some(8),
+            is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    };
+
+    let classification = SourceClassification {
+        source_type: SourceType::Unknown,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+
+    let mut job = create_job(candidate, probe, classification);
+    job.status = status;
+    job
+}
+
+fn write_command(config: &DaemonConfig, name: &str, body: serde_json::Value) {
+    let command_dir = config.command_dir();
+    fs::create_dir_all(&command_dir).unwrap();
+    fs::write(command_dir.join(name), body.to_string()).unwrap();
+}
+
+fn reload_job(config: &DaemonConfig, id: &str) -> Job {
+    load_all_jobs(&config.job_state_dir)
+        .unwrap()
+        .into_iter()
+        .find(|j| j.id == id)
+        .unwrap()
+}
+
+fn read_ack(config: &DaemonConfig, name: &str) -> CommandAck {
+    let ack_path = config.command_dir().join(ACK_DIR_NAME).join(name);
+    serde_json::from_str(&fs::read_to_string(ack_path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_tui_requeue_file_requeues_stale_running_job() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let mut job = create_test_job(&temp_dir.path().join("movie.mkv"), JobStatus::Running);
+    job.progress = Some(42.0);
+    save_job(&job, &config.job_state_dir).unwrap();
+
+    // Same shape av1top writes (no version field)
+    let name = format!("requeue-{}.json", job.id);
+    write_command(
+        &config,
+        &name,
+        serde_json::json!({
+            "action": "requeue",
+            "job_id": job.id,
+            "reason": "manual_requeue_from_tui",
+            "timestamp": "2025-01-01T00:00:00Z",
+        }),
+    );
+
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert_eq!(acks.len(), 1);
+    assert!(acks[0].success, "ack: {:?}", acks[0]);
+    assert_eq!(acks[0].action, Some(CommandAction::Requeue));
+
+    let reloaded = reload_job(&config, &job.id);
+    assert_eq!(reloaded.status, JobStatus::Pending);
+    assert_eq!(reloaded.progress, None);
+
+    // Command file consumed, ack written back
+    assert!(!config.command_dir().join(&name).exists());
+    assert_eq!(read_ack(&config, &name), acks[0]);
+}
+
+#[test]
+fn test_requeue_running_job_interrupts_encode() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let job = create_test_job(&temp_dir.path().join("movie.mkv"), JobStatus::Running);
+    save_job(&job, &config.job_state_dir).unwrap();
+
+    let running = RunningJobs::new();
+    let interrupt = running.register(&job.id);
+
+    write_command(
+        &config,
+        "requeue.json",
+        serde_json::json!({ "action": "requeue", "job_id": job.id }),
+    );
+    let acks = process_pending_commands(&config, &running).unwrap();
+    assert!(acks[0].success);
+
+    // The running encode is told to stop; the job file is left to the encode task
+    assert_eq!(*interrupt.borrow(), Some(JobInterrupt::Requeue));
+    assert_eq!(reload_job(&config, &job.id).status, JobStatus::Running);
+}
+
+#[test]
+fn test_scoped_registration_reaches_the_job_until_dropped() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let job = create_test_job(&temp_dir.path().join("movie.mkv"), JobStatus::Running);
+    save_job(&job, &config.job_state_dir).unwrap();
+
+    let running = RunningJobs::new();
+    let (registration, interrupt) = running.register_scoped(&job.id);
+    write_command(
+        &config,
+        "a-skip.json",
+        serde_json::json!({ "action": "skip", "job_id": job.id }),
+    );
+    let acks = process_pending_commands(&config, &running).unwrap();
+    assert!(acks[0].success);
+    assert_eq!(*interrupt.borrow(), Some(JobInterrupt::Skip));
+    assert_eq!(reload_job(&config, &job.id).status, JobStatus::Running);
+
+    // Once the pipeline is gone, commands change the job file themselves
+    drop(registration);
+    assert!(!running.is_running(&job.id));
+    write_command(
+        &config,
+        "b-cancel.json",
+        serde_json::json!({ "action": "cancel", "job_id": job.id }),
+    );
+    let acks = process_pending_commands(&config, &running).unwrap();
+    assert!(acks[0].success);
+    assert_eq!(reload_job(&config, &job.id).status, JobStatus::Failed);
+}
+
+#[test]
+fn test_retry_failed_job_clears_skip_marker() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let source = temp_dir.path().join("movie.mkv");
+    fs::write(&source, "dummy").unwrap();
+    create_skip_marker(&source).unwrap();
+
+    let mut job = create_test_job(&source, JobStatus::Failed);
+    job.reason = Some("Encoding failed".to_string());
+    save_job(&job, &config.job_state_dir).unwrap();
+    update_ledger(&config, |ledger| {
+        ledger.record_failure(&source, FailureKind::Permanent, "bad", Utc::now(), &config)
+    })
+    .unwrap();
+
+    write_command(
+        &config,
+        "retry.json",
+        serde_json::json!({ "version": 1, "action": "retry", "job_id": job.id }),
+    );
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert!(acks[0].success, "ack: {:?}", acks[0]);
+
+    let reloaded = reload_job(&config, &job.id);
+    assert_eq!(reloaded.status, JobStatus::Pending);
+    assert_eq!(reloaded.reason, None);
+    assert!(!has_skip_marker(&source));
+    let ledger = load_ledger(&config.retry_ledger_path()).unwrap();
+    assert!(ledger.entry(&source).is_none());
+}
+
+#[test]
+fn test_retry_rejects_successful_job() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let job = create_test_job(&temp_dir.path().join("movie.mkv"), JobStatus::Success);
+    save_job(&job, &config.job_state_dir).unwrap();
+
+    write_command(
+        &config,
+        "retry.json",
+        serde_json::json!({ "action": "retry", "job_id": job.id }),
+    );
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert!(!acks[0].success);
+    assert_eq!(reload_job(&config, &job.id).status, JobStatus::Success);
+}
+
+#[test]
+fn test_skip_and_unskip_by_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let source = temp_dir.path().join("home_video.mp4");
+    fs::write(&source, "dummy").unwrap();
+
+    write_command(
+        &config,
+        "a-skip.json",
+        serde_json::json!({ "action": "skip", "path": source, "reason": "home video" }),
+    );
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert!(acks[0].success);
+    assert!(has_skip_marker(&source));
+    let why = fs::read_to_string(source.with_extension("mp4.why.txt")).unwrap();
+    assert_eq!(why, "Skipped by user: home video");
+
+    write_command(
+        &config,
+        "b-unskip.json",
+        serde_json::json!({ "action": "unskip", "path": source }),
+    );
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert!(acks[0].success);
+    assert!(!has_skip_marker(&source));
+}
+
+#[test]
+fn test_cancel_only_stops_the_current_attempt() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let source = temp_dir.path().join("movie.mkv");
+    fs::write(&source, "dummy").unwrap();
+    let job = create_test_job(&source, JobStatus::Pending);
+    save_job(&job, &config.job_state_dir).unwrap();
+
+    write_command(
+        &config,
+        "cancel.json",
+        serde_json::json!({ "action": "cancel", "job_id": job.id }),
+    );
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert!(acks[0].success);
+
+    let reloaded = reload_job(&config, &job.id);
+    assert_eq!(reloaded.status, JobStatus::Failed);
+    assert_eq!(reloaded.reason.as_deref(), Some("Cancelled by user"));
+    assert!(reloaded.finished_at.is_some());
+    // Neither a skip marker nor a backoff holds the file back, so the next
+    // scan queues it again
+    assert!(!has_skip_marker(&source));
+    let ledger = load_ledger(&config.retry_ledger_path()).unwrap();
+    assert!(ledger.entry(&source).is_none());
+    assert_eq!(ledger.backoff_until(&source, Utc::now()), None);
+}
+
+#[test]
+fn test_bump_priority_accumulates() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let job = create_test_job(&temp_dir.path().join("movie.mkv"), JobStatus::Pending);
+    save_job(&job, &config.job_state_dir).unwrap();
+
+    write_command(
+        &config,
+        "a.json",
+        serde_json::json!({ "action": "bump_priority", "job_id": job.id }),
+    );
+    write_command(
+        &config,
+        "b.json",
+        serde_json::json!({ "action": "bump_priority", "job_id": job.id, "priority": 5 }),
+    );
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert!(acks.iter().all(|a| a.success));
+
+    assert_eq!(reload_job(&config, &job.id).priority, Some(6));
+}
+
+#[test]
+fn test_invalid_commands_are_acknowledged_as_failures() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let command_dir = config.command_dir();
+    fs::create_dir_all(&command_dir).unwrap();
+
+    fs::write(command_dir.join("a-garbage.json"), "not json").unwrap();
+    write_command(
+        &config,
+        "b-future.json",
+        serde_json::json!({ "version": 99, "action": "requeue", "job_id": "x" }),
+    );
+    write_command(
+        &config,
+        "c-missing.json",
+        serde_json::json!({ "action": "requeue", "job_id": "does-not-exist" }),
+    );
+    // Temp files written by the TUI are ignored until renamed
+    fs::write(command_dir.join(".requeue-x.json.tmp"), "{}").unwrap();
+
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert_eq!(acks.len(), 3);
+    assert!(acks.iter().all(|a| !a.success));
+    assert_eq!(acks[0].action, None);
+    assert!(acks[1].message.contains("Unsupported command version"));
+    assert!(acks[2].message.contains("not found"));
+    assert!(command_dir.join(".requeue-x.json.tmp").exists());
+}
+
+#[test]
+fn test_missing_command_dir_is_not_an_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DaemonConfig {
+        command_dir: Some(PathBuf::from("/nonexistent/av1d/commands")),
+        ..test_config(&temp_dir)
+    };
+
+    let acks = process_pending_commands(&config, &RunningJobs::new()).unwrap();
+    assert!(acks.is_empty());
+}
@@ -1,7 +1,16 @@
-use av1d_daemon::encode::JobExecutor;
-use std::path::PathBuf;
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::daemon_loop::run_encode_dispatcher;
+use av1d_daemon::encode::{execute_encode, JobExecutor};
+use av1d_daemon::jobs::{create_job, load_all_jobs, save_job, Job, JobStage};
+use av1d_daemon::probe::{FormatInfo, ProbeResult};
+use av1d_daemon::scan::CandidateFile;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 
 /// **Feature: av1-reencoder, Property 20: Concurrent job limiting**
@@ -77,3 +86,170 @@ async fn test_concurrent_job_limiting() {
         );
     }
 }
+
+fn test_job(source_path: PathBuf) -> Job {
+    let candidate = CandidateFile {
+        path: source_path,
+        size_bytes: 10_000_000,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let probe = ProbeResult {
+        format: FormatInfo {
+            duration: Some(10.0),
+            size: 10_000_000,
+            bitrate: Some(8_000_000),
+        },
+        video_streams: vec![],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::Unknown,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    create_job(candidate, probe, classification)
+}
+
+/// Stand-in for ffmpeg: records how many copies are running, writes the output
+/// file (last argument) and reports progress like `-progress pipe:1`.
+fn write_fake_ffmpeg(dir: &Path) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    let running_dir = dir.join("running");
+    let log = dir.join("concurrency.log");
+    fs::create_dir_all(&running_dir).unwrap();
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\n\
+             for out; do :; done\n\
+             touch {running}/$$\n\
+             sleep 0.2\n\
+             ls {running} | wc -l >> {log}\n\
+             sleep 0.2\n\
+             rm {running}/$$\n\
+             echo encoded > \"$out\"\n\
+             echo out_time_ms=10000000\n\
+             echo total_size=8\n\
+             echo progress=end\n",
+            running = running_dir.display(),
+            log = log.display(),
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+/// **Feature: av1-reencoder, Property 20: Concurrent job limiting**
+/// **Validates: Requirements 19.2**
+///
+/// Queued items handed to the encode dispatcher never run more than
+/// max_concurrent_jobs at once, and all of them run.
+#[tokio::test]
+async fn test_dispatcher_limits_concurrent_jobs() {
+    for max_concurrent in 1..=4 {
+        let executor = Arc::new(JobExecutor::new(max_concurrent));
+        let concurrent_count = Arc::new(AtomicUsize::new(0));
+        let max_observed = Arc::new(AtomicUsize::new(0));
+        let completed = Arc::new(AtomicUsize::new(0));
+
+        let (tx, rx) = mpsc::channel(max_concurrent);
+        let num_jobs = max_concurrent * 3;
+        let producer = tokio::spawn(async move {
+            for job_id in 0..num_jobs {
+                tx.send(job_id).await.unwrap();
+            }
+        });
+
+        let (count, max, done) = (
+            concurrent_count.clone(),
+            max_observed.clone(),
+            completed.clone(),
+        );
+        run_encode_dispatcher(rx, executor, move |_job_id: usize| {
+            let (count, max, done) = (count.clone(), max.clone(), done.clone());
+            async move {
+                let current = count.fetch_add(1, Ordering::SeqCst) + 1;
+                max.fetch_max(current, Ordering::SeqCst);
+                sleep(Duration::from_millis(30)).await;
+                count.fetch_sub(1, Ordering::SeqCst);
+                done.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .await;
+        producer.await.unwrap();
+
+        assert_eq!(completed.load(Ordering::SeqCst), num_jobs);
+        assert_eq!(max_observed.load(Ordering::SeqCst), max_concurrent);
+    }
+}
+
+/// **Feature: av1-reencoder, Property 20: Concurrent job limiting**
+/// **Validates: Requirements 19.2**
+///
+/// Real encode processes started through the dispatcher are limited to
+/// max_concurrent_jobs, and each job finishes with its own output and state.
+#[tokio::test]
+async fn test_dispatcher_limits_concurrent_encode_processes() {
+    for max_concurrent in 1..=3 {
+        let temp_dir = TempDir::new().unwrap();
+        let ffmpeg = write_fake_ffmpeg(temp_dir.path());
+        let state_dir = temp_dir.path().join("jobs");
+        let num_jobs = max_concurrent * 3;
+
+        let jobs: Vec<Job> = (0..num_jobs)
+            .map(|i| test_job(temp_dir.path().join(format!("movie-{}.mkv", i))))
+            .collect();
+
+        let (tx, rx) = mpsc::channel(max_concurrent);
+        let producer = tokio::spawn(async move {
+            for job in jobs {
+                tx.send(job).await.unwrap();
+            }
+        });
+
+        let executor = Arc::new(JobExecutor::new(max_concurrent));
+        let (ffmpeg, dir, jobs_dir) = (ffmpeg.clone(), temp_dir.path().to_path_buf(), state_dir);
+        run_encode_dispatcher(rx, executor, move |mut job: Job| {
+            let output = dir.join(format!("{}.mkv", job.id));
+            let command = vec![
+                ffmpeg.to_string_lossy().to_string(),
+                "-i".to_string(),
+                job.source_path.to_string_lossy().to_string(),
+                output.to_string_lossy().to_string(),
+            ];
+            let jobs_dir = jobs_dir.clone();
+            async move {
+                let result = execute_encode(&mut job, command, &jobs_dir).await;
+                assert_eq!(result.unwrap(), output);
+                save_job(&job, &jobs_dir).unwrap();
+            }
+        })
+        .await;
+        producer.await.unwrap();
+
+        let log = fs::read_to_string(temp_dir.path().join("concurrency.log")).unwrap();
+        let counts: Vec<usize> = log.lines().map(|l| l.trim().parse().unwrap()).collect();
+        assert_eq!(counts.len(), num_jobs);
+        let max_observed = counts.into_iter().max().unwrap();
+        assert!(
+            max_observed <= max_concurrent,
+            "Concurrent encode limit violated: max_concurrent={}, observed={}",
+            max_concurrent,
+            max_observed
+        );
+        assert_eq!(max_observed, max_concurrent);
+
+        let finished = load_all_jobs(&temp_dir.path().join("jobs")).unwrap();
+        assert_eq!(finished.len(), num_jobs);
+        for job in finished {
+            assert_eq!(job.stage, Some(JobStage::Verifying));
+            assert_eq!(job.progress, Some(100.0));
+            assert!(temp_dir.path().join(format!("{}.mkv", job.id)).exists());
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use av1d_daemon::config::OutputContainerPolicy;
+use av1d_daemon::container::{plan_output_container, OutputContainer};
+use av1d_daemon::probe::{AudioStream, FormatInfo, ProbeResult, SubtitleStream};
+use std::path::Path;
+
+fn probe_with(audio: &[&str], subtitles: &[&str]) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(60.0),
+            size: 1_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![],
+        audio_streams: audio
+            .iter()
+            .enumerate()
+            .map(|(i, codec)| AudioStream {
+                index: i + 1,
+                codec_name: codec.to_string(),
+                language: None,
+                title: None,
+                is_default: false,
+                is_commentary: false,
+                channels: None,
+                channel_layout: None,
+                bitrate: None,
+            })
+            .collect(),
+        subtitle_streams: subtitles
+            .iter()
+            .enumerate()
+            .map(|(i, codec)| SubtitleStream {
+                index: audio.len() + i + 1,
+                codec_name: codec.to_string(),
+                language: None,
+                title: None,
+                is_default: false,
+                is_commentary: false,
+            })
+            .collect(),
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+#[test]
+fn test_mkv_source_stays_mkv() {
+    let source = Path::new("/media/movie.mkv");
+    let plan = plan_output_container(
+        source,
+        &probe_with(&["truehd"], &["hdmv_pgs_subtitle"]),
+        OutputContainerPolicy::KeepSource,
+    );
+
+    assert_eq!(plan.container, OutputContainer::Matroska);
+    assert_eq!(plan.final_path, source);
+    assert!(!plan.is_renamed(source));
+}
+
+#[test]
+fn test_compatible_mp4_keeps_container() {
+    let source = Path::new("/media/show.mp4");
+    let plan = plan_output_container(
+        source,
+        &probe_with(&["aac", "eac3"], &["mov_text"]),
+        OutputContainerPolicy::KeepSource,
+    );
+
+    assert_eq!(plan.container, OutputContainer::Mp4);
+    assert_eq!(plan.final_path, source);
+    assert_eq!(plan.switch_reason, None);
+}
+
+#[test]
+fn test_mp4_with_incompatible_streams_switches_to_mkv() {
+    let source = Path::new("/media/show.M4V");
+    let plan = plan_output_container(
+        source,
+        &probe_with(&["aac", "truehd"], &["subrip"]),
+        OutputContainerPolicy::KeepSource,
+    );
+
+    assert_eq!(plan.container, OutputContainer::Matroska);
+    assert_eq!(plan.final_path, Path::new("/media/show.mkv"));
+    let reason = plan.switch_reason.unwrap();
+    assert!(reason.contains("audio truehd"), "{}", reason);
+    assert!(reason.contains("subtitle subrip"), "{}", reason);
+}
+
+#[test]
+fn test_webm_keeps_container_only_with_webm_codecs() {
+    let source = Path::new("/media/clip.webm");
+    let keep = plan_output_container(
+        source,
+        &probe_with(&["opus"], &[]),
+        OutputContainerPolicy::KeepSource,
+    );
+    assert_eq!(keep.container, OutputContainer::WebM);
+
+    let switch = plan_output_container(
+        source,
+        &probe_with(&["aac"], &[]),
+        OutputContainerPolicy::KeepSource,
+    );
+    assert_eq!(switch.container, OutputContainer::Matroska);
+}
+
+#[test]
+fn test_containers_without_av1_support_are_renamed() {
+    for ext in ["avi", "m2ts", "ts", "wmv", "mov"] {
+        let source_name = format!("/media/movie.{}", ext);
+        let source = Path::new(&source_name);
+        let plan = plan_output_container(
+            source,
+            &probe_with(&["ac3"], &[]),
+            OutputContainerPolicy::KeepSource,
+        );
+        assert_eq!(plan.container, OutputContainer::Matroska);
+        assert_eq!(plan.final_path, Path::new("/media/movie.mkv"));
+        assert!(plan.is_renamed(source));
+    }
+}
+
+#[test]
+fn test_mkv_policy_always_switches() {
+    let source = Path::new("/media/show.mp4");
+    let plan = plan_output_container(
+        source,
+        &probe_with(&["aac"], &[]),
+        OutputContainerPolicy::Mkv,
+    );
+
+    assert_eq!(plan.container, OutputContainer::Matroska);
+    assert_eq!(plan.final_path, Path::new("/media/show.mkv"));
+}
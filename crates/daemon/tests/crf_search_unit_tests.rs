@@ -0,0 +1,200 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::config::{CrfSearchConfig, DaemonConfig, QualityMetric, QualityTier};
+use av1d_daemon::crf_search::{binary_search_crf, search_crf, CrfAttempt};
+use av1d_daemon::encode::VideoSettings;
+use av1d_daemon::jobs::{create_job, Job};
+use av1d_daemon::probe::{FormatInfo, ProbeResult, VideoStream};
+use av1d_daemon::profiles::EncodingProfile;
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::startup::AvailableEncoder;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn svt_settings() -> VideoSettings {
+    let config = DaemonConfig {
+        quality_tier: QualityTier::High,
+        ..Default::default()
+    };
+    VideoSettings::new(
+        &config,
+        AvailableEncoder::SvtAv1,
+        &EncodingProfile::default(),
+        1080,
+    )
+}
+
+fn probe() -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(3600.0),
+            size: 8_000_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            bitrate: None,
+            frame_rate: Some("24/1".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+            bit_depth: Some(8),
+            is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+fn job(source: PathBuf) -> Job {
+    let candidate = CandidateFile {
+        path: source,
+        size_bytes: 8_000_000_000,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::DiscLike,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    let mut job = create_job(candidate, probe(), classification);
+    job.video_width = Some(1920);
+    job.video_height = Some(1080);
+    job
+}
+
+/// Stand-in for ffmpeg. Sample encodes write their output file; comparisons
+/// report VMAF 120 - 2 * CRF, read from the sample file name.
+fn write_fake_ffmpeg(dir: &Path) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    fs::write(
+        &script,
+        format!(
+            r#"#!/bin/sh
+if [ "$2" = "-filters" ]; then
+    echo ' ... libvmaf          VV->V      Calculate the VMAF between two video streams.'
+    exit 0
+fi
+echo "$@" >> "{log}"
+for out; do :; done
+case "$*" in
+    *-lavfi*)
+        crf=$(echo "$*" | sed 's/.*\.crf\([0-9]*\)\..*/\1/')
+        echo "[Parsed_ssim_4 @ 0x1] SSIM Y:0.99 (20.0) All:0.99 (20.0)" >&2
+        echo "[Parsed_psnr_5 @ 0x2] PSNR y:43.0 average:43.0 min:40.0 max:50.0" >&2
+        echo "[Parsed_libvmaf_6 @ 0x3] VMAF score: $((120 - 2 * crf)).0" >&2
+        ;;
+    *)
+        echo sample > "$out"
+        ;;
+esac
+"#,
+            log = dir.join("calls").display(),
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+#[tokio::test]
+async fn test_binary_search_finds_highest_passing_crf() {
+    let (best, attempts) =
+        binary_search_crf(10, 40, 75.0, |crf| async move { Ok(100.0 - crf as f64) })
+            .await
+            .unwrap();
+
+    assert_eq!(best, Some(25));
+    let tried: Vec<u8> = attempts.iter().map(|a| a.crf).collect();
+    assert_eq!(tried, vec![25, 33, 29, 27, 26]);
+    assert_eq!(
+        attempts[0],
+        CrfAttempt {
+            crf: 25,
+            score: 75.0
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_binary_search_reports_when_nothing_passes() {
+    let (best, attempts) = binary_search_crf(20, 24, 99.0, |_| async { Ok(90.0) })
+        .await
+        .unwrap();
+
+    assert_eq!(best, None);
+    let tried: Vec<u8> = attempts.iter().map(|a| a.crf).collect();
+    assert_eq!(tried, vec![22, 20]);
+}
+
+#[tokio::test]
+async fn test_search_crf_encodes_and_scores_samples() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path());
+    let work_dir = temp_dir.path().join("work");
+    fs::create_dir_all(&work_dir).unwrap();
+    let job = job(PathBuf::from("/media/movie.mkv"));
+    let config = CrfSearchConfig {
+        enabled: true,
+        metric: QualityMetric::Vmaf,
+        target: 95.0,
+        min_crf: 10,
+        max_crf: 40,
+        samples: 2,
+        sample_secs: 5,
+    };
+
+    let trace = search_crf(
+        ffmpeg.to_str().unwrap(),
+        &job,
+        &probe(),
+        &svt_settings(),
+        &config,
+        &work_dir,
+    )
+    .await
+    .unwrap();
+
+    // 120 - 2 * CRF >= 95 holds up to CRF 12
+    assert_eq!(trace.chosen_crf, 12);
+    assert!(trace.met_target);
+    assert_eq!(trace.attempts[0].crf, 25);
+    assert_eq!(trace.attempts[0].score, 70.0);
+
+    let calls = fs::read_to_string(temp_dir.path().join("calls")).unwrap();
+    let encodes: Vec<&str> = calls.lines().filter(|l| !l.contains("-lavfi")).collect();
+    assert_eq!(encodes.len(), trace.attempts.len() * 2);
+    assert!(encodes[0].contains("-ss 897.500 -t 5.000 -i /media/movie.mkv -map 0:0 -an"));
+    assert!(encodes[0].contains("-c:v libsvtav1 -crf 25"));
+
+    // Sample encodes are cleaned up
+    assert_eq!(fs::read_dir(&work_dir).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_search_crf_by_vmaf_requires_libvmaf() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = temp_dir.path().join("fake-ffmpeg");
+    fs::write(&script, "#!/bin/sh\necho ' ... ssim'\n").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let result = search_crf(
+        script.to_str().unwrap(),
+        &job(PathBuf::from("/media/movie.mkv")),
+        &probe(),
+        &svt_settings(),
+        &CrfSearchConfig::default(),
+        temp_dir.path(),
+    )
+    .await;
+
+    assert!(result.unwrap_err().to_string().contains("libvmaf"));
+}
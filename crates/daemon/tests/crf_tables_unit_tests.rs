@@ -0,0 +1,206 @@
+use av1d_daemon::config::{validate_config, BitrateAdjustment, DaemonConfig, QualityTier};
+use av1d_daemon::encode::{select_crf, select_preset, video_encoder_args, VideoSettings};
+use av1d_daemon::probe::{FormatInfo, ProbeResult};
+use av1d_daemon::profiles::EncodingProfile;
+use av1d_daemon::startup::AvailableEncoder;
+
+fn parse(toml: &str) -> DaemonConfig {
+    let config: DaemonConfig = toml::from_str(toml).unwrap();
+    validate_config(&config).unwrap();
+    config
+}
+
+fn empty_probe() -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(60.0),
+            size: 1_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+#[test]
+fn test_tables_are_per_encoder_and_tier() {
+    let config = parse(
+        r#"
+        quality_tier = "high"
+
+        [crf_tables.aom]
+        high = [{ min_height = 2160, value = 24 }, { min_height = 0, value = 28 }]
+        very_high = [{ min_height = 0, value = 22 }]
+
+        [preset_tables.svt]
+        high = [{ min_height = 0, value = 6 }]
+        very_high = [{ min_height = 0, value = 4 }]
+        "#,
+    );
+
+    assert_eq!(
+        select_crf(&config, AvailableEncoder::LibaomAv1, 2160, None),
+        24
+    );
+    assert_eq!(
+        select_crf(&config, AvailableEncoder::LibaomAv1, 1080, None),
+        28
+    );
+    // Encoders without their own entry keep the built-in tables
+    assert_eq!(
+        select_crf(&config, AvailableEncoder::SvtAv1, 1080, None),
+        20
+    );
+    assert_eq!(
+        select_preset(&config, AvailableEncoder::SvtAv1, 1080),
+        Some(6)
+    );
+    assert_eq!(
+        select_preset(&config, AvailableEncoder::LibaomAv1, 1080),
+        Some(4)
+    );
+
+    let config = DaemonConfig {
+        quality_tier: QualityTier::VeryHigh,
+        ..config
+    };
+    assert_eq!(
+        select_crf(&config, AvailableEncoder::LibaomAv1, 2160, None),
+        22
+    );
+    assert_eq!(
+        select_preset(&config, AvailableEncoder::SvtAv1, 2160),
+        Some(4)
+    );
+}
+
+/// Height rules the tables replaced: SVT-AV1 CRF and preset (two steps lower
+/// for `very_high`) and libaom cpu-used
+fn previous_rules(height: i32, tier: QualityTier) -> (u8, u8, u8) {
+    let (crf, preset) = match height {
+        h if h >= 2160 => (18, 1),
+        h if h >= 1440 => (19, 2),
+        h if h >= 1080 => (20, 2),
+        _ => (21, 3),
+    };
+    let cpu_used = if height > 1080 { 3 } else { 4 };
+    match tier {
+        QualityTier::High => (crf, preset, cpu_used),
+        QualityTier::VeryHigh => (crf - 2, preset.saturating_sub(2), cpu_used),
+    }
+}
+
+#[test]
+fn test_default_tables_match_the_previous_rules() {
+    for quality_tier in [QualityTier::High, QualityTier::VeryHigh] {
+        let config = DaemonConfig {
+            quality_tier,
+            ..Default::default()
+        };
+        for height in [
+            480, 719, 720, 1079, 1080, 1081, 1200, 1439, 1440, 2159, 2160, 4320,
+        ] {
+            let (crf, preset, cpu_used) = previous_rules(height, quality_tier);
+            let tables = (
+                select_crf(&config, AvailableEncoder::SvtAv1, height, None),
+                select_preset(&config, AvailableEncoder::SvtAv1, height),
+                select_preset(&config, AvailableEncoder::LibaomAv1, height),
+            );
+            assert_eq!(
+                tables,
+                (crf, Some(preset), Some(cpu_used)),
+                "{:?} at {}p",
+                quality_tier,
+                height
+            );
+        }
+    }
+}
+
+#[test]
+fn test_bitrate_adjustment_scales_with_resolution() {
+    let adjustment = BitrateAdjustment {
+        enabled: true,
+        low_bitrate: 4_000_000,
+        low_bitrate_crf_offset: 2,
+        high_bitrate: 30_000_000,
+        high_bitrate_crf_offset: 1,
+    };
+
+    assert_eq!(adjustment.adjust(20, 1080, Some(3_000_000)), 22);
+    assert_eq!(adjustment.adjust(20, 1080, Some(10_000_000)), 20);
+    assert_eq!(adjustment.adjust(20, 1080, Some(40_000_000)), 19);
+    // 4K has four times the pixels, so 10 Mb/s is starved there
+    assert_eq!(adjustment.adjust(20, 2160, Some(10_000_000)), 22);
+    assert_eq!(adjustment.adjust(20, 2160, Some(100_000_000)), 20);
+    // Unknown bitrate or disabled adjustment leave the CRF alone
+    assert_eq!(adjustment.adjust(20, 1080, None), 20);
+    let disabled = BitrateAdjustment {
+        enabled: false,
+        ..adjustment
+    };
+    assert_eq!(disabled.adjust(20, 1080, Some(3_000_000)), 20);
+
+    let config = DaemonConfig {
+        quality_tier: QualityTier::High,
+        bitrate_adjustment: BitrateAdjustment {
+            enabled: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(
+        select_crf(&config, AvailableEncoder::SvtAv1, 1080, Some(2_000_000)),
+        22
+    );
+}
+
+#[test]
+fn test_empty_preset_table_leaves_the_encoder_default() {
+    let config = DaemonConfig::default();
+    let video = VideoSettings::new(
+        &config,
+        AvailableEncoder::Librav1e,
+        &EncodingProfile::default(),
+        1080,
+    );
+    assert_eq!(video.preset, None);
+    let args = video_encoder_args(&video, &empty_probe(), 1080, 80).join(" ");
+    assert!(!args.contains("-speed"), "{}", args);
+
+    let config = parse(
+        r#"
+        [preset_tables.rav1e]
+        high = [{ min_height = 0, value = 6 }]
+        very_high = [{ min_height = 0, value = 4 }]
+        "#,
+    );
+    let video = VideoSettings::new(
+        &config,
+        AvailableEncoder::Librav1e,
+        &EncodingProfile::default(),
+        1080,
+    );
+    let args = video_encoder_args(&video, &empty_probe(), 1080, 80).join(" ");
+    assert!(args.contains("-speed 4"), "{}", args);
+}
+
+#[test]
+fn test_invalid_tables_fail_validation() {
+    let invalid = [
+        // Missing CRF values for a tier
+        "[crf_tables.svt]\nhigh = []\nvery_high = [{ min_height = 0, value = 20 }]",
+        // Not ordered by descending height
+        "[crf_tables.svt]\nhigh = [{ min_height = 0, value = 20 }, { min_height = 1080, value = 18 }]\nvery_high = [{ min_height = 0, value = 18 }]",
+        // libaom cpu-used only goes to 8
+        "[preset_tables.aom]\nhigh = [{ min_height = 0, value = 9 }]\nvery_high = []",
+        "[bitrate_adjustment]\nlow_bitrate = 50000000\nhigh_bitrate = 10000000",
+    ];
+    for toml in invalid {
+        let config: DaemonConfig = toml::from_str(toml).unwrap();
+        assert!(validate_config(&config).is_err(), "{}", toml);
+    }
+}
@@ -0,0 +1,276 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::config::{CropDetectionConfig, DaemonConfig};
+use av1d_daemon::crop::{agree_on_crop, detect_crop, parse_cropdetect, CropDetection, CropRect};
+use av1d_daemon::encode::common::video_filter_flags;
+use av1d_daemon::encode::{build_command, VideoSettings};
+use av1d_daemon::jobs::{create_job, Job};
+use av1d_daemon::probe::{FormatInfo, ProbeResult, VideoStream};
+use av1d_daemon::profiles::EncodingProfile;
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::startup::AvailableEncoder;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+const LETTERBOX: CropRect = CropRect {
+    width: 1920,
+    height: 800,
+    x: 0,
+    y: 140,
+};
+
+fn probe() -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(7200.0),
+            size: 20_000_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            bitrate: None,
+            frame_rate: Some("24000/1001".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+            bit_depth: Some(8),
+            is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+fn job(is_web_like: bool) -> Job {
+    let probe = probe();
+    let candidate = CandidateFile {
+        path: PathBuf::from("/media/movie.mkv"),
+        size_bytes: probe.format.size,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let classification = SourceClassification {
+        source_type: if is_web_like {
+            SourceType::WebLike
+        } else {
+            SourceType::DiscLike
+        },
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    create_job(candidate, probe, classification)
+}
+
+fn cropped(crop: CropRect) -> Option<CropDetection> {
+    Some(CropDetection {
+        samples: 8,
+        agreeing: 8,
+        crop: Some(crop),
+    })
+}
+
+/// Stand-in for ffmpeg that logs its arguments and prints `crops`, one per
+/// call, as cropdetect lines; "black" prints the all-black result
+fn write_fake_ffmpeg(dir: &Path, crops: &[&str]) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    let cases: String = crops
+        .iter()
+        .enumerate()
+        .map(|(i, crop)| {
+            let crop = if *crop == "black" {
+                "-1920:-1088:1920:1088"
+            } else {
+                crop
+            };
+            format!(
+                "    {}) echo \"[Parsed_cropdetect_0 @ 0x1] x1:0 x2:1919 t:1.0 crop={}\" >&2 ;;\n",
+                i + 1,
+                crop
+            )
+        })
+        .collect();
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\nDIR=\"{dir}\"\necho \"$@\" >> \"$DIR/args\"\n\
+             n=$(cat \"$DIR/calls\" 2>/dev/null || echo 0)\nn=$((n + 1))\necho $n > \"$DIR/calls\"\n\
+             case $n in\n{cases}esac\n",
+            dir = dir.display(),
+            cases = cases
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+#[test]
+fn test_parse_cropdetect_uses_the_last_line() {
+    let log = "\
+[Parsed_cropdetect_0 @ 0x1] x1:0 x2:1919 y1:150 y2:929 w:1920 h:780 x:0 y:150 pts:0 t:0.0 crop=1920:780:0:150
+[Parsed_cropdetect_0 @ 0x1] x1:0 x2:1919 y1:140 y2:939 w:1920 h:800 x:0 y:140 pts:24 t:1.0 crop=1920:800:0:140
+";
+    assert_eq!(parse_cropdetect(log), Some(LETTERBOX));
+    assert_eq!(parse_cropdetect("crop=-1920:-1088:1920:1088"), None);
+    assert_eq!(parse_cropdetect("Conversion failed!"), None);
+}
+
+#[test]
+fn test_agree_on_crop() {
+    // Small differences between samples agree, and the union keeps all the picture
+    let jittered = CropRect {
+        height: 802,
+        y: 138,
+        ..LETTERBOX
+    };
+    let results = [Some(LETTERBOX), Some(jittered), None, Some(LETTERBOX)];
+    let detection = agree_on_crop(&results, 1920, 1080, 0.75);
+    assert_eq!(detection.samples, 4);
+    assert_eq!(detection.agreeing, 3);
+    assert_eq!(
+        detection.crop,
+        Some(CropRect {
+            width: 1920,
+            height: 802,
+            x: 0,
+            y: 138,
+        })
+    );
+
+    // Mixed-aspect films disagree and keep the full frame
+    let full = CropRect {
+        width: 1920,
+        height: 1080,
+        x: 0,
+        y: 0,
+    };
+    let results = [Some(LETTERBOX), Some(full), Some(LETTERBOX), Some(full)];
+    let detection = agree_on_crop(&results, 1920, 1080, 0.75);
+    assert_eq!(detection.agreeing, 2);
+    assert_eq!(detection.crop, None);
+
+    // ...even when the letterboxed scenes reach the agreement threshold
+    let results = [
+        Some(LETTERBOX),
+        Some(LETTERBOX),
+        Some(full),
+        Some(LETTERBOX),
+        Some(LETTERBOX),
+        Some(full),
+        Some(LETTERBOX),
+        Some(LETTERBOX),
+    ];
+    let detection = agree_on_crop(&results, 1920, 1080, 0.75);
+    assert_eq!(detection.agreeing, 6);
+    assert_eq!(detection.crop, None);
+
+    // Dark scenes find a smaller area and do not prevent the crop
+    let dark = CropRect {
+        width: 1600,
+        height: 600,
+        x: 160,
+        y: 240,
+    };
+    let mut results = [Some(LETTERBOX); 8];
+    results[2] = Some(dark);
+    results[5] = Some(dark);
+    let detection = agree_on_crop(&results, 1920, 1080, 0.75);
+    assert_eq!(detection.crop, Some(LETTERBOX));
+
+    // No black bars, or nothing but black
+    assert_eq!(agree_on_crop(&[Some(full); 3], 1920, 1080, 0.75).crop, None);
+    let all_black = agree_on_crop(&[None, None], 1920, 1080, 0.75);
+    assert_eq!((all_black.agreeing, all_black.crop), (0, None));
+}
+
+#[test]
+fn test_crop_is_combined_with_the_pad_filter() {
+    let mut disc = job(false);
+    assert!(video_filter_flags(&disc, 1920, 1080).is_empty());
+
+    disc.crop_detection = cropped(LETTERBOX);
+    assert_eq!(
+        video_filter_flags(&disc, 1920, 1080),
+        vec!["-vf", "crop=1920:800:0:140"]
+    );
+
+    // Odd cropped sizes are padded back to even
+    disc.crop_detection = cropped(CropRect {
+        height: 801,
+        ..LETTERBOX
+    });
+    assert_eq!(
+        video_filter_flags(&disc, 1920, 1080),
+        vec![
+            "-vf",
+            "crop=1920:801:0:140,pad=ceil(iw/2)*2:ceil(ih/2)*2,setsar=1"
+        ]
+    );
+
+    let mut web = job(true);
+    web.crop_detection = cropped(LETTERBOX);
+    let config = DaemonConfig::default();
+    let video = VideoSettings::new(
+        &config,
+        AvailableEncoder::SvtAv1,
+        &EncodingProfile::default(),
+        1080,
+    );
+    let command = build_command(&web, &probe(), &video, &config, 20, "/tmp/out.mkv").join(" ");
+    assert!(
+        command.contains("-vf crop=1920:800:0:140,pad=ceil(iw/2)*2:ceil(ih/2)*2,setsar=1"),
+        "{}",
+        command
+    );
+}
+
+#[tokio::test]
+async fn test_detect_crop_samples_the_source() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(
+        temp_dir.path(),
+        &[
+            "1920:800:0:140",
+            "black",
+            "1920:800:0:140",
+            "1920:804:0:138",
+        ],
+    );
+    let config = CropDetectionConfig {
+        samples: 4,
+        ..Default::default()
+    };
+
+    let detection = detect_crop(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/movie.mkv"),
+        &probe(),
+        &config,
+    )
+    .await
+    .unwrap();
+    assert_eq!(detection.samples, 4);
+    assert_eq!(detection.agreeing, 3);
+    assert_eq!(
+        detection.crop,
+        Some(CropRect {
+            width: 1920,
+            height: 804,
+            x: 0,
+            y: 138,
+        })
+    );
+
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    let calls: Vec<&str> = args.lines().collect();
+    assert_eq!(calls.len(), 4);
+    assert!(calls[0].contains("-ss 897.500 -t 5.000 -i /media/movie.mkv"));
+    assert!(calls[0].contains("[0:0]cropdetect=limit=0.0941:round=2:reset=0"));
+}
@@ -1,14 +1,97 @@
-use av1d_daemon::config::QualityTier;
-use av1d_daemon::encode::aom::{build_aom_command, select_cpu_used, select_tiles};
+use av1d_daemon::config::{AudioPolicy, DaemonConfig, QualityTier, TrackPolicy};
+use av1d_daemon::container::OutputContainer;
+use av1d_daemon::encode::aom::{build_aom_command, select_tiles};
 use av1d_daemon::encode::common::{pad_filter, stream_mapping_flags, websafe_input_flags};
 use av1d_daemon::encode::rav1e::build_rav1e_command;
 use av1d_daemon::encode::svt::build_svt_command;
-use av1d_daemon::encode::{select_crf, select_preset};
+use av1d_daemon::encode::{select_crf, select_preset, VideoSettings};
 use av1d_daemon::jobs::{Job, JobStatus};
+use av1d_daemon::probe::{AudioStream, FormatInfo, ProbeResult, SubtitleStream, VideoStream};
+use av1d_daemon::profiles::EncodingProfile;
+use av1d_daemon::startup::AvailableEncoder;
 use chrono::Utc;
 use proptest::prelude::*;
 use std::path::PathBuf;
 
+/// Encoder settings without an encoding profile
+fn settings(encoder: AvailableEncoder, preset: u8) -> VideoSettings {
+    VideoSettings {
+        encoder,
+        preset: Some(preset),
+        svt_params: Vec::new(),
+        film_grain: None,
+        film_grain_denoise: None,
+        keyint: None,
+    }
+}
+
+fn track_language() -> impl Strategy<Value = Option<&'static str>> {
+    prop::option::of(prop_oneof![
+        Just("eng"),
+        Just("jpn"),
+        Just("ru"),
+        Just("rus"),
+        Just("und"),
+    ])
+}
+
+/// Probe result with one video stream followed by the given (language, default) tracks
+fn probe_with_tracks(
+    audio: &[(Option<&str>, bool)],
+    subtitles: &[(Option<&str>, bool)],
+) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(3600.0),
+            size: 1_000_000_000,
+            bitrate: Some(5_000_000),
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "hevc".to_string(),
+            width: 1920,
+            height: 1080,
+            bitrate: Some(5_000_000),
+            frame_rate: Some("24/1".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+            bit_depth: Some(8),
+            is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: audio
+            .iter()
+            .enumerate()
+            .map(|(i, (language, is_default))| AudioStream {
+                index: i + 1,
+                codec_name: "aac".to_string(),
+                language: language.map(str::to_string),
+                title: None,
+                is_default: *is_default,
+                is_commentary: false,
+                channels: None,
+                channel_layout: None,
+                bitrate: None,
+            })
+            .collect(),
+        subtitle_streams: subtitles
+            .iter()
+            .enumerate()
+            .map(|(i, (language, is_default))| SubtitleStream {
+                index: audio.len() + i + 1,
+                codec_name: "subrip".to_string(),
+                language: language.map(str::to_string),
+                title: None,
+                is_default: *is_default,
+                is_commentary: false,
+            })
+            .collect(),
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(100))]
 
@@ -21,17 +104,18 @@ proptest! {
     /// - 19 for 1440p
     /// - 20 for 1080p
     /// - 21 for <1080p
-    /// No bitrate adjustment - prioritize quality over size
+    /// No bitrate adjustment by default - prioritize quality over size
     #[test]
     fn prop_crf_selection_by_resolution(
         height in 480i32..4320i32,
         bitrate in prop::option::of(1_000_000u64..100_000_000u64),
         quality_tier in prop_oneof![Just(QualityTier::High), Just(QualityTier::VeryHigh)],
     ) {
-        let crf = select_crf(height, bitrate, quality_tier);
+        let config = DaemonConfig { quality_tier, ..Default::default() };
+        let crf = select_crf(&config, AvailableEncoder::SvtAv1, height, bitrate);
 
         // Determine expected CRF based on height (no bitrate adjustment)
-        let base_crf = if height >= 2160 { 18 } else if height >= 1440 { 19 } else if height >= 1080 { 20 } else { 21 };
+        let base_crf: u8 = if height >= 2160 { 18 } else if height >= 1440 { 19 } else if height >= 1080 { 20 } else { 21 };
         let expected_crf = match quality_tier {
             QualityTier::High => base_crf,
             QualityTier::VeryHigh => base_crf.saturating_sub(2),
@@ -56,10 +140,11 @@ proptest! {
         height in 480i32..4320i32,
         quality_tier in prop_oneof![Just(QualityTier::High), Just(QualityTier::VeryHigh)]
     ) {
-        let preset = select_preset(height, quality_tier);
+        let config = DaemonConfig { quality_tier, ..Default::default() };
+        let preset = select_preset(&config, AvailableEncoder::SvtAv1, height);
 
         // Determine expected base preset based on height (slower presets for quality)
-        let expected_base_preset: u8 = if height >= 2160 { 1 } else if height >= 1440 { 2 } else if height >= 1080 { 2 } else { 3 };
+        let expected_base_preset: u8 = if height >= 2160 { 1 } else if height >= 1080 { 2 } else { 3 };
 
         // Apply quality tier adjustment (very_high pushes 2 steps slower)
         let expected_preset = match quality_tier {
@@ -67,40 +152,74 @@ proptest! {
             QualityTier::VeryHigh => expected_base_preset.saturating_sub(2),
         };
 
-        prop_assert_eq!(preset, expected_preset,
-            "Preset mismatch for height={}, quality_tier={:?}: expected {}, got {}",
+        prop_assert_eq!(preset, Some(expected_preset),
+            "Preset mismatch for height={}, quality_tier={:?}: expected {}, got {:?}",
             height, quality_tier, expected_preset, preset);
     }
 
     /// **Feature: av1-reencoder, Property 14: Stream mapping command construction**
     /// **Validates: Requirements 13.1, 13.2, 13.3, 13.4, 13.5**
     ///
-    /// For any ffmpeg command, it should include all required stream mapping flags:
-    /// - Map all streams initially (-map 0)
-    /// - Exclude attached pictures (-map -0:v:m:attached_pic)
-    /// - Exclude Russian audio tracks (-map -0:a:m:language:ru and rus)
-    /// - Exclude Russian subtitle tracks (-map -0:s:m:language:ru and rus)
+    /// For any set of audio and subtitle tracks and a drop list, the mapping flags should:
+    /// - Map video without attached pictures (-map 0:V)
+    /// - Map each kept audio and subtitle track explicitly by index
+    /// - Never map a dropped-language track unless it is default or the original language
+    /// - Always map at least one audio track when the source has any
     /// - Preserve chapters and metadata (-map_chapters 0, -map_metadata 0)
     #[test]
-    fn prop_stream_mapping_command_construction(_dummy in 0u8..1u8) {
-        let flags = stream_mapping_flags();
-
-        // Convert to a single string for easier checking
+    fn prop_stream_mapping_command_construction(
+        audio in prop::collection::vec((track_language(), any::<bool>()), 0..5),
+        subtitles in prop::collection::vec((track_language(), any::<bool>()), 0..5),
+    ) {
+        let probe = probe_with_tracks(&audio, &subtitles);
+        let policy = TrackPolicy {
+            audio_drop_languages: vec!["ru".to_string(), "rus".to_string()],
+            subtitle_drop_languages: vec!["ru".to_string(), "rus".to_string()],
+            ..Default::default()
+        };
+        let flags = stream_mapping_flags(
+            &probe,
+            &policy,
+            &AudioPolicy::default(),
+            OutputContainer::Matroska,
+        );
         let flags_str = flags.join(" ");
 
-        // Check for required flags
-        prop_assert!(flags.contains(&"-map".to_string()), "Missing -map flag");
-        prop_assert!(flags.contains(&"0".to_string()), "Missing initial stream selection '0'");
-        prop_assert!(flags_str.contains("-map -0:v:m:attached_pic"),
-            "Missing attached picture exclusion");
-        prop_assert!(flags_str.contains("-map -0:a:m:language:ru"),
-            "Missing Russian audio exclusion (ru)");
-        prop_assert!(flags_str.contains("-map -0:a:m:language:rus"),
-            "Missing Russian audio exclusion (rus)");
-        prop_assert!(flags_str.contains("-map -0:s:m:language:ru"),
-            "Missing Russian subtitle exclusion (ru)");
-        prop_assert!(flags_str.contains("-map -0:s:m:language:rus"),
-            "Missing Russian subtitle exclusion (rus)");
+        let mapped: Vec<String> = flags
+            .windows(2)
+            .filter(|w| w[0] == "-map")
+            .map(|w| w[1].clone())
+            .collect();
+
+        prop_assert!(mapped.contains(&"0:V".to_string()), "Missing video mapping");
+        prop_assert!(!mapped.contains(&"0".to_string()), "Should not map all streams");
+
+        let original = probe.audio_streams.first().and_then(|s| s.language.clone());
+        let streams = probe
+            .audio_streams
+            .iter()
+            .map(|s| (s.index, &s.language, s.is_default))
+            .chain(probe.subtitle_streams.iter().map(|s| (s.index, &s.language, s.is_default)));
+        for (index, language, is_default) in streams {
+            let dropped_language = matches!(language.as_deref(), Some("ru") | Some("rus"));
+            let protected = is_default || (language.is_some() && *language == original);
+            if dropped_language && !protected {
+                prop_assert!(!mapped.contains(&format!("0:{}", index)),
+                    "Track {} ({:?}) should be dropped", index, language);
+            }
+            if !dropped_language {
+                prop_assert!(mapped.contains(&format!("0:{}", index)),
+                    "Track {} ({:?}) should be kept", index, language);
+            }
+        }
+
+        if !probe.audio_streams.is_empty() {
+            prop_assert!(
+                probe.audio_streams.iter().any(|s| mapped.contains(&format!("0:{}", s.index))),
+                "Last audio track was dropped"
+            );
+        }
+
         prop_assert!(flags_str.contains("-map_chapters 0"),
             "Missing chapter preservation");
         prop_assert!(flags_str.contains("-map_metadata 0"),
@@ -197,6 +316,7 @@ proptest! {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -206,9 +326,27 @@ proptest! {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
-        let command = build_svt_command(&job, crf, preset, "/test/output.mkv");
+        let probe = probe_with_tracks(&[(Some("eng"), true)], &[(Some("eng"), false)]);
+        let command = build_svt_command(&job, &probe, &TrackPolicy::default(), &AudioPolicy::default(), &settings(AvailableEncoder::SvtAv1, preset), crf, "/test/output.mkv");
         let command_str = command.join(" ");
 
         // Check for required SVT-AV1 parameters
@@ -224,9 +362,9 @@ proptest! {
             "Missing SVT-AV1 logical processor parameter");
 
         // Check for audio and subtitle copying
-        prop_assert!(command_str.contains("-c:a copy"),
+        prop_assert!(command_str.contains("-c:a:0 copy"),
             "Missing audio stream copy");
-        prop_assert!(command_str.contains("-c:s copy"),
+        prop_assert!(command_str.contains("-c:s:0 copy"),
             "Missing subtitle stream copy");
 
         // Check for max muxing queue size
@@ -274,6 +412,7 @@ proptest! {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -283,9 +422,29 @@ proptest! {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
-        let command = build_aom_command(&job, crf, "/test/output.mkv");
+        let probe = probe_with_tracks(&[(Some("eng"), true)], &[(Some("eng"), false)]);
+        let config = DaemonConfig::default();
+        let video = VideoSettings::new(&config, AvailableEncoder::LibaomAv1, &EncodingProfile::default(), height);
+        let command = build_aom_command(&job, &probe, &TrackPolicy::default(), &AudioPolicy::default(), &video, crf, "/test/output.mkv");
         let command_str = command.join(" ");
 
         // Check for required libaom-av1 parameters
@@ -296,8 +455,8 @@ proptest! {
         prop_assert!(command_str.contains(&format!("-crf {}", crf)),
             "Missing or incorrect CRF value");
 
-        // Check cpu-used based on resolution
-        let expected_cpu_used = select_cpu_used(height);
+        // Check cpu-used from the preset table
+        let expected_cpu_used = select_preset(&config, AvailableEncoder::LibaomAv1, height).unwrap();
         prop_assert!(command_str.contains(&format!("-cpu-used {}", expected_cpu_used)),
             "Missing or incorrect cpu-used value for height {}", height);
 
@@ -311,9 +470,9 @@ proptest! {
             "Missing or incorrect tile configuration for height {}", height);
 
         // Check for audio and subtitle copying
-        prop_assert!(command_str.contains("-c:a copy"),
+        prop_assert!(command_str.contains("-c:a:0 copy"),
             "Missing audio stream copy");
-        prop_assert!(command_str.contains("-c:s copy"),
+        prop_assert!(command_str.contains("-c:s:0 copy"),
             "Missing subtitle stream copy");
 
         // Check for max muxing queue size
@@ -361,6 +520,7 @@ proptest! {
             crf_used: None,
             preset_used: None,
             encoder_used: None,
+            encoding_profile: None,
             source_bit_depth: Some(8),
             source_pix_fmt: Some("yuv420p".to_string()),
             is_hdr: Some(false),
@@ -370,23 +530,43 @@ proptest! {
             quality_tier: None,
             test_clip_path: None,
             test_clip_approved: None,
+            stage: None,
+            encoded_bytes: None,
+            encoded_duration: None,
+            progress: None,
+            eta: None,
+            output_est_bytes: None,
+            speed_bps: None,
+            original_duration: None,
+            priority: None,
+            quality_scores: None,
+            crf_search: None,
+            savings_prediction: None,
+            effective_config: None,
+            grain_analysis: None,
+            crop_detection: None,
+            interlace: None,
+            subtitles: None,
         };
 
+        let probe = probe_with_tracks(&[(Some("eng"), true)], &[(Some("eng"), false)]);
+        let policy = TrackPolicy::default();
+
         // Build command based on encoder type
         let command = match encoder_type {
-            0 => build_svt_command(&job, crf, 4, "/test/output.mkv"),
-            1 => build_aom_command(&job, crf, "/test/output.mkv"),
-            _ => build_rav1e_command(&job, crf, "/test/output.mkv"),
+            0 => build_svt_command(&job, &probe, &policy, &AudioPolicy::default(), &settings(AvailableEncoder::SvtAv1, 4), crf, "/test/output.mkv"),
+            1 => build_aom_command(&job, &probe, &policy, &AudioPolicy::default(), &settings(AvailableEncoder::LibaomAv1, 4), crf, "/test/output.mkv"),
+            _ => build_rav1e_command(&job, &probe, &policy, &AudioPolicy::default(), &settings(AvailableEncoder::Librav1e, 4), crf, "/test/output.mkv"),
         };
 
         let command_str = command.join(" ");
 
         // Check for audio stream copying
-        prop_assert!(command_str.contains("-c:a copy"),
+        prop_assert!(command_str.contains("-c:a:0 copy"),
             "Missing audio stream copy parameter");
 
         // Check for subtitle stream copying
-        prop_assert!(command_str.contains("-c:s copy"),
+        prop_assert!(command_str.contains("-c:s:0 copy"),
             "Missing subtitle stream copy parameter");
 
         // Check for max muxing queue size
@@ -0,0 +1,136 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::encode::{execute_encode_interruptible, EncodeAbortedEarly};
+use av1d_daemon::jobs::{create_job, Job};
+use av1d_daemon::probe::{FormatInfo, ProbeResult};
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::size_gate::EarlyAbortPolicy;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use tokio::sync::watch;
+
+fn test_job(source_path: PathBuf, size_bytes: u64, duration: f64) -> Job {
+    let candidate = CandidateFile {
+        path: source_path,
+        size_bytes,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let probe = ProbeResult {
+        format: FormatInfo {
+            duration: Some(duration),
+            size: size_bytes,
+            bitrate: None,
+        },
+        video_streams: vec![],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::Unknown,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    create_job(candidate, probe, classification)
+}
+
+/// Stand-in for ffmpeg that writes its output (last argument) and then runs
+/// `body` (shell) to report progress.
+fn write_fake_ffmpeg(dir: &Path, body: &str) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\nfor out; do :; done\necho partial > \"$out\"\n{}\n",
+            body
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+fn command(ffmpeg: &Path, output: &Path) -> Vec<String> {
+    vec![
+        ffmpeg.to_string_lossy().to_string(),
+        "-i".to_string(),
+        "input.mkv".to_string(),
+        output.to_string_lossy().to_string(),
+    ]
+}
+
+#[tokio::test]
+async fn test_encode_aborts_when_projection_fails_size_gate() {
+    let temp_dir = TempDir::new().unwrap();
+    // 25% through a 1000s file after writing 900 bytes: projects 3600 bytes
+    // against a 900 byte threshold. Progress blocks repeat like ffmpeg's.
+    let block =
+        "echo out_time_ms=250000000; echo total_size=900; echo speed=1.0x; echo progress=continue";
+    let ffmpeg = write_fake_ffmpeg(
+        temp_dir.path(),
+        &format!("{block}\nsleep 1\n{block}\nsleep 30\necho progress=end"),
+    );
+    let output = temp_dir.path().join("out.mkv");
+    let mut job = test_job(temp_dir.path().join("movie.mkv"), 1_000, 1000.0);
+    let policy = EarlyAbortPolicy {
+        original_bytes: 1_000,
+        max_ratio: 0.9,
+        min_percent: 10.0,
+        min_encoded_secs: 60.0,
+        margin: 1.1,
+    };
+
+    let (_tx, rx) = watch::channel(None);
+    let started = std::time::Instant::now();
+    let err = execute_encode_interruptible(
+        &mut job,
+        command(&ffmpeg, &output),
+        &temp_dir.path().join("jobs"),
+        rx,
+        Some(policy),
+    )
+    .await
+    .unwrap_err();
+
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    let aborted = err.downcast::<EncodeAbortedEarly>().unwrap();
+    assert_eq!(aborted.progress, 25.0);
+    assert_eq!(aborted.projected_bytes, 3600);
+    assert_eq!(aborted.threshold_bytes, 900);
+    assert!(!output.exists());
+}
+
+#[tokio::test]
+async fn test_encode_completes_when_projection_is_within_gate() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(
+        temp_dir.path(),
+        "echo out_time_ms=500000000\necho total_size=200\necho speed=1.0x\nsleep 1\necho progress=end",
+    );
+    let output = temp_dir.path().join("out.mkv");
+    let mut job = test_job(temp_dir.path().join("movie.mkv"), 1_000, 1000.0);
+    let policy = EarlyAbortPolicy {
+        original_bytes: 1_000,
+        max_ratio: 0.9,
+        min_percent: 10.0,
+        min_encoded_secs: 60.0,
+        margin: 1.1,
+    };
+
+    let (_tx, rx) = watch::channel(None);
+    let result = execute_encode_interruptible(
+        &mut job,
+        command(&ffmpeg, &output),
+        &temp_dir.path().join("jobs"),
+        rx,
+        Some(policy),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result, output);
+    assert_eq!(job.output_est_bytes, Some(400));
+}
@@ -48,9 +48,14 @@ fn property_size_threshold_enforcement() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: true,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             }],
             audio_streams: vec![],
             subtitle_streams: vec![],
+            chapters: 0,
+            attachments: vec![],
         };
 
         // Create config with specified min_bytes
@@ -66,6 +71,7 @@ fn property_size_threshold_enforcement() {
             quality_tier: QualityTier::High,
             keep_original: false,
             write_why_sidecars: true,
+            ..Default::default()
         };
 
         // Check gates
@@ -121,9 +127,14 @@ fn test_size_threshold_boundary() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let config = DaemonConfig {
@@ -138,6 +149,7 @@ fn test_size_threshold_boundary() {
         quality_tier: QualityTier::High,
         keep_original: false,
         write_why_sidecars: true,
+        ..Default::default()
     };
 
     let result = check_gates(&candidate, &probe, &config);
@@ -180,9 +192,14 @@ fn test_size_just_above_threshold() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let config = DaemonConfig {
@@ -197,6 +214,7 @@ fn test_size_just_above_threshold() {
         quality_tier: QualityTier::High,
         keep_original: false,
         write_why_sidecars: true,
+        ..Default::default()
     };
 
     let result = check_gates(&candidate, &probe, &config);
@@ -247,9 +265,14 @@ fn property_av1_codec_detection_and_skip() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: true,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             }],
             audio_streams: vec![],
             subtitle_streams: vec![],
+            chapters: 0,
+            attachments: vec![],
         };
 
         let config = DaemonConfig {
@@ -264,6 +287,7 @@ fn property_av1_codec_detection_and_skip() {
             quality_tier: QualityTier::High,
             keep_original: false,
             write_why_sidecars: true,
+            ..Default::default()
         };
 
         let result = check_gates(&candidate, &probe, &config);
@@ -311,8 +335,16 @@ fn test_no_video_streams() {
             index: 0,
             codec_name: "aac".to_string(),
             language: Some("eng".to_string()),
+            title: None,
+            is_default: true,
+            is_commentary: false,
+            channels: None,
+            channel_layout: None,
+            bitrate: None,
         }],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let config = DaemonConfig {
@@ -327,6 +359,7 @@ fn test_no_video_streams() {
         quality_tier: QualityTier::High,
         keep_original: false,
         write_why_sidecars: true,
+        ..Default::default()
     };
 
     let result = check_gates(&candidate, &probe, &config);
@@ -369,9 +402,14 @@ fn test_skip_marker_gate() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let config = DaemonConfig {
@@ -386,6 +424,7 @@ fn test_skip_marker_gate() {
         quality_tier: QualityTier::High,
         keep_original: false,
         write_why_sidecars: true,
+        ..Default::default()
     };
 
     let result = check_gates(&candidate, &probe, &config);
@@ -422,6 +461,8 @@ fn test_gate_evaluation_order() {
         video_streams: vec![], // No video
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let config = DaemonConfig {
@@ -436,6 +477,7 @@ fn test_gate_evaluation_order() {
         quality_tier: QualityTier::High,
         keep_original: false,
         write_why_sidecars: true,
+        ..Default::default()
     };
 
     let result = check_gates(&candidate, &probe, &config);
@@ -477,9 +519,14 @@ fn test_valid_file_passes_gates() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let config = DaemonConfig {
@@ -494,6 +541,7 @@ fn test_valid_file_passes_gates() {
         quality_tier: QualityTier::High,
         keep_original: false,
         write_why_sidecars: true,
+        ..Default::default()
     };
 
     let result = check_gates(&candidate, &probe, &config);
@@ -535,6 +583,9 @@ fn test_multiple_video_streams() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: true,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             },
             VideoStream {
                 index: 1,
@@ -546,10 +597,15 @@ fn test_multiple_video_streams() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: false,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             },
         ],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let config = DaemonConfig {
@@ -564,6 +620,7 @@ fn test_multiple_video_streams() {
         quality_tier: QualityTier::High,
         keep_original: false,
         write_why_sidecars: true,
+        ..Default::default()
     };
 
     let result = check_gates(&candidate, &probe, &config);
@@ -0,0 +1,194 @@
+use av1d_daemon::config::{DaemonConfig, GrainDetectionConfig};
+use av1d_daemon::encode::common::SourceFilters;
+use av1d_daemon::encode::{video_encoder_args, VideoSettings};
+use av1d_daemon::grain::{
+    detect_grain, grain_strength, noise_from_psnr, parse_noise_psnr, GrainAnalysis,
+};
+use av1d_daemon::probe::{FormatInfo, ProbeResult, VideoStream};
+use av1d_daemon::profiles::EncodingProfile;
+use av1d_daemon::startup::AvailableEncoder;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn probe(duration: f64) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(duration),
+            size: 1_000_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            bitrate: None,
+            frame_rate: Some("24000/1001".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+            bit_depth: Some(8),
+            is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+/// Stand-in for ffmpeg that logs its arguments and reports a PSNR line,
+/// noisier with each sample, or fails
+fn write_fake_ffmpeg(dir: &Path, succeed: bool) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    let body = if succeed {
+        r#"n=$(cat "$DIR/calls" 2>/dev/null || echo 0)
+n=$((n + 1))
+echo $n > "$DIR/calls"
+echo "[Parsed_psnr_3 @ 0x1] PSNR y:$((41 - n)).0 average:$((41 - n)).0 min:30.0 max:50.0" >&2"#
+    } else {
+        "echo 'Invalid data found when processing input' >&2\nexit 1"
+    };
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\nDIR=\"{}\"\necho \"$@\" >> \"$DIR/args\"\n{}\n",
+            dir.display(),
+            body
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+#[test]
+fn test_grain_strength_follows_the_noise_level() {
+    let config = GrainDetectionConfig::default();
+    assert_eq!(grain_strength(0.8, &config), 0);
+    assert_eq!(grain_strength(1.5, &config), 6);
+    assert_eq!(grain_strength(3.1, &config), 12);
+    assert_eq!(grain_strength(40.0, &config), config.max_film_grain);
+
+    let clean = GrainAnalysis::new(0.5, 4, &config);
+    assert_eq!(clean.film_grain, 0);
+    assert!(!clean.denoise);
+    let grainy = GrainAnalysis::new(3.0, 4, &config);
+    assert_eq!(grainy.film_grain, 12);
+    assert!(grainy.denoise);
+}
+
+#[test]
+fn test_noise_from_psnr() {
+    assert!((noise_from_psnr(48.13) - 1.0).abs() < 0.01);
+    assert!((noise_from_psnr(34.15) - 5.0).abs() < 0.01);
+    assert_eq!(noise_from_psnr(f64::INFINITY), 0.0);
+
+    let log = "[Parsed_psnr_3 @ 0x1] PSNR y:38.50 average:38.50 min:35.1 max:41.2";
+    assert_eq!(parse_noise_psnr(log), Some(38.5));
+    assert_eq!(
+        parse_noise_psnr("PSNR y:inf average:inf min:inf max:inf"),
+        Some(f64::INFINITY)
+    );
+    assert_eq!(parse_noise_psnr("Conversion failed!"), None);
+}
+
+#[test]
+fn test_grain_settings_reach_the_encoder_arguments() {
+    let config = DaemonConfig::default();
+    let grain = GrainAnalysis::new(3.0, 4, &config.grain_detection);
+    let probe = probe(600.0);
+
+    let mut svt = VideoSettings::new(
+        &config,
+        AvailableEncoder::SvtAv1,
+        &EncodingProfile::default(),
+        1080,
+    );
+    grain.apply(&mut svt);
+    let args = video_encoder_args(&svt, &probe, 1080, 20).join(" ");
+    assert!(
+        args.contains("film-grain=12:film-grain-denoise=1"),
+        "{}",
+        args
+    );
+
+    let no_denoise = GrainDetectionConfig {
+        denoise: false,
+        ..Default::default()
+    };
+    let mut aom = VideoSettings::new(
+        &config,
+        AvailableEncoder::LibaomAv1,
+        &EncodingProfile::default(),
+        1080,
+    );
+    GrainAnalysis::new(3.0, 4, &no_denoise).apply(&mut aom);
+    let args = video_encoder_args(&aom, &probe, 1080, 20).join(" ");
+    assert!(args.contains("-denoise-noise-level 12"), "{}", args);
+    assert!(
+        args.contains("-aom-params enable-dnl-denoising=0"),
+        "{}",
+        args
+    );
+
+    // Clean sources leave the encoder settings alone
+    let mut clean = svt.clone();
+    clean.film_grain = None;
+    clean.film_grain_denoise = None;
+    GrainAnalysis::new(0.5, 4, &config.grain_detection).apply(&mut clean);
+    let args = video_encoder_args(&clean, &probe, 1080, 20).join(" ");
+    assert!(!args.contains("film-grain"), "{}", args);
+}
+
+#[tokio::test]
+async fn test_detect_grain_averages_the_samples() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), true);
+    let config = GrainDetectionConfig {
+        samples: 2,
+        ..Default::default()
+    };
+
+    let grain = detect_grain(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/movie.mkv"),
+        &probe(600.0),
+        &SourceFilters::default(),
+        &config,
+    )
+    .await
+    .unwrap();
+
+    // PSNR 40 and 39 against the denoised copy
+    let expected = (noise_from_psnr(40.0) + noise_from_psnr(39.0)) / 2.0;
+    assert_eq!(grain.samples, 2);
+    assert!((grain.noise_level - expected).abs() < 1e-9);
+    assert_eq!(grain.film_grain, grain_strength(expected, &config));
+
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    let calls: Vec<&str> = args.lines().collect();
+    assert_eq!(calls.len(), 2);
+    assert!(calls[0].contains("-ss 149.000 -t 2.000 -i /media/movie.mkv"));
+    assert!(calls[0].contains("hqdn3d"));
+}
+
+#[tokio::test]
+async fn test_detect_grain_reports_ffmpeg_failure() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), false);
+
+    let err = detect_grain(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/movie.mkv"),
+        &probe(600.0),
+        &SourceFilters::default(),
+        &GrainDetectionConfig::default(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("Invalid data"), "{}", err);
+}
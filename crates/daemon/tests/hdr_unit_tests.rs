@@ -0,0 +1,312 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::config::{AudioPolicy, TrackPolicy};
+use av1d_daemon::encode::aom::build_aom_command;
+use av1d_daemon::encode::svt::build_svt_command;
+use av1d_daemon::encode::VideoSettings;
+use av1d_daemon::jobs::create_job;
+use av1d_daemon::probe::{parse_probe_json, ColorInfo, DolbyVisionConfig, HdrFormat, ProbeResult};
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::startup::AvailableEncoder;
+use av1d_daemon::validate::{check_hdr_signalling, ValidationError};
+use std::path::PathBuf;
+
+/// Encoder settings without an encoding profile
+fn settings(encoder: AvailableEncoder, preset: u8) -> VideoSettings {
+    VideoSettings {
+        encoder,
+        preset: Some(preset),
+        svt_params: Vec::new(),
+        film_grain: None,
+        film_grain_denoise: None,
+        keyint: None,
+    }
+}
+
+const HDR10_PROBE: &str = r#"{
+    "format": { "duration": "5400.0", "size": "40000000000", "bit_rate": "59000000" },
+    "streams": [
+        {
+            "index": 0,
+            "codec_type": "video",
+            "codec_name": "hevc",
+            "width": 3840,
+            "height": 2160,
+            "pix_fmt": "yuv420p10le",
+            "color_range": "tv",
+            "color_space": "bt2020nc",
+            "color_transfer": "smpte2084",
+            "color_primaries": "bt2020",
+            "disposition": { "default": 1 },
+            "side_data_list": [
+                {
+                    "side_data_type": "DOVI configuration record",
+                    "dv_version_major": 1,
+                    "dv_version_minor": 0,
+                    "dv_profile": 8,
+                    "dv_level": 6,
+                    "rpu_present_flag": 1,
+                    "el_present_flag": 0,
+                    "bl_present_flag": 1,
+                    "dv_bl_signal_compatibility_id": 1
+                },
+                {
+                    "side_data_type": "Mastering display metadata",
+                    "red_x": "34000/50000",
+                    "red_y": "16000/50000",
+                    "green_x": "13250/50000",
+                    "green_y": "34500/50000",
+                    "blue_x": "7500/50000",
+                    "blue_y": "3000/50000",
+                    "white_point_x": "15635/50000",
+                    "white_point_y": "16450/50000",
+                    "min_luminance": "50/10000",
+                    "max_luminance": "10000000/10000"
+                },
+                {
+                    "side_data_type": "Content light level metadata",
+                    "max_content": 1000,
+                    "max_average": 400
+                }
+            ]
+        },
+        {
+            "index": 1,
+            "codec_type": "audio",
+            "codec_name": "truehd",
+            "tags": { "language": "eng" }
+        }
+    ]
+}"#;
+
+fn hdr10_color() -> ColorInfo {
+    ColorInfo {
+        primaries: Some("bt2020".to_string()),
+        transfer: Some("smpte2084".to_string()),
+        matrix: Some("bt2020nc".to_string()),
+        range: Some("tv".to_string()),
+        ..Default::default()
+    }
+}
+
+fn dolby_vision(profile: u8, compatibility_id: u8) -> DolbyVisionConfig {
+    DolbyVisionConfig {
+        profile,
+        level: 6,
+        rpu_present: true,
+        el_present: false,
+        bl_present: true,
+        bl_signal_compatibility_id: compatibility_id,
+    }
+}
+
+fn job_for(probe: &ProbeResult) -> av1d_daemon::jobs::Job {
+    let candidate = CandidateFile {
+        path: PathBuf::from("/media/movie.mkv"),
+        size_bytes: probe.format.size,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::DiscLike,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    let mut job = create_job(candidate, probe.clone(), classification);
+    job.video_width = Some(3840);
+    job.video_height = Some(2160);
+    job
+}
+
+#[test]
+fn test_probe_parses_color_and_side_data() {
+    let probe = parse_probe_json(HDR10_PROBE).unwrap();
+    let color = &probe.main_video_stream().unwrap().color;
+
+    assert_eq!(color.primaries.as_deref(), Some("bt2020"));
+    assert_eq!(color.transfer.as_deref(), Some("smpte2084"));
+    assert_eq!(color.matrix.as_deref(), Some("bt2020nc"));
+    assert_eq!(color.range.as_deref(), Some("tv"));
+
+    let md = color.mastering_display.unwrap();
+    assert_eq!(md.red, (0.68, 0.32));
+    assert_eq!(md.white_point, (0.3127, 0.329));
+    assert_eq!(md.max_luminance, 1000.0);
+    assert_eq!(md.min_luminance, 0.005);
+
+    let cll = color.content_light_level.unwrap();
+    assert_eq!((cll.max_cll, cll.max_fall), (1000, 400));
+
+    let dv = color.dolby_vision.unwrap();
+    assert_eq!(dv, dolby_vision(8, 1));
+    assert!(dv.has_compatible_base_layer());
+    assert_eq!(color.hdr_format(), Some(HdrFormat::DolbyVision));
+}
+
+#[test]
+fn test_probe_ignores_unknown_color_values() {
+    let json = r#"{
+        "streams": [{
+            "index": 0, "codec_type": "video", "codec_name": "h264",
+            "width": 1920, "height": 1080, "pix_fmt": "yuv420p10le",
+            "color_primaries": "unknown", "color_transfer": "unknown"
+        }]
+    }"#;
+    let probe = parse_probe_json(json).unwrap();
+    let color = &probe.video_streams[0].color;
+
+    assert_eq!(color, &ColorInfo::default());
+    assert_eq!(color.hdr_format(), None);
+}
+
+#[test]
+fn test_create_job_detects_hdr_from_signalling_not_bit_depth() {
+    let probe = parse_probe_json(HDR10_PROBE).unwrap();
+    assert_eq!(job_for(&probe).is_hdr, Some(true));
+
+    let mut sdr_10bit = probe.clone();
+    sdr_10bit.video_streams[0].color = ColorInfo::default();
+    assert_eq!(job_for(&sdr_10bit).is_hdr, Some(false));
+}
+
+#[test]
+fn test_encoders_pass_color_description() {
+    let probe = parse_probe_json(HDR10_PROBE).unwrap();
+    let job = job_for(&probe);
+    let policy = TrackPolicy::default();
+
+    for command in [
+        build_svt_command(
+            &job,
+            &probe,
+            &policy,
+            &AudioPolicy::default(),
+            &settings(AvailableEncoder::SvtAv1, 4),
+            20,
+            "/tmp/out.mkv",
+        ),
+        build_aom_command(
+            &job,
+            &probe,
+            &policy,
+            &AudioPolicy::default(),
+            &settings(AvailableEncoder::LibaomAv1, 4),
+            20,
+            "/tmp/out.mkv",
+        ),
+    ] {
+        let command = command.join(" ");
+        assert!(command.contains("-color_primaries bt2020"), "{}", command);
+        assert!(command.contains("-color_trc smpte2084"), "{}", command);
+        assert!(command.contains("-colorspace bt2020nc"), "{}", command);
+        assert!(command.contains("-color_range tv"), "{}", command);
+        assert!(command.contains("-strict unofficial"), "{}", command);
+    }
+}
+
+#[test]
+fn test_svt_writes_hdr10_static_metadata() {
+    let probe = parse_probe_json(HDR10_PROBE).unwrap();
+    let command = build_svt_command(
+        &job_for(&probe),
+        &probe,
+        &TrackPolicy::default(),
+        &AudioPolicy::default(),
+        &settings(AvailableEncoder::SvtAv1, 4),
+        20,
+        "/tmp/out.mkv",
+    );
+    let params_index = command.iter().position(|a| a == "-svtav1-params").unwrap();
+
+    assert_eq!(
+        command[params_index + 1],
+        "lp=0:enable-hdr=1:mastering-display=G(0.2650,0.6900)B(0.1500,0.0600)R(0.6800,0.3200)WP(0.3127,0.3290)L(1000.0000,0.0050):content-light=1000,400"
+    );
+}
+
+#[test]
+fn test_sdr_source_gets_no_hdr_parameters() {
+    let mut probe = parse_probe_json(HDR10_PROBE).unwrap();
+    probe.video_streams[0].color = ColorInfo::default();
+    let command = build_svt_command(
+        &job_for(&probe),
+        &probe,
+        &TrackPolicy::default(),
+        &AudioPolicy::default(),
+        &settings(AvailableEncoder::SvtAv1, 4),
+        20,
+        "/tmp/out.mkv",
+    )
+    .join(" ");
+
+    assert!(command.contains("-svtav1-params lp=0 "));
+    assert!(!command.contains("-color_trc"));
+    assert!(!command.contains("-strict"));
+}
+
+#[test]
+fn test_validation_rejects_hdr_tagged_as_sdr() {
+    let output = ColorInfo {
+        primaries: Some("bt709".to_string()),
+        transfer: Some("bt709".to_string()),
+        matrix: Some("bt709".to_string()),
+        ..Default::default()
+    };
+
+    let error = check_hdr_signalling(&hdr10_color(), &output).unwrap();
+    assert_eq!(
+        error,
+        ValidationError::HdrMismatch {
+            expected: "HDR10 (bt2020/smpte2084/bt2020nc)".to_string(),
+            actual: "SDR (bt709/bt709/bt709)".to_string(),
+        }
+    );
+    assert!(check_hdr_signalling(&hdr10_color(), &ColorInfo::default()).is_some());
+}
+
+#[test]
+fn test_validation_checks_primaries_and_hlg() {
+    let wrong_primaries = ColorInfo {
+        primaries: Some("bt709".to_string()),
+        ..hdr10_color()
+    };
+    assert!(check_hdr_signalling(&hdr10_color(), &wrong_primaries).is_some());
+    assert_eq!(check_hdr_signalling(&hdr10_color(), &hdr10_color()), None);
+
+    let hlg = ColorInfo {
+        transfer: Some("arib-std-b67".to_string()),
+        ..hdr10_color()
+    };
+    assert!(check_hdr_signalling(&hlg, &hdr10_color()).is_some());
+    assert_eq!(check_hdr_signalling(&hlg, &hlg), None);
+
+    // SDR sources with untagged output are fine
+    assert_eq!(
+        check_hdr_signalling(&ColorInfo::default(), &ColorInfo::default()),
+        None
+    );
+}
+
+#[test]
+fn test_validation_of_dolby_vision_sources() {
+    // Profile 8.1: the HDR10 base layer is enough
+    let profile_8 = ColorInfo {
+        dolby_vision: Some(dolby_vision(8, 1)),
+        ..hdr10_color()
+    };
+    assert_eq!(check_hdr_signalling(&profile_8, &hdr10_color()), None);
+
+    // Profile 5: without Dolby Vision the colours are wrong
+    let profile_5 = ColorInfo {
+        dolby_vision: Some(dolby_vision(5, 0)),
+        ..hdr10_color()
+    };
+    assert!(matches!(
+        check_hdr_signalling(&profile_5, &hdr10_color()),
+        Some(ValidationError::HdrMismatch { .. })
+    ));
+    let dv_output = ColorInfo {
+        dolby_vision: Some(dolby_vision(10, 0)),
+        ..hdr10_color()
+    };
+    assert_eq!(check_hdr_signalling(&profile_5, &dv_output), None);
+}
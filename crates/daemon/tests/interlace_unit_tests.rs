@@ -0,0 +1,332 @@
+use av1d_daemon::classify::{classify_source, SourceClassification, SourceType};
+use av1d_daemon::config::{DaemonConfig, DeinterlaceConfig};
+use av1d_daemon::crop::{CropDetection, CropRect};
+use av1d_daemon::encode::common::video_filter_flags;
+use av1d_daemon::gates::GateResult;
+use av1d_daemon::interlace::{
+    detect_interlacing, is_interlaced_field_order, parse_idet, IdetCounts, InterlaceDecision,
+    ScanType, DEINTERLACE_FILTER, INVERSE_TELECINE_FILTER,
+};
+use av1d_daemon::jobs::{create_job, Job};
+use av1d_daemon::probe::{
+    parse_probe_json, FormatInfo, ProbeResult, VideoStream, PROBE_SCHEMA_VERSION,
+};
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::scan_cache::{
+    load_scan_cache, save_scan_cache, FileFingerprint, GateSettings, ScanCache, ScanCacheEntry,
+};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn probe() -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(3600.0),
+            size: 8_000_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "mpeg2video".to_string(),
+            width: 720,
+            height: 480,
+            bitrate: None,
+            frame_rate: Some("30000/1001".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+            bit_depth: Some(8),
+            is_default: true,
+            color: Default::default(),
+            field_order: Some("tt".to_string()),
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+fn job() -> Job {
+    let probe = probe();
+    let candidate = CandidateFile {
+        path: PathBuf::from("/media/show.mkv"),
+        size_bytes: probe.format.size,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::DiscLike,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    create_job(candidate, probe, classification)
+}
+
+fn idet(tff: u64, progressive: u64, repeated: u64, frames: u64) -> IdetCounts {
+    IdetCounts {
+        tff,
+        bff: 0,
+        progressive,
+        undetermined: 0,
+        repeated,
+        frames,
+    }
+}
+
+/// Stand-in for ffmpeg that logs its arguments and prints `counts`, one
+/// (tff, progressive, repeated) per call, as idet's end-of-run lines
+fn write_fake_ffmpeg(dir: &Path, counts: &[(u64, u64, u64)]) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    let cases: String = counts
+        .iter()
+        .enumerate()
+        .map(|(i, (tff, progressive, repeated))| {
+            format!(
+                "    {n}) echo \"[Parsed_idet_0 @ 0x1] Repeated Fields: Neither: {neither} Top: {repeated} Bottom: 0\" >&2\n\
+                 echo \"[Parsed_idet_0 @ 0x1] Multi frame detection: TFF: {tff} BFF: 0 Progressive: {progressive} Undetermined: 0\" >&2 ;;\n",
+                n = i + 1,
+                neither = tff + progressive - repeated,
+                repeated = repeated,
+                tff = tff,
+                progressive = progressive
+            )
+        })
+        .collect();
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\nDIR=\"{dir}\"\necho \"$@\" >> \"$DIR/args\"\n\
+             n=$(cat \"$DIR/calls\" 2>/dev/null || echo 0)\nn=$((n + 1))\necho $n > \"$DIR/calls\"\n\
+             case $n in\n{cases}    *) exit 1 ;;\nesac\n",
+            dir = dir.display(),
+            cases = cases
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+#[test]
+fn test_parse_idet_uses_the_final_counts() {
+    let log = "\
+[Parsed_idet_0 @ 0x1] Repeated Fields: Neither:   230 Top:    10 Bottom:    12
+[Parsed_idet_0 @ 0x1] Single frame detection: TFF:   100 BFF:     0 Progressive:   120 Undetermined:    32
+[Parsed_idet_0 @ 0x1] Multi frame detection: TFF:   140 BFF:     2 Progressive:   105 Undetermined:     5
+";
+    assert_eq!(
+        parse_idet(log),
+        Some(IdetCounts {
+            tff: 140,
+            bff: 2,
+            progressive: 105,
+            undetermined: 5,
+            repeated: 22,
+            frames: 252,
+        })
+    );
+    assert_eq!(parse_idet("Conversion failed!"), None);
+}
+
+#[test]
+fn test_scan_type_thresholds() {
+    let config = DeinterlaceConfig::default();
+    assert_eq!(
+        idet(5, 95, 0, 100).scan_type(&config),
+        ScanType::Progressive
+    );
+    assert_eq!(
+        idet(90, 10, 2, 100).scan_type(&config),
+        ScanType::Interlaced
+    );
+    assert_eq!(
+        idet(60, 40, 20, 100).scan_type(&config),
+        ScanType::Telecined
+    );
+    assert_eq!(
+        IdetCounts::default().scan_type(&config),
+        ScanType::Progressive
+    );
+
+    assert_eq!(ScanType::Progressive.filter(), None);
+    assert_eq!(ScanType::Interlaced.filter(), Some(DEINTERLACE_FILTER));
+    assert_eq!(ScanType::Telecined.filter(), Some(INVERSE_TELECINE_FILTER));
+}
+
+#[test]
+fn test_decision_falls_back_to_the_field_order() {
+    let config = DeinterlaceConfig::default();
+    assert!(is_interlaced_field_order(Some("bb")));
+    assert!(!is_interlaced_field_order(Some("progressive")));
+    assert!(!is_interlaced_field_order(None));
+
+    let flagged = InterlaceDecision::new(Some("tt"), None, &config);
+    assert_eq!(flagged.scan_type, ScanType::Interlaced);
+    assert_eq!(flagged.filter.as_deref(), Some(DEINTERLACE_FILTER));
+
+    // idet overrules a field order flag that does not match the content
+    let detected = InterlaceDecision::new(Some("tt"), Some(idet(2, 98, 0, 100)), &config);
+    assert_eq!(detected.scan_type, ScanType::Progressive);
+    assert_eq!(detected.filter, None);
+}
+
+#[test]
+fn test_deinterlace_comes_before_crop_and_pad() {
+    let mut job = job();
+    job.interlace = Some(InterlaceDecision::new(
+        Some("tt"),
+        None,
+        &DeinterlaceConfig::default(),
+    ));
+    assert_eq!(
+        video_filter_flags(&job, 720, 480),
+        vec!["-vf".to_string(), DEINTERLACE_FILTER.to_string()]
+    );
+
+    job.crop_detection = Some(CropDetection {
+        samples: 8,
+        agreeing: 8,
+        crop: Some(CropRect {
+            width: 704,
+            height: 363,
+            x: 8,
+            y: 58,
+        }),
+    });
+    assert_eq!(
+        video_filter_flags(&job, 720, 480),
+        vec![
+            "-vf".to_string(),
+            format!(
+                "{},crop=704:363:8:58,pad=ceil(iw/2)*2:ceil(ih/2)*2,setsar=1",
+                DEINTERLACE_FILTER
+            )
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_detect_interlacing_sums_the_samples() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), &[(150, 90, 48), (140, 100, 50)]);
+    let config = DeinterlaceConfig {
+        detect: true,
+        samples: 2,
+        ..Default::default()
+    };
+
+    let counts = detect_interlacing(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/show.mkv"),
+        &probe(),
+        &config,
+    )
+    .await
+    .unwrap();
+    assert_eq!(counts, idet(290, 190, 98, 480));
+    assert_eq!(counts.scan_type(&config), ScanType::Telecined);
+
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    let calls: Vec<&str> = args.lines().collect();
+    assert_eq!(calls.len(), 2);
+    assert!(calls[0].contains("-i /media/show.mkv -lavfi [0:0]idet -f null -"));
+
+    // A failing sample fails the pass
+    let config = DeinterlaceConfig {
+        samples: 1,
+        ..config
+    };
+    assert!(detect_interlacing(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/show.mkv"),
+        &probe(),
+        &config,
+    )
+    .await
+    .is_err());
+}
+
+#[test]
+fn test_probe_reads_the_field_order() {
+    let json = r#"{
+        "streams": [{
+            "index": 0, "codec_type": "video", "codec_name": "mpeg2video",
+            "width": 720, "height": 480, "pix_fmt": "yuv420p", "field_order": "bb"
+        }]
+    }"#;
+    let probe = parse_probe_json(json).unwrap();
+    assert_eq!(probe.video_streams[0].field_order.as_deref(), Some("bb"));
+
+    let json = json.replace("\"bb\"", "\"unknown\"");
+    let probe = parse_probe_json(&json).unwrap();
+    assert_eq!(probe.video_streams[0].field_order, None);
+}
+
+/// Scan type the daemon decides for a cached entry with the default config
+fn cached_scan_type(entry: &ScanCacheEntry) -> ScanType {
+    let field_order = entry
+        .probe
+        .main_video_stream()
+        .and_then(|v| v.field_order.as_deref());
+    InterlaceDecision::new(field_order, None, &DeinterlaceConfig::default()).scan_type
+}
+
+#[test]
+fn test_cached_entries_keep_the_field_order_across_upgrades() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_path = temp_dir.path().join("scan_cache.json");
+    let settings = GateSettings::from_config(&DaemonConfig::default());
+
+    let mut cache = ScanCache::new(settings.clone());
+    let mut entry = |name: &str, field_order: &str| {
+        let path = temp_dir.path().join(name);
+        fs::write(&path, "video").unwrap();
+        let mut probe = probe();
+        probe.video_streams[0].field_order = Some(field_order.to_string());
+        cache.insert(
+            path.clone(),
+            ScanCacheEntry {
+                fingerprint: FileFingerprint::of(&path).unwrap(),
+                gate_settings: settings.clone(),
+                probe_schema: PROBE_SCHEMA_VERSION,
+                classification: classify_source(&path, &probe),
+                probe,
+                gate: GateResult::Pass,
+            },
+        );
+        path
+    };
+    let interlaced = entry("interlaced.mkv", "tt");
+    let progressive = entry("progressive.mkv", "progressive");
+    save_scan_cache(&mut cache, &cache_path).unwrap();
+
+    // A cache of the current format keeps the field order
+    let loaded = load_scan_cache(&cache_path, &settings).unwrap();
+    assert_eq!(
+        cached_scan_type(&loaded.entries[&interlaced]),
+        ScanType::Interlaced
+    );
+    assert_eq!(
+        cached_scan_type(&loaded.entries[&progressive]),
+        ScanType::Progressive
+    );
+
+    // A cache from before field orders were probed would treat every file as
+    // progressive, so it is discarded and the files are probed again
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+    json.as_object_mut().unwrap().remove("format_version");
+    for entry in json["entries"].as_object_mut().unwrap().values_mut() {
+        entry["probe"]["video_streams"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("field_order");
+    }
+    fs::write(&cache_path, json.to_string()).unwrap();
+    assert!(load_scan_cache(&cache_path, &settings)
+        .unwrap()
+        .entries
+        .is_empty());
+}
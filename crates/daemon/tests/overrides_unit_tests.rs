@@ -0,0 +1,184 @@
+use av1d_daemon::config::{DaemonConfig, EncoderPreference, QualityTier};
+use av1d_daemon::encode::{select_crf, select_preset};
+use av1d_daemon::overrides::{EffectiveConfig, OverrideResolver, OVERRIDE_FILE_NAME};
+use av1d_daemon::startup::AvailableEncoder;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn write_overrides(dir: &Path, contents: &str) {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join(OVERRIDE_FILE_NAME), contents).unwrap();
+}
+
+fn resolver_for(root: &Path) -> OverrideResolver {
+    OverrideResolver::new(Arc::new(DaemonConfig {
+        library_roots: vec![root.to_path_buf()],
+        ..Default::default()
+    }))
+}
+
+#[test]
+fn test_deeper_overrides_win_setting_by_setting() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_overrides(root, "quality_tier = \"very_high\"\nmin_bytes = 1000\n");
+    write_overrides(
+        &root.join("Anime"),
+        "prefer_encoder = \"aom\"\nmin_bytes = 2000\n",
+    );
+
+    let mut resolver = resolver_for(root);
+    let file = resolver
+        .resolve(&root.join("Anime/Show/S01E01.mkv"))
+        .unwrap();
+    assert!(!file.skip);
+    assert_eq!(file.config.quality_tier, QualityTier::VeryHigh);
+    assert_eq!(file.config.prefer_encoder, EncoderPreference::Aom);
+    assert_eq!(file.config.min_bytes, 2000);
+    assert_eq!(
+        file.override_files,
+        vec![
+            root.join(OVERRIDE_FILE_NAME),
+            root.join("Anime").join(OVERRIDE_FILE_NAME),
+        ]
+    );
+
+    let effective = EffectiveConfig::new(&file);
+    assert_eq!(effective.min_bytes, 2000);
+    assert_eq!(effective.override_files, file.override_files);
+
+    let file = resolver.resolve(&root.join("Movies/Movie.mkv")).unwrap();
+    assert_eq!(file.config.prefer_encoder, EncoderPreference::Svt);
+    assert_eq!(file.config.min_bytes, 1000);
+}
+
+#[test]
+fn test_skip_applies_below_the_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_overrides(&root.join("Archive"), "skip = true\n");
+    write_overrides(&root.join("Archive/Redo"), "skip = false\n");
+
+    let mut resolver = resolver_for(root);
+    assert!(
+        resolver
+            .resolve(&root.join("Archive/Old/a.mkv"))
+            .unwrap()
+            .skip
+    );
+    assert!(
+        !resolver
+            .resolve(&root.join("Archive/Redo/b.mkv"))
+            .unwrap()
+            .skip
+    );
+    assert!(!resolver.resolve(&root.join("c.mkv")).unwrap().skip);
+}
+
+#[test]
+fn test_nested_table_overrides_win_per_encoder_and_tier() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_overrides(
+        root,
+        r#"
+        [crf_tables.svt]
+        high = [{ min_height = 0, value = 24 }]
+        very_high = [{ min_height = 0, value = 22 }]
+
+        [preset_tables.svt]
+        high = [{ min_height = 0, value = 6 }]
+        "#,
+    );
+    write_overrides(
+        &root.join("Anime"),
+        r#"
+        [crf_tables.svt]
+        high = [{ min_height = 1080, value = 26 }, { min_height = 0, value = 28 }]
+
+        [crf_tables.rav1e]
+        high = [{ min_height = 0, value = 120 }]
+        "#,
+    );
+
+    let mut resolver = OverrideResolver::new(Arc::new(DaemonConfig {
+        library_roots: vec![root.to_path_buf()],
+        quality_tier: QualityTier::High,
+        ..Default::default()
+    }));
+    let file = resolver
+        .resolve(&root.join("Anime/Show/S01E01.mkv"))
+        .unwrap();
+    let config = file.config.as_ref();
+    assert_eq!(select_crf(config, AvailableEncoder::SvtAv1, 1080, None), 26);
+    assert_eq!(select_crf(config, AvailableEncoder::SvtAv1, 720, None), 28);
+    assert_eq!(
+        select_crf(config, AvailableEncoder::Librav1e, 720, None),
+        120
+    );
+    // Tables the deeper file leaves alone are inherited from the parent
+    assert_eq!(
+        select_preset(config, AvailableEncoder::SvtAv1, 1080),
+        Some(6)
+    );
+    let very_high = DaemonConfig {
+        quality_tier: QualityTier::VeryHigh,
+        ..config.clone()
+    };
+    assert_eq!(
+        select_crf(&very_high, AvailableEncoder::SvtAv1, 1080, None),
+        22
+    );
+    // ...and the built-in tables where no file sets them
+    assert_eq!(
+        select_crf(config, AvailableEncoder::LibaomAv1, 1080, None),
+        20
+    );
+
+    let effective = EffectiveConfig::new(&file);
+    assert_eq!(effective.crf_tables, config.crf_tables);
+    assert_eq!(effective.preset_tables, config.preset_tables);
+
+    let file = resolver.resolve(&root.join("Movies/Movie.mkv")).unwrap();
+    assert_eq!(
+        select_crf(&file.config, AvailableEncoder::SvtAv1, 1080, None),
+        24
+    );
+}
+
+#[test]
+fn test_files_without_overrides_share_the_base_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let base = Arc::new(DaemonConfig {
+        library_roots: vec![root.to_path_buf()],
+        ..Default::default()
+    });
+
+    let mut resolver = OverrideResolver::new(base.clone());
+    let file = resolver.resolve(&root.join("Show/S01E01.mkv")).unwrap();
+    assert!(Arc::ptr_eq(&file.config, &base));
+    assert!(file.override_files.is_empty());
+}
+
+#[test]
+fn test_invalid_override_files_are_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_overrides(&root.join("Typo"), "qualty_tier = \"high\"\n");
+    write_overrides(&root.join("Ratio"), "max_size_ratio = 1.5\n");
+    write_overrides(
+        &root.join("Crf"),
+        "[crf_tables.svt]\nhigh = [{ min_height = 0, value = 70 }]\n",
+    );
+
+    let mut resolver = resolver_for(root);
+    let err = resolver.resolve(&root.join("Typo/a.mkv")).unwrap_err();
+    assert!(format!("{:#}", err).contains(OVERRIDE_FILE_NAME));
+    let err = resolver.resolve(&root.join("Ratio/b.mkv")).unwrap_err();
+    assert!(format!("{:#}", err).contains("max_size_ratio"));
+    let err = resolver.resolve(&root.join("Crf/c.mkv")).unwrap_err();
+    assert!(format!("{:#}", err).contains("crf_tables.svt.high"));
+}
@@ -0,0 +1,248 @@
+use av1d_daemon::audio::plan_audio;
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::config::{AudioPolicy, SavingsPredictionConfig, TrackPolicy};
+use av1d_daemon::jobs::{create_job, Job};
+use av1d_daemon::predict::{extrapolate, predict_savings, SavingsPrediction};
+use av1d_daemon::probe::{AudioStream, FormatInfo, ProbeResult, VideoStream};
+use av1d_daemon::scan::CandidateFile;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn probe(size: u64, duration: f64, video_bitrate: Option<u64>) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(duration),
+            size,
+            bitrate: None,
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            bitrate: video_bitrate,
+            frame_rate: Some("24/1".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+            bit_depth: Some(8),
+            is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+fn job(probe: &ProbeResult) -> Job {
+    let candidate = CandidateFile {
+        path: PathBuf::from("/media/movie.mkv"),
+        size_bytes: probe.format.size,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::DiscLike,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    create_job(candidate, probe.clone(), classification)
+}
+
+/// Stand-in for ffmpeg that writes 1000 bytes to its output, or fails
+fn write_fake_ffmpeg(dir: &Path, succeed: bool) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    let body = if succeed {
+        "head -c 1000 /dev/zero > \"$out\""
+    } else {
+        "echo 'Invalid data found when processing input' >&2; echo partial > \"$out\"; exit 1"
+    };
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\necho \"$@\" >> \"{}\"\nfor out; do :; done\n{}\n",
+            dir.join("calls").display(),
+            body
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+fn video_args() -> Vec<String> {
+    ["-c:v", "libsvtav1", "-crf", "23"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+#[test]
+fn test_extrapolate_adds_back_non_video_bytes() {
+    // 1000s at 72 Mbit/s is 9 GB of video in a 10 GB file
+    let probe = probe(10_000_000_000, 1000.0, Some(72_000_000));
+    let prediction = extrapolate(&probe, &[], &[(10.0, 5_000_000), (10.0, 5_000_000)]).unwrap();
+
+    assert_eq!(
+        prediction,
+        SavingsPrediction {
+            samples: 2,
+            sampled_secs: 20.0,
+            sample_bytes: 10_000_000,
+            other_bytes: 1_000_000_000,
+            predicted_bytes: 1_500_000_000,
+            predicted_ratio: 0.15,
+        }
+    );
+}
+
+#[test]
+fn test_extrapolate_without_video_bitrate() {
+    let probe = probe(10_000_000_000, 1000.0, None);
+    let prediction = extrapolate(&probe, &[], &[(20.0, 180_000_000)]).unwrap();
+
+    assert_eq!(prediction.other_bytes, 0);
+    assert_eq!(prediction.predicted_bytes, 9_000_000_000);
+    assert_eq!(prediction.predicted_ratio, 0.9);
+
+    assert!(extrapolate(&probe, &[], &[]).is_err());
+}
+
+#[test]
+fn test_extrapolate_counts_audio_as_planned() {
+    // 7 GB of video, 2 GB of TrueHD and 1 GB of everything else
+    let mut probe = probe(10_000_000_000, 1000.0, Some(56_000_000));
+    probe.audio_streams.push(AudioStream {
+        index: 1,
+        codec_name: "truehd".to_string(),
+        language: Some("eng".to_string()),
+        title: None,
+        is_default: true,
+        is_commentary: false,
+        channels: Some(8),
+        channel_layout: Some("7.1".to_string()),
+        bitrate: Some(16_000_000),
+    });
+    let samples = [(20.0, 100_000_000)];
+
+    // Copying the TrueHD track predicts 80%, over a 75% gate
+    let copied = plan_audio(&probe, &TrackPolicy::default(), &AudioPolicy::default());
+    let prediction = extrapolate(&probe, &copied, &samples).unwrap();
+    assert_eq!(prediction.other_bytes, 3_000_000_000);
+    assert_eq!(prediction.predicted_ratio, 0.8);
+    assert!(prediction.gate_failure(0.75, 1.0).is_some());
+
+    // Transcoded to Opus it shrinks to its target bitrate and the gate passes
+    let policy = AudioPolicy {
+        enabled: true,
+        ..Default::default()
+    };
+    let transcoded = plan_audio(&probe, &TrackPolicy::default(), &policy);
+    let opus_kbps = transcoded[0].transcode.unwrap().bitrate_kbps as u64;
+    let prediction = extrapolate(&probe, &transcoded, &samples).unwrap();
+    assert_eq!(
+        prediction.other_bytes,
+        1_000_000_000 + opus_kbps * 1000 * 1000 / 8
+    );
+    assert!(
+        prediction.predicted_ratio < 0.75,
+        "{}",
+        prediction.predicted_ratio
+    );
+    assert_eq!(prediction.gate_failure(0.75, 1.0), None);
+
+    // A dropped track is not counted at all
+    let prediction = extrapolate(&probe, &[], &samples).unwrap();
+    assert_eq!(prediction.other_bytes, 1_000_000_000);
+}
+
+#[test]
+fn test_gate_failure_applies_margin() {
+    let probe = probe(10_000_000_000, 1000.0, None);
+    let prediction = extrapolate(&probe, &[], &[(20.0, 184_000_000)]).unwrap();
+    assert_eq!(prediction.predicted_ratio, 0.92);
+
+    // 0.92 is over the 0.90 gate but within a 5% margin of it
+    assert_eq!(prediction.gate_failure(0.90, 1.05), None);
+
+    let reason = prediction.gate_failure(0.90, 1.0).unwrap();
+    assert!(
+        reason.starts_with("Size gate predicted to fail"),
+        "{}",
+        reason
+    );
+    assert!(reason.contains("projecting 9200000000 bytes (92.0% of original"));
+    assert!(reason.contains("limit is 90.0%"));
+}
+
+#[tokio::test]
+async fn test_predict_savings_encodes_samples() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), true);
+    let work_dir = temp_dir.path().join("work");
+    fs::create_dir_all(&work_dir).unwrap();
+    let probe = probe(1_000_000, 100.0, None);
+    let config = SavingsPredictionConfig {
+        enabled: true,
+        samples: 2,
+        sample_secs: 10,
+        margin: 1.0,
+    };
+
+    let prediction = predict_savings(
+        ffmpeg.to_str().unwrap(),
+        &job(&probe),
+        &probe,
+        &[],
+        &video_args(),
+        &config,
+        &work_dir,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(prediction.samples, 2);
+    assert_eq!(prediction.sampled_secs, 20.0);
+    assert_eq!(prediction.sample_bytes, 2000);
+    assert_eq!(prediction.predicted_bytes, 10_000);
+
+    let calls = fs::read_to_string(temp_dir.path().join("calls")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(calls.len(), 2);
+    assert!(calls[0].contains("-ss 20.000 -t 10.000 -i /media/movie.mkv -map 0:0 -an -sn -dn"));
+    assert!(calls[1].contains("-ss 70.000"));
+    assert!(calls[0].contains("-c:v libsvtav1 -crf 23"));
+
+    // Samples are removed once measured
+    assert_eq!(fs::read_dir(&work_dir).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_predict_savings_reports_failed_sample() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), false);
+    let probe = probe(1_000_000, 100.0, None);
+
+    let result = predict_savings(
+        ffmpeg.to_str().unwrap(),
+        &job(&probe),
+        &probe,
+        &[],
+        &video_args(),
+        &SavingsPredictionConfig::default(),
+        temp_dir.path(),
+    )
+    .await;
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("Invalid data found"), "{}", error);
+    let leftovers = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .filter(|e| e.as_ref().unwrap().path().extension() == Some("mkv".as_ref()))
+        .count();
+    assert_eq!(leftovers, 0);
+}
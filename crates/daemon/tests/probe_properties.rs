@@ -197,6 +197,19 @@ fn parse_test_json(value: serde_json::Value) -> Result<ProbeResult, serde_json::
                 .unwrap_or("")
                 .to_string();
 
+            let title = stream
+                .get("tags")
+                .and_then(|t| t.get("title"))
+                .and_then(|t| t.as_str())
+                .map(|s| s.to_string());
+            let disposition_flag = |name: &str| {
+                stream
+                    .get("disposition")
+                    .and_then(|d| d.get(name))
+                    .and_then(|v| v.as_i64())
+                    == Some(1)
+            };
+
             match codec_type {
                 "video" => {
                     if let (Some(width), Some(height)) = (
@@ -230,6 +243,9 @@ fn parse_test_json(value: serde_json::Value) -> Result<ProbeResult, serde_json::
                                 .and_then(|v| v.as_i64())
                                 .map(|v| v == 1)
                                 .unwrap_or(false),
+                            color: Default::default(),
+                            field_order: None,
+                            frame_count: None,
                         });
                     }
                 }
@@ -242,6 +258,24 @@ fn parse_test_json(value: serde_json::Value) -> Result<ProbeResult, serde_json::
                             .and_then(|t| t.get("language"))
                             .and_then(|l| l.as_str())
                             .map(|s| s.to_string()),
+                        title: title.clone(),
+                        is_default: disposition_flag("default"),
+                        is_commentary: disposition_flag("comment")
+                            || title
+                                .as_deref()
+                                .is_some_and(|t| t.to_lowercase().contains("commentary")),
+                        channels: stream
+                            .get("channels")
+                            .and_then(|c| c.as_u64())
+                            .map(|c| c as u32),
+                        channel_layout: stream
+                            .get("channel_layout")
+                            .and_then(|l| l.as_str())
+                            .map(|s| s.to_string()),
+                        bitrate: stream
+                            .get("bit_rate")
+                            .and_then(|b| b.as_str())
+                            .and_then(|s| s.parse::<u64>().ok()),
                     });
                 }
                 "subtitle" => {
@@ -253,6 +287,12 @@ fn parse_test_json(value: serde_json::Value) -> Result<ProbeResult, serde_json::
                             .and_then(|t| t.get("language"))
                             .and_then(|l| l.as_str())
                             .map(|s| s.to_string()),
+                        title: title.clone(),
+                        is_default: disposition_flag("default"),
+                        is_commentary: disposition_flag("comment")
+                            || title
+                                .as_deref()
+                                .is_some_and(|t| t.to_lowercase().contains("commentary")),
                     });
                 }
                 _ => {}
@@ -265,6 +305,8 @@ fn parse_test_json(value: serde_json::Value) -> Result<ProbeResult, serde_json::
         video_streams,
         audio_streams,
         subtitle_streams,
+        chapters: 0,
+        attachments: vec![],
     })
 }
 
@@ -293,6 +335,9 @@ fn property_main_video_stream_selection() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             });
         }
 
@@ -347,6 +392,9 @@ fn test_single_stream() {
         pix_fmt: Some("yuv420p".to_string()),
         bit_depth: Some(8),
         is_default: false,
+        color: Default::default(),
+        field_order: None,
+        frame_count: None,
     }];
 
     let selected = select_main_video_stream(&streams);
@@ -368,6 +416,9 @@ fn test_default_preference() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
         VideoStream {
             index: 1,
@@ -379,6 +430,9 @@ fn test_default_preference() {
             pix_fmt: Some("yuv420p10le".to_string()),
             bit_depth: Some(10),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
         VideoStream {
             index: 2,
@@ -390,6 +444,9 @@ fn test_default_preference() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
     ];
 
@@ -417,6 +474,9 @@ fn test_first_stream_fallback() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
         VideoStream {
             index: 1,
@@ -428,6 +488,9 @@ fn test_first_stream_fallback() {
             pix_fmt: Some("yuv420p10le".to_string()),
             bit_depth: Some(10),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
     ];
 
@@ -456,6 +519,9 @@ fn test_various_codecs() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }];
 
         let selected = select_main_video_stream(&streams);
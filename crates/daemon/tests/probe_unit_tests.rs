@@ -24,6 +24,9 @@ fn test_single_stream() {
         pix_fmt: Some("yuv420p".to_string()),
         bit_depth: Some(8),
         is_default: false,
+        color: Default::default(),
+        field_order: None,
+        frame_count: None,
     }];
 
     let selected = select_main_video_stream(&streams);
@@ -45,6 +48,9 @@ fn test_default_preference() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
         VideoStream {
             index: 1,
@@ -56,6 +62,9 @@ fn test_default_preference() {
             pix_fmt: Some("yuv420p10le".to_string()),
             bit_depth: Some(10),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
         VideoStream {
             index: 2,
@@ -67,6 +76,9 @@ fn test_default_preference() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
     ];
 
@@ -94,6 +106,9 @@ fn test_first_stream_fallback() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
         VideoStream {
             index: 1,
@@ -105,6 +120,9 @@ fn test_first_stream_fallback() {
             pix_fmt: Some("yuv420p10le".to_string()),
             bit_depth: Some(10),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         },
     ];
 
@@ -133,6 +151,9 @@ fn test_various_codecs() {
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: false,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }];
 
         let selected = select_main_video_stream(&streams);
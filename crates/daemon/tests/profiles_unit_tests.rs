@@ -0,0 +1,234 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::config::{validate_config, DaemonConfig, EncoderPreference};
+use av1d_daemon::encode::{select_crf, select_preset, video_encoder_args, VideoSettings};
+use av1d_daemon::probe::{ColorInfo, FormatInfo, ProbeResult, VideoStream};
+use av1d_daemon::profiles::{
+    select_profile, table_value, EncodingProfile, ProfileRule, ResolutionStep,
+};
+use av1d_daemon::startup::AvailableEncoder;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+fn probe(codec: &str, height: i32, bitrate: u64, transfer: Option<&str>) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(3600.0),
+            size: 8_000_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: codec.to_string(),
+            width: height * 16 / 9,
+            height,
+            bitrate: Some(bitrate),
+            frame_rate: Some("24/1".to_string()),
+            pix_fmt: Some("yuv420p10le".to_string()),
+            bit_depth: Some(10),
+            is_default: true,
+            color: ColorInfo {
+                transfer: transfer.map(String::from),
+                ..Default::default()
+            },
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+fn classification(source_type: SourceType) -> SourceClassification {
+    SourceClassification {
+        source_type,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    }
+}
+
+fn steps(table: &[(i32, u8)]) -> Vec<ResolutionStep> {
+    table
+        .iter()
+        .map(|&(min_height, value)| ResolutionStep { min_height, value })
+        .collect()
+}
+
+fn config_with_rules(rules: Vec<ProfileRule>) -> DaemonConfig {
+    let profiles = ["anime", "hdr", "web"]
+        .into_iter()
+        .map(|name| (name.to_string(), EncodingProfile::default()))
+        .collect::<BTreeMap<_, _>>();
+    DaemonConfig {
+        profiles,
+        profile_rules: rules,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_table_value_uses_the_first_row_at_or_below_the_height() {
+    let table = steps(&[(2160, 20), (1080, 24), (720, 28)]);
+    assert_eq!(table_value(&table, 4320), Some(20));
+    assert_eq!(table_value(&table, 2160), Some(20));
+    assert_eq!(table_value(&table, 1440), Some(24));
+    assert_eq!(table_value(&table, 720), Some(28));
+    // Below the last row
+    assert_eq!(table_value(&table, 480), Some(28));
+    assert_eq!(table_value(&[], 1080), None);
+}
+
+#[test]
+fn test_profile_tables_fall_back_to_the_built_in_values() {
+    let config = DaemonConfig::default();
+    let svt = AvailableEncoder::SvtAv1;
+    let profile = EncodingProfile {
+        crf: steps(&[(1080, 30), (0, 32)]),
+        ..Default::default()
+    };
+    assert_eq!(profile.crf(&config, svt, 1080, None), 30);
+    assert_eq!(profile.crf(&config, svt, 480, None), 32);
+    assert_eq!(
+        profile.preset(&config, svt, 1080),
+        select_preset(&config, svt, 1080)
+    );
+    assert_eq!(
+        EncodingProfile::default().crf(&config, svt, 2160, None),
+        select_crf(&config, svt, 2160, None)
+    );
+}
+
+#[test]
+fn test_first_matching_rule_picks_the_profile() {
+    let config = config_with_rules(vec![
+        ProfileRule {
+            profile: "anime".to_string(),
+            paths: vec!["**/anime/**".to_string()],
+            ..Default::default()
+        },
+        ProfileRule {
+            profile: "hdr".to_string(),
+            hdr: Some(true),
+            min_height: Some(2160),
+            ..Default::default()
+        },
+        ProfileRule {
+            profile: "web".to_string(),
+            source_type: Some(SourceType::WebLike),
+            codecs: vec!["H264".to_string()],
+            max_bitrate: Some(10_000_000),
+            ..Default::default()
+        },
+    ]);
+    let disc = classification(SourceType::DiscLike);
+    let web = classification(SourceType::WebLike);
+    let uhd_hdr = probe("hevc", 2160, 60_000_000, Some("smpte2084"));
+    let web_h264 = probe("h264", 1080, 6_000_000, None);
+
+    let select = |path: &str, probe: &ProbeResult, class: &SourceClassification| {
+        select_profile(&config, Path::new(path), probe, class)
+    };
+    assert_eq!(
+        select("/media/Anime/Show/S01E01.mkv", &uhd_hdr, &disc),
+        Some("anime")
+    );
+    assert_eq!(
+        select("/media/Movies/Movie.mkv", &uhd_hdr, &disc),
+        Some("hdr")
+    );
+    assert_eq!(select("/media/TV/Show.mkv", &web_h264, &web), Some("web"));
+    // Every condition of a rule has to match
+    assert_eq!(select("/media/TV/Show.mkv", &web_h264, &disc), None);
+    let high_bitrate = probe("h264", 1080, 20_000_000, None);
+    assert_eq!(select("/media/TV/Show.mkv", &high_bitrate, &web), None);
+}
+
+#[test]
+fn test_profile_settings_reach_the_encoder_arguments() {
+    let profile = EncodingProfile {
+        encoder: Some(EncoderPreference::Svt),
+        preset: steps(&[(0, 6)]),
+        svt_params: vec!["tune=0".to_string()],
+        film_grain: Some(8),
+        keyint: Some(240),
+        ..Default::default()
+    };
+    let probe = probe("h264", 1080, 6_000_000, None);
+
+    let config = DaemonConfig::default();
+    let svt = VideoSettings::new(&config, AvailableEncoder::SvtAv1, &profile, 1080);
+    let args = video_encoder_args(&svt, &probe, 1080, 24).join(" ");
+    assert!(args.contains("-preset 6"), "{}", args);
+    assert!(
+        args.contains("-svtav1-params lp=0:film-grain=8:tune=0"),
+        "{}",
+        args
+    );
+    assert!(args.contains("-g 240"), "{}", args);
+
+    let aom = VideoSettings::new(&config, AvailableEncoder::LibaomAv1, &profile, 1080);
+    let args = video_encoder_args(&aom, &probe, 1080, 24).join(" ");
+    assert!(args.contains("-denoise-noise-level 8"), "{}", args);
+    assert!(args.contains("-g 240"), "{}", args);
+}
+
+#[test]
+fn test_invalid_profiles_fail_validation() {
+    let config = config_with_rules(vec![ProfileRule {
+        profile: "missing".to_string(),
+        ..Default::default()
+    }]);
+    assert!(validate_config(&config).is_err());
+
+    let mut config = config_with_rules(vec![]);
+    config.profiles.insert(
+        "unordered".to_string(),
+        EncodingProfile {
+            crf: steps(&[(720, 28), (1080, 24)]),
+            ..Default::default()
+        },
+    );
+    assert!(validate_config(&config).is_err());
+
+    let config = config_with_rules(vec![ProfileRule {
+        profile: "anime".to_string(),
+        paths: vec!["[unclosed".to_string()],
+        ..Default::default()
+    }]);
+    assert!(validate_config(&config).is_err());
+}
+
+#[test]
+fn test_rav1e_profiles_use_the_qp_scale() {
+    let rav1e = EncodingProfile {
+        encoder: Some(EncoderPreference::Rav1e),
+        crf: steps(&[(0, 120)]),
+        ..Default::default()
+    };
+    let mut config = config_with_rules(vec![]);
+    config.profiles.insert("rav1e".to_string(), rav1e.clone());
+    validate_config(&config).unwrap();
+
+    // The same table is out of range for the other encoders
+    config.profiles.insert(
+        "svt".to_string(),
+        EncodingProfile {
+            encoder: Some(EncoderPreference::Svt),
+            ..rav1e.clone()
+        },
+    );
+    assert!(validate_config(&config).is_err());
+
+    // When rav1e is unavailable the -qp value falls back to the built-in table
+    let config = DaemonConfig::default();
+    assert_eq!(
+        rav1e.crf(&config, AvailableEncoder::Librav1e, 1080, None),
+        120
+    );
+    assert_eq!(
+        rav1e.crf(&config, AvailableEncoder::SvtAv1, 1080, None),
+        select_crf(&config, AvailableEncoder::SvtAv1, 1080, None)
+    );
+}
@@ -0,0 +1,258 @@
+use av1d_daemon::config::QualityCheckConfig;
+use av1d_daemon::crop::CropRect;
+use av1d_daemon::encode::common::SourceFilters;
+use av1d_daemon::probe::{FormatInfo, ProbeResult, VideoStream};
+use av1d_daemon::quality::{
+    check_quality, measure_quality, parse_sample_scores, sample_offsets, QualityScores,
+    PSNR_IDENTICAL,
+};
+use av1d_daemon::validate::ValidationError;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+const FFMPEG_LOG: &str = "\
+[Parsed_ssim_4 @ 0x55d1] SSIM Y:0.991234 (20.57) U:0.995 (23.0) V:0.994 (22.2) All:0.992817 (21.44)
+[Parsed_psnr_5 @ 0x55d2] PSNR y:43.12 u:47.80 v:47.01 average:44.351234 min:39.87 max:50.12
+[Parsed_libvmaf_6 @ 0x55d3] VMAF score: 94.871234
+";
+
+fn probe(duration: f64) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(duration),
+            size: 1_000_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "hevc".to_string(),
+            width: 1920,
+            height: 1080,
+            bitrate: None,
+            frame_rate: Some("24/1".to_string()),
+            pix_fmt: Some("yuv420p10le".to_string()),
+            bit_depth: Some(10),
+            is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+/// Stand-in for ffmpeg: lists libvmaf for `-filters` when `with_vmaf`, and
+/// reports SSIM/PSNR/VMAF lines, getting worse with each sample
+fn write_fake_ffmpeg(dir: &Path, with_vmaf: bool) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    let counter = dir.join("calls");
+    let vmaf_filter = if with_vmaf {
+        "echo ' ... libvmaf          VV->V      Calculate the VMAF between two video streams.'"
+    } else {
+        "echo ' ... ssim             VV->V      Calculate the SSIM between two video streams.'"
+    };
+    fs::write(
+        &script,
+        format!(
+            r#"#!/bin/sh
+if [ "$2" = "-filters" ]; then
+    {vmaf_filter}
+    exit 0
+fi
+echo "$@" >> "{args}"
+n=$(cat "{counter}" 2>/dev/null || echo 0)
+n=$((n + 1))
+echo $n > "{counter}"
+echo "[Parsed_ssim_4 @ 0x1] SSIM Y:0.99 (20.0) All:0.99$((9 - n)) (20.0)" >&2
+echo "[Parsed_psnr_5 @ 0x2] PSNR y:43.0 average:4$((5 - n)).5 min:40.0 max:50.0" >&2
+echo "[Parsed_libvmaf_6 @ 0x3] VMAF score: 9$((6 - n)).0" >&2
+"#,
+            vmaf_filter = vmaf_filter,
+            args = dir.join("args").display(),
+            counter = counter.display(),
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+#[test]
+fn test_sample_offsets_are_evenly_spaced() {
+    assert_eq!(
+        sample_offsets(100.0, 5, 10.0),
+        vec![5.0, 25.0, 45.0, 65.0, 85.0]
+    );
+    // Samples never run past the end
+    assert_eq!(sample_offsets(20.0, 2, 15.0), vec![0.0, 5.0]);
+    // Short files get a single sample covering what there is
+    assert_eq!(sample_offsets(6.0, 5, 10.0), vec![0.0]);
+}
+
+#[test]
+fn test_parse_sample_scores() {
+    let scores = parse_sample_scores(FFMPEG_LOG, true).unwrap();
+    assert_eq!(scores.ssim, 0.992817);
+    assert_eq!(scores.psnr, 44.351234);
+    assert_eq!(scores.vmaf, Some(94.871234));
+
+    // VMAF is ignored unless requested, and required when it is
+    assert_eq!(parse_sample_scores(FFMPEG_LOG, false).unwrap().vmaf, None);
+    let without_vmaf = FFMPEG_LOG.lines().take(2).collect::<Vec<_>>().join("\n");
+    assert!(parse_sample_scores(&without_vmaf, true).is_none());
+    assert!(parse_sample_scores("Conversion failed!", false).is_none());
+}
+
+#[test]
+fn test_identical_samples_report_capped_psnr() {
+    let log = "SSIM Y:1.000000 (inf) All:1.000000 (inf)\nPSNR y:inf u:inf v:inf average:inf min:inf max:inf";
+    let scores = parse_sample_scores(log, false).unwrap();
+    assert_eq!(scores.ssim, 1.0);
+    assert_eq!(scores.psnr, PSNR_IDENTICAL);
+}
+
+#[test]
+fn test_check_quality_thresholds() {
+    let config = QualityCheckConfig::default();
+    let mut scores = QualityScores {
+        ssim: 0.98,
+        psnr: 42.0,
+        vmaf: Some(93.0),
+        samples: 5,
+    };
+    assert_eq!(check_quality(&scores, &config), None);
+
+    scores.psnr = 30.0;
+    assert_eq!(
+        check_quality(&scores, &config),
+        Some(ValidationError::QualityBelowThreshold {
+            metric: "PSNR".to_string(),
+            score: 30.0,
+            threshold: 35.0,
+        })
+    );
+
+    // A zero threshold disables the metric
+    let lenient = QualityCheckConfig {
+        min_psnr: 0.0,
+        ..config.clone()
+    };
+    assert_eq!(check_quality(&scores, &lenient), None);
+
+    scores.vmaf = Some(70.0);
+    assert!(matches!(
+        check_quality(&scores, &lenient),
+        Some(ValidationError::QualityBelowThreshold { metric, .. }) if metric == "VMAF"
+    ));
+}
+
+#[tokio::test]
+async fn test_measure_quality_keeps_worst_sample() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), true);
+    let config = QualityCheckConfig {
+        enabled: true,
+        samples: 3,
+        ..Default::default()
+    };
+
+    let scores = measure_quality(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/source.mkv"),
+        Path::new("/tmp/output.mkv"),
+        &probe(600.0),
+        &SourceFilters::default(),
+        &config,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        scores,
+        QualityScores {
+            ssim: 0.996,
+            psnr: 42.5,
+            vmaf: Some(93.0),
+            samples: 3,
+        }
+    );
+
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    let calls: Vec<&str> = args.lines().collect();
+    assert_eq!(calls.len(), 3);
+    assert!(calls[0].contains("-ss 95.000 -t 10.000 -i /tmp/output.mkv"));
+    assert!(calls[0].contains("-i /media/source.mkv"));
+    assert!(calls[0].contains("scale=1920:1080"));
+    assert!(calls[0].contains("format=yuv420p10le"));
+    assert!(calls[0].contains("[d2][r2]libvmaf"));
+}
+
+#[tokio::test]
+async fn test_measure_quality_without_libvmaf() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), false);
+    let config = QualityCheckConfig {
+        enabled: true,
+        samples: 1,
+        ..Default::default()
+    };
+
+    let scores = measure_quality(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/source.mkv"),
+        Path::new("/tmp/output.mkv"),
+        &probe(600.0),
+        &SourceFilters::default(),
+        &config,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(scores.vmaf, None);
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    assert!(!args.contains("libvmaf"));
+}
+
+#[tokio::test]
+async fn test_measure_quality_crops_the_source_like_the_encode() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), false);
+    let config = QualityCheckConfig {
+        enabled: true,
+        samples: 1,
+        ..Default::default()
+    };
+    let crop = CropRect {
+        width: 1920,
+        height: 800,
+        x: 0,
+        y: 140,
+    };
+
+    measure_quality(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/source.mkv"),
+        Path::new("/tmp/output.mkv"),
+        &probe(600.0),
+        &SourceFilters {
+            crop: Some(crop),
+            ..Default::default()
+        },
+        &config,
+    )
+    .await
+    .unwrap();
+
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    assert!(args.contains("[0:v:0]scale=1920:800"), "{}", args);
+    assert!(
+        args.contains("[1:0]crop=1920:800:0:140,format="),
+        "{}",
+        args
+    );
+}
@@ -1,4 +1,4 @@
-use av1d_daemon::replace::atomic_replace;
+use av1d_daemon::replace::{atomic_replace, atomic_replace_to, move_sibling_files};
 use proptest::prelude::*;
 
 use std::fs;
@@ -337,3 +337,98 @@ fn test_special_characters_in_filename() {
     let final_content = fs::read(&original_path).unwrap();
     assert_eq!(final_content, b"new");
 }
+
+/// Test replacement into a renamed target when the container changes
+#[test]
+fn test_replace_to_renamed_target() {
+    let temp_dir = TempDir::new().unwrap();
+    let original_path = temp_dir.path().join("video.mp4");
+    let target_path = temp_dir.path().join("video.mkv");
+    let new_path = temp_dir.path().join("encoded.mkv");
+
+    fs::write(&original_path, b"original content").unwrap();
+    fs::write(&new_path, b"new content").unwrap();
+
+    atomic_replace_to(&original_path, &new_path, &target_path, false).unwrap();
+
+    assert!(!original_path.exists());
+    assert!(!new_path.exists());
+    assert_eq!(fs::read(&target_path).unwrap(), b"new content");
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+}
+
+/// Test that a renamed target never overwrites an existing file
+#[test]
+fn test_replace_to_existing_target_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let original_path = temp_dir.path().join("video.mp4");
+    let target_path = temp_dir.path().join("video.mkv");
+    let new_path = temp_dir.path().join("encoded.mkv");
+
+    fs::write(&original_path, b"original content").unwrap();
+    fs::write(&target_path, b"unrelated").unwrap();
+    fs::write(&new_path, b"new content").unwrap();
+
+    assert!(atomic_replace_to(&original_path, &new_path, &target_path, false).is_err());
+    assert_eq!(fs::read(&original_path).unwrap(), b"original content");
+    assert_eq!(fs::read(&target_path).unwrap(), b"unrelated");
+    assert!(new_path.exists());
+}
+
+/// Test that sidecars named after the full file name follow a rename
+#[test]
+fn test_move_sibling_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let old_video = dir.join("Movie (2020).mp4");
+    let new_video = dir.join("Movie (2020).mkv");
+    fs::write(&new_video, b"video").unwrap();
+
+    for name in [
+        "Movie (2020).mp4.srt",
+        "Movie (2020).en.srt",
+        "Movie (2020).nfo",
+        "Movie (2020)-poster.jpg",
+        "Movie (2020) Extras.nfo",
+        "Other.srt",
+    ] {
+        fs::write(dir.join(name), name).unwrap();
+    }
+
+    let moved = move_sibling_files(&old_video, &new_video).unwrap();
+
+    // Stem-named siblings still match the new file; only full-name ones move
+    assert_eq!(moved.len(), 1);
+    assert!(dir.join("Movie (2020).mkv.srt").exists());
+    assert!(!dir.join("Movie (2020).mp4.srt").exists());
+    for name in [
+        "Movie (2020).en.srt",
+        "Movie (2020).nfo",
+        "Movie (2020)-poster.jpg",
+        "Movie (2020) Extras.nfo",
+        "Other.srt",
+    ] {
+        assert!(dir.join(name).exists(), "{} should be untouched", name);
+    }
+}
+
+/// Test that stem-named siblings follow when the stem itself changes
+#[test]
+fn test_move_sibling_files_with_new_stem() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let old_video = dir.join("movie.m2ts");
+    let new_video = dir.join("movie-av1.mkv");
+    fs::write(&new_video, b"video").unwrap();
+    fs::write(dir.join("movie.en.srt"), b"subs").unwrap();
+    fs::write(dir.join("movie-poster.jpg"), b"poster").unwrap();
+    fs::write(dir.join("movie.txt"), b"notes").unwrap();
+
+    let moved = move_sibling_files(&old_video, &new_video).unwrap();
+
+    assert_eq!(moved.len(), 2);
+    assert!(dir.join("movie-av1.en.srt").exists());
+    assert!(dir.join("movie-av1-poster.jpg").exists());
+    // Not a known sidecar extension
+    assert!(dir.join("movie.txt").exists());
+}
@@ -0,0 +1,143 @@
+use av1d_daemon::config::DaemonConfig;
+use av1d_daemon::encode::FfmpegFailed;
+use av1d_daemon::retry::{
+    backoff_secs, classify_failure, load_ledger, update_ledger, FailureKind, RetryDecision,
+    RetryLedger,
+};
+use chrono::{Duration, TimeZone, Utc};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn test_config(temp_dir: &TempDir) -> DaemonConfig {
+    DaemonConfig {
+        job_state_dir: temp_dir.path().join("jobs"),
+        max_attempts: 3,
+        retry_base_delay_secs: 60,
+        retry_max_delay_secs: 200,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_backoff_doubles_and_is_capped() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+
+    assert_eq!(backoff_secs(1, &config), 60);
+    assert_eq!(backoff_secs(2, &config), 120);
+    assert_eq!(backoff_secs(3, &config), 200);
+    assert_eq!(backoff_secs(100, &config), 200);
+}
+
+#[test]
+fn test_transient_failures_retry_until_max_attempts() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let path = Path::new("/media/movie.mkv");
+    let now = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+    let mut ledger = RetryLedger::default();
+
+    let decision = ledger.record_failure(path, FailureKind::Transient, "killed", now, &config);
+    assert_eq!(
+        decision,
+        RetryDecision::RetryAfter(now + Duration::seconds(60))
+    );
+    assert!(ledger.backoff_until(path, now).is_some());
+    assert!(ledger
+        .backoff_until(path, now + Duration::seconds(61))
+        .is_none());
+
+    let decision = ledger.record_failure(path, FailureKind::Transient, "killed", now, &config);
+    assert_eq!(
+        decision,
+        RetryDecision::RetryAfter(now + Duration::seconds(120))
+    );
+
+    let decision = ledger.record_failure(
+        path,
+        FailureKind::Transient,
+        "Encoding failed: FFmpeg failed\nStderr:\nframe=1\nNo space left on device",
+        now,
+        &config,
+    );
+    let RetryDecision::GiveUp { summary } = decision else {
+        panic!("expected to give up, got {:?}", decision);
+    };
+    assert!(summary.starts_with("Giving up after 3 failed attempts"));
+    assert_eq!(summary.matches("[transient]").count(), 3);
+    assert!(summary.contains("  3. 2025-01-01 12:00:00 UTC [transient] Encoding failed: FFmpeg failed ... No space left on device"));
+    assert!(ledger.backoff_until(path, now).is_none());
+}
+
+#[test]
+fn test_permanent_failure_gives_up_immediately() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let path = Path::new("/media/movie.mkv");
+    let mut ledger = RetryLedger::default();
+
+    let decision = ledger.record_failure(
+        path,
+        FailureKind::Permanent,
+        "Validation failed: NoAv1Stream",
+        Utc::now(),
+        &config,
+    );
+    let RetryDecision::GiveUp { summary } = decision else {
+        panic!("expected to give up, got {:?}", decision);
+    };
+    assert!(summary.contains("permanent failure on attempt 1"));
+    assert!(summary.contains("[permanent] Validation failed: NoAv1Stream"));
+}
+
+#[test]
+fn test_classify_failure() {
+    let killed: anyhow::Error = FfmpegFailed {
+        exit_code: None,
+        stderr: String::new(),
+    }
+    .into();
+    assert_eq!(classify_failure(&killed), FailureKind::Transient);
+
+    let disk_full: anyhow::Error = FfmpegFailed {
+        exit_code: Some(1),
+        stderr: "av_interleaved_write_frame(): No space left on device".to_string(),
+    }
+    .into();
+    assert_eq!(classify_failure(&disk_full), FailureKind::Transient);
+
+    let bad_stream: anyhow::Error = FfmpegFailed {
+        exit_code: Some(1),
+        stderr: "Invalid data found when processing input".to_string(),
+    }
+    .into();
+    assert_eq!(classify_failure(&bad_stream), FailureKind::Permanent);
+
+    let io = anyhow::Error::from(std::io::Error::other("disk went away")).context("Replacing");
+    assert_eq!(classify_failure(&io), FailureKind::Transient);
+
+    assert_eq!(
+        classify_failure(&anyhow::anyhow!("Command has no output path")),
+        FailureKind::Permanent
+    );
+}
+
+#[test]
+fn test_ledger_persists_and_clears() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = test_config(&temp_dir);
+    let path = Path::new("/media/movie.mkv");
+
+    update_ledger(&config, |ledger| {
+        ledger.record_failure(path, FailureKind::Transient, "killed", Utc::now(), &config)
+    })
+    .unwrap();
+
+    let ledger_path = config.retry_ledger_path();
+    assert_eq!(ledger_path, temp_dir.path().join("retry_ledger.json"));
+    let ledger = load_ledger(&ledger_path).unwrap();
+    assert_eq!(ledger.entry(path).unwrap().attempts.len(), 1);
+
+    assert!(update_ledger(&config, |ledger| ledger.clear(path)).unwrap());
+    assert!(load_ledger(&ledger_path).unwrap().entry(path).is_none());
+}
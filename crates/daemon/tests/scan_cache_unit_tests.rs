@@ -0,0 +1,183 @@
+use av1d_daemon::classify::classify_source;
+use av1d_daemon::config::DaemonConfig;
+use av1d_daemon::gates::{GateResult, SkipReason};
+use av1d_daemon::probe::{FormatInfo, ProbeResult, PROBE_SCHEMA_VERSION};
+use av1d_daemon::scan_cache::{
+    load_scan_cache, save_scan_cache, FileFingerprint, GateSettings, ScanCache, ScanCacheEntry,
+    CACHE_FORMAT_VERSION,
+};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn entry_for(path: &Path) -> ScanCacheEntry {
+    let probe = ProbeResult {
+        format: FormatInfo {
+            duration: Some(60.0),
+            size: 5,
+            bitrate: None,
+        },
+        video_streams: vec![],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    };
+    ScanCacheEntry {
+        fingerprint: FileFingerprint::of(path).unwrap(),
+        gate_settings: GateSettings::from_config(&DaemonConfig::default()),
+        probe_schema: PROBE_SCHEMA_VERSION,
+        classification: classify_source(path, &probe),
+        probe,
+        gate: GateResult::Skip(SkipReason::NoVideo),
+    }
+}
+
+#[test]
+fn test_lookup_misses_once_the_file_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("movie.mkv");
+    fs::write(&path, "video").unwrap();
+
+    let settings = GateSettings::from_config(&DaemonConfig::default());
+    let mut cache = ScanCache::new(settings.clone());
+    cache.insert(path.clone(), entry_for(&path));
+    let fingerprint = FileFingerprint::of(&path).unwrap();
+    assert_eq!(
+        cache.lookup(&path, &fingerprint, &settings),
+        Some(&entry_for(&path))
+    );
+
+    // Gated under a directory override with other settings
+    let overridden = GateSettings { min_bytes: 1 };
+    assert_eq!(cache.lookup(&path, &fingerprint, &overridden), None);
+
+    // Same size, but replaced by a new file
+    let replacement = temp_dir.path().join("movie.mkv.tmp");
+    fs::write(&replacement, "VIDEO").unwrap();
+    fs::rename(&replacement, &path).unwrap();
+    let fingerprint = FileFingerprint::of(&path).unwrap();
+    assert_eq!(cache.lookup(&path, &fingerprint, &settings), None);
+}
+
+#[test]
+fn test_cache_round_trips_and_prunes_missing_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_path = temp_dir.path().join("scan_cache.json");
+    let kept = temp_dir.path().join("kept.mkv");
+    let gone = temp_dir.path().join("gone.mkv");
+    fs::write(&kept, "video").unwrap();
+    fs::write(&gone, "video").unwrap();
+
+    let settings = GateSettings::from_config(&DaemonConfig::default());
+    let mut cache = load_scan_cache(&cache_path, &settings).unwrap();
+    assert!(cache.entries.is_empty());
+    assert!(!cache.is_dirty());
+
+    cache.insert(kept.clone(), entry_for(&kept));
+    cache.insert(gone.clone(), entry_for(&gone));
+    cache.retain_paths([kept.as_path()]);
+    assert!(cache.is_dirty());
+    save_scan_cache(&mut cache, &cache_path).unwrap();
+    assert!(!cache.is_dirty());
+
+    let loaded = load_scan_cache(&cache_path, &settings).unwrap();
+    assert_eq!(loaded.entries.len(), 1);
+    assert_eq!(loaded.entries.get(&kept), Some(&entry_for(&kept)));
+}
+
+#[test]
+fn test_changed_gate_settings_discard_the_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_path = temp_dir.path().join("scan_cache.json");
+    let path = temp_dir.path().join("movie.mkv");
+    fs::write(&path, "video").unwrap();
+
+    let config = DaemonConfig::default();
+    let mut cache = ScanCache::new(GateSettings::from_config(&config));
+    cache.insert(path.clone(), entry_for(&path));
+    save_scan_cache(&mut cache, &cache_path).unwrap();
+
+    let unchanged = load_scan_cache(&cache_path, &GateSettings::from_config(&config)).unwrap();
+    assert_eq!(unchanged.entries.len(), 1);
+
+    let config = DaemonConfig {
+        min_bytes: config.min_bytes / 2,
+        ..config
+    };
+    let changed = load_scan_cache(&cache_path, &GateSettings::from_config(&config)).unwrap();
+    assert!(changed.entries.is_empty());
+}
+
+#[test]
+fn test_caches_from_other_versions_are_discarded() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_path = temp_dir.path().join("scan_cache.json");
+    let path = temp_dir.path().join("movie.mkv");
+    fs::write(&path, "video").unwrap();
+
+    let settings = GateSettings::from_config(&DaemonConfig::default());
+    let mut cache = ScanCache::new(settings.clone());
+    cache.insert(path.clone(), entry_for(&path));
+    save_scan_cache(&mut cache, &cache_path).unwrap();
+    assert_eq!(
+        load_scan_cache(&cache_path, &settings)
+            .unwrap()
+            .format_version,
+        CACHE_FORMAT_VERSION
+    );
+
+    // A cache written before the version was recorded, whose probe results
+    // lack the fields added since
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+    json.as_object_mut().unwrap().remove("format_version");
+    let entry = json["entries"][path.to_str().unwrap()]["probe"]
+        .as_object_mut()
+        .unwrap();
+    entry.remove("chapters");
+    entry.remove("attachments");
+    fs::write(&cache_path, json.to_string()).unwrap();
+
+    let loaded = load_scan_cache(&cache_path, &settings).unwrap();
+    assert!(loaded.entries.is_empty());
+    assert_eq!(loaded.format_version, CACHE_FORMAT_VERSION);
+}
+
+#[test]
+fn test_entries_probed_with_an_older_schema_are_discarded() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_path = temp_dir.path().join("scan_cache.json");
+    let old = temp_dir.path().join("old.mkv");
+    let new = temp_dir.path().join("new.mkv");
+    fs::write(&old, "video").unwrap();
+    fs::write(&new, "video").unwrap();
+
+    let settings = GateSettings::from_config(&DaemonConfig::default());
+    let mut cache = ScanCache::new(settings.clone());
+    for path in [&old, &new] {
+        let mut entry = entry_for(path);
+        entry.probe.chapters = 12;
+        cache.insert(path.clone(), entry);
+    }
+    save_scan_cache(&mut cache, &cache_path).unwrap();
+
+    // An entry written before chapters were probed: without the schema check it
+    // would load with `chapters: 0` and fail validation of every output
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+    let entry = json["entries"][old.to_str().unwrap()]
+        .as_object_mut()
+        .unwrap();
+    entry.remove("probe_schema");
+    entry["probe"].as_object_mut().unwrap().remove("chapters");
+    fs::write(&cache_path, json.to_string()).unwrap();
+
+    let loaded = load_scan_cache(&cache_path, &settings).unwrap();
+    assert_eq!(loaded.entries.len(), 1);
+    assert!(!loaded.entries.contains_key(&old));
+    assert_eq!(loaded.entries[&new].probe.chapters, 12);
+    assert_eq!(loaded.entries[&new].probe_schema, PROBE_SCHEMA_VERSION);
+    // Saved again without the stale entry
+    assert!(loaded.is_dirty());
+}
@@ -0,0 +1,153 @@
+use av1d_daemon::config::{validate_config, DaemonConfig, ScanRules};
+use av1d_daemon::scan::scan_libraries_with;
+use av1d_daemon::scan_filter::ScanFilter;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn touch(root: &Path, relative: &str) {
+    let path = root.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, "video").unwrap();
+}
+
+fn scanned(root: &Path, config: &DaemonConfig) -> Vec<PathBuf> {
+    let filter = ScanFilter::new(config).unwrap();
+    let mut paths: Vec<PathBuf> = scan_libraries_with(&[root.to_path_buf()], &filter)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.path.strip_prefix(root).unwrap().to_path_buf())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_exclude_globs_and_regexes() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    touch(root, "Movie (2020)/Movie.mkv");
+    touch(root, "Movie (2020)/Extras/Interview.mkv");
+    touch(root, "Movie (2020)/Movie-Trailer.mkv");
+    touch(root, "Movie (2020)/sample/movie-sample.mkv");
+
+    let config = DaemonConfig {
+        scan_rules: vec![ScanRules {
+            exclude: vec!["**/Extras/**".to_string(), "*trailer*".to_string()],
+            exclude_regex: vec!["(^|/)sample/".to_string()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert_eq!(
+        scanned(root, &config),
+        vec![PathBuf::from("Movie (2020)/Movie.mkv")]
+    );
+}
+
+#[test]
+fn test_include_rules_only_apply_to_their_root() {
+    let temp_dir = TempDir::new().unwrap();
+    let tv_root = temp_dir.path().join("tv");
+    let other_root = temp_dir.path().join("other");
+    touch(&tv_root, "TV/Show/S01E01.mkv");
+    touch(&tv_root, "Movies/Movie.mkv");
+    touch(&other_root, "Movies/Movie.mkv");
+
+    let config = DaemonConfig {
+        scan_rules: vec![ScanRules {
+            root: Some(tv_root.clone()),
+            include: vec!["TV/**".to_string()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert_eq!(
+        scanned(&tv_root, &config),
+        vec![PathBuf::from("TV/Show/S01E01.mkv")]
+    );
+    assert_eq!(
+        scanned(&other_root, &config),
+        vec![PathBuf::from("Movies/Movie.mkv")]
+    );
+}
+
+#[test]
+fn test_max_depth_and_extensions() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    touch(root, "top.MKV");
+    touch(root, "top.webm");
+    touch(root, "Show/episode.mkv");
+    touch(root, "Show/Season 1/episode.mkv");
+
+    let config = DaemonConfig {
+        video_extensions: vec!["mkv".to_string(), ".webm".to_string()],
+        scan_rules: vec![ScanRules {
+            max_depth: Some(2),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert_eq!(
+        scanned(root, &config),
+        vec![
+            PathBuf::from("Show/episode.mkv"),
+            PathBuf::from("top.MKV"),
+            PathBuf::from("top.webm"),
+        ]
+    );
+}
+
+#[test]
+fn test_exclusion_names_the_matching_rule() {
+    let config = DaemonConfig {
+        scan_rules: vec![ScanRules {
+            include: vec!["TV/**".to_string()],
+            exclude: vec!["**/Extras/**".to_string()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let filter = ScanFilter::new(&config).unwrap();
+    let root = Path::new("/media");
+
+    assert_eq!(
+        filter.exclusion(root, Path::new("/media/TV/Show/Extras"), true),
+        Some("exclude \"**/Extras/**\"".to_string())
+    );
+    assert_eq!(
+        filter.exclusion(root, Path::new("/media/Movies/Movie.mkv"), false),
+        Some("no include pattern matched".to_string())
+    );
+    // Directories outside the includes are still walked
+    assert_eq!(
+        filter.exclusion(root, Path::new("/media/Movies"), true),
+        None
+    );
+    assert_eq!(
+        filter.exclusion(root, Path::new("/media/TV/Show/S01E01.mkv"), false),
+        None
+    );
+}
+
+#[test]
+fn test_invalid_patterns_fail_validation() {
+    let config = DaemonConfig {
+        scan_rules: vec![ScanRules {
+            exclude_regex: vec!["(unclosed".to_string()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert!(validate_config(&config).is_err());
+
+    let config = DaemonConfig {
+        scan_rules: vec![ScanRules {
+            exclude: vec!["[unclosed".to_string()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert!(validate_config(&config).is_err());
+}
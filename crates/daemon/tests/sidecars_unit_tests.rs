@@ -1,4 +1,6 @@
-use av1d_daemon::sidecars::{create_skip_marker, has_skip_marker, write_why_file};
+use av1d_daemon::sidecars::{
+    create_skip_marker, has_skip_marker, remove_skip_marker, write_why_file,
+};
 use std::fs;
 use tempfile::TempDir;
 
@@ -149,3 +151,21 @@ fn test_overwrite_why_file() {
     let content2 = fs::read_to_string(&why_file_path).unwrap();
     assert_eq!(content2, reason2);
 }
+
+#[test]
+fn test_remove_skip_marker_clears_both_sidecars() {
+    let temp_dir = TempDir::new().unwrap();
+    let video_path = temp_dir.path().join("test_video.mkv");
+    fs::write(&video_path, "dummy content").unwrap();
+
+    create_skip_marker(&video_path).unwrap();
+    write_why_file(&video_path, "Skipped by user").unwrap();
+
+    remove_skip_marker(&video_path).unwrap();
+
+    assert!(!has_skip_marker(&video_path));
+    assert!(!video_path.with_extension("mkv.why.txt").exists());
+
+    // Removing again is a no-op
+    remove_skip_marker(&video_path).unwrap();
+}
@@ -1,4 +1,5 @@
-use av1d_daemon::size_gate::{check_size_gate, SizeGateResult};
+use av1d_daemon::config::DaemonConfig;
+use av1d_daemon::size_gate::{check_size_gate, EarlyAbortPolicy, SizeGateResult};
 use proptest::prelude::*;
 
 /// **Feature: av1-reencoder, Property 23: Size gate enforcement**
@@ -296,3 +297,72 @@ fn test_savings_calculation() {
         }
     }
 }
+
+fn early_abort_policy() -> EarlyAbortPolicy {
+    EarlyAbortPolicy {
+        original_bytes: 10_000_000_000,
+        max_ratio: 0.9,
+        min_percent: 10.0,
+        min_encoded_secs: 300.0,
+        margin: 1.1,
+    }
+}
+
+/// **Feature: av1-reencoder, Property 26: Early size gate abort**
+/// *For any* projection, the early abort only fires once enough of the file is
+/// encoded and the projection exceeds the threshold by the configured margin
+#[test]
+fn property_early_abort_requires_progress_and_margin() {
+    proptest!(|(
+        progress in 0.0f64..100.0f64,
+        encoded_secs in 0.0f64..3600.0f64,
+        projected in 1_000_000u64..30_000_000_000u64,
+    )| {
+        let policy = early_abort_policy();
+        let result = policy.check(progress, encoded_secs, projected);
+
+        let enough = progress >= policy.min_percent && encoded_secs >= policy.min_encoded_secs;
+        let threshold = (policy.original_bytes as f64 * policy.max_ratio) as u64;
+        let clearly_over = projected as f64 >= threshold as f64 * policy.margin;
+
+        match result {
+            Some(SizeGateResult::Fail { new_bytes, threshold_bytes }) => {
+                prop_assert!(enough && clearly_over);
+                prop_assert_eq!(new_bytes, projected);
+                prop_assert_eq!(threshold_bytes, threshold);
+            }
+            Some(SizeGateResult::Pass { .. }) => prop_assert!(false, "check never passes"),
+            None => prop_assert!(!(enough && clearly_over)),
+        }
+    });
+}
+
+#[test]
+fn test_early_abort_ignores_projection_within_margin() {
+    let policy = early_abort_policy();
+
+    // Just over the 9 GB threshold, but not by 10%
+    assert!(policy.check(50.0, 1800.0, 9_500_000_000).is_none());
+    // Clearly over, but too early in the file to trust
+    assert!(policy.check(5.0, 1800.0, 12_000_000_000).is_none());
+    assert!(policy.check(50.0, 120.0, 12_000_000_000).is_none());
+    assert!(matches!(
+        policy.check(50.0, 1800.0, 12_000_000_000),
+        Some(SizeGateResult::Fail { .. })
+    ));
+}
+
+#[test]
+fn test_early_abort_policy_from_config() {
+    let config = DaemonConfig::default();
+    let policy = EarlyAbortPolicy::from_config(&config, 1_000).unwrap();
+    assert_eq!(policy.max_ratio, config.max_size_ratio);
+    assert_eq!(policy.min_percent, config.early_abort_min_percent);
+
+    let disabled = DaemonConfig {
+        early_abort: false,
+        ..Default::default()
+    };
+    assert!(EarlyAbortPolicy::from_config(&disabled, 1_000).is_none());
+    assert!(EarlyAbortPolicy::from_config(&config, 0).is_none());
+}
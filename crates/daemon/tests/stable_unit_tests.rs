@@ -0,0 +1,78 @@
+use av1d_daemon::scan::{candidate_from_path, CandidateFile};
+use av1d_daemon::stable::{find_unstable, open_for_writing};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn write_candidate(temp_dir: &TempDir, name: &str) -> CandidateFile {
+    let path = temp_dir.path().join(name);
+    fs::write(&path, vec![0u8; 1000]).unwrap();
+    candidate_from_path(&path).unwrap()
+}
+
+#[tokio::test]
+async fn test_recently_modified_files_are_not_quiet() {
+    let temp_dir = TempDir::new().unwrap();
+    let candidate = write_candidate(&temp_dir, "movie.mkv");
+
+    let unstable = find_unstable(&[&candidate], Duration::from_secs(3600), Duration::ZERO).await;
+    assert_eq!(unstable, HashSet::from([candidate.path.clone()]));
+
+    let unstable = find_unstable(&[&candidate], Duration::ZERO, Duration::ZERO).await;
+    assert!(unstable.is_empty());
+}
+
+#[tokio::test]
+async fn test_files_changing_during_the_recheck_are_unstable() {
+    let temp_dir = TempDir::new().unwrap();
+    let growing = write_candidate(&temp_dir, "growing.mkv");
+    let done = write_candidate(&temp_dir, "done.mkv");
+
+    let path = growing.path.clone();
+    let writer = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[1u8; 500]).unwrap();
+    });
+
+    let unstable = find_unstable(
+        &[&growing, &done],
+        Duration::ZERO,
+        Duration::from_millis(500),
+    )
+    .await;
+    writer.await.unwrap();
+    assert_eq!(unstable, HashSet::from([growing.path.clone()]));
+}
+
+#[tokio::test]
+async fn test_files_open_for_writing_are_unstable() {
+    let temp_dir = TempDir::new().unwrap();
+    let candidate = write_candidate(&temp_dir, "movie.mkv");
+
+    let writer = OpenOptions::new()
+        .write(true)
+        .open(&candidate.path)
+        .unwrap();
+    let unstable = find_unstable(&[&candidate], Duration::ZERO, Duration::ZERO).await;
+    assert_eq!(unstable, HashSet::from([candidate.path.clone()]));
+
+    drop(writer);
+    let unstable = find_unstable(&[&candidate], Duration::ZERO, Duration::ZERO).await;
+    assert!(unstable.is_empty());
+}
+
+#[test]
+fn test_open_for_writing_ignores_readers() {
+    let temp_dir = TempDir::new().unwrap();
+    let read_path = temp_dir.path().join("read.mkv");
+    let write_path = temp_dir.path().join("write.mkv");
+    fs::write(&read_path, "video").unwrap();
+    let _reader = File::open(&read_path).unwrap();
+    let _writer = File::create(&write_path).unwrap();
+
+    let found = open_for_writing([read_path.as_path(), write_path.as_path()]);
+    assert_eq!(found, HashSet::from([write_path.clone()]));
+}
@@ -1,5 +1,12 @@
-use av1d_daemon::config::EncoderPreference;
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::config::{DaemonConfig, EncoderPreference, InterruptedJobPolicy};
+use av1d_daemon::jobs::{create_job, load_all_jobs, save_job, Job, JobStatus};
+use av1d_daemon::probe::{FormatInfo, ProbeResult};
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::sidecars::has_skip_marker;
 use av1d_daemon::startup::*;
+use std::fs;
+use tempfile::TempDir;
 
 #[test]
 fn test_encoder_selection_integration() {
@@ -45,3 +52,185 @@ fn test_encoder_hierarchy() {
     let result = select_encoder(&svt_rav1e, EncoderPreference::Aom).unwrap();
     assert_eq!(result.encoder, AvailableEncoder::SvtAv1);
 }
+
+fn recovery_config(temp_dir: &TempDir, policy: InterruptedJobPolicy) -> DaemonConfig {
+    let config = DaemonConfig {
+        library_roots: vec![temp_dir.path().join("media")],
+        job_state_dir: temp_dir.path().join("jobs"),
+        temp_output_dir: temp_dir.path().join("temp"),
+        interrupted_job_policy: policy,
+        ..Default::default()
+    };
+    fs::create_dir_all(&config.library_roots[0]).unwrap();
+    fs::create_dir_all(&config.temp_output_dir).unwrap();
+    config
+}
+
+/// Save a job in `status` for a fresh source file, with a partial temp output
+/// when it was mid-encode.
+fn interrupted_job(config: &DaemonConfig, name: &str, status: JobStatus) -> Job {
+    let source = config.library_roots[0].join(name);
+    fs::write(&source, "source").unwrap();
+
+    let candidate = CandidateFile {
+        path: source,
+        size_bytes: 6,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let probe = ProbeResult {
+        format: FormatInfo {
+            duration: Some(60.0),
+            size: 6,
+            bitrate: None,
+        },
+        video_streams: vec![],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::Unknown,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+
+    let mut job = create_job(candidate, probe, classification);
+    job.status = status;
+    if status == JobStatus::Running {
+        let output = config.temp_output_dir.join(format!("{}.mkv", job.id));
+        fs::write(&output, "partial").unwrap();
+        job.output_path = Some(output);
+        job.progress = Some(37.5);
+    }
+    save_job(&job, &config.job_state_dir).unwrap();
+    job
+}
+
+fn reload(config: &DaemonConfig, id: &str) -> Job {
+    load_all_jobs(&config.job_state_dir)
+        .unwrap()
+        .into_iter()
+        .find(|j| j.id == id)
+        .unwrap()
+}
+
+#[test]
+fn test_recovery_requeues_running_job_and_removes_partial_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = recovery_config(&temp_dir, InterruptedJobPolicy::Requeue);
+    let running = interrupted_job(&config, "a.mkv", JobStatus::Running);
+    let done = interrupted_job(&config, "b.mkv", JobStatus::Success);
+
+    let recovered = recover_interrupted_jobs(&config).unwrap();
+    assert_eq!(recovered.len(), 1);
+
+    let job = reload(&config, &running.id);
+    assert_eq!(job.status, JobStatus::Pending);
+    assert_eq!(job.progress, None);
+    assert!(job.reason.unwrap().starts_with("Interrupted"));
+    assert!(!running.output_path.unwrap().exists());
+    assert!(!has_skip_marker(&job.source_path));
+
+    assert_eq!(reload(&config, &done.id).status, JobStatus::Success);
+}
+
+#[test]
+fn test_recovery_requeue_queued_policy_fails_only_running_jobs() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = recovery_config(&temp_dir, InterruptedJobPolicy::RequeueQueued);
+    let running = interrupted_job(&config, "a.mkv", JobStatus::Running);
+    let queued = interrupted_job(&config, "b.mkv", JobStatus::Pending);
+
+    recover_interrupted_jobs(&config).unwrap();
+
+    let failed = reload(&config, &running.id);
+    assert_eq!(failed.status, JobStatus::Failed);
+    assert!(failed.finished_at.is_some());
+    assert!(has_skip_marker(&failed.source_path));
+
+    let requeued = reload(&config, &queued.id);
+    assert_eq!(requeued.status, JobStatus::Pending);
+    assert!(!has_skip_marker(&requeued.source_path));
+}
+
+#[test]
+fn test_recovery_fail_policy_marks_all_jobs_failed() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = recovery_config(&temp_dir, InterruptedJobPolicy::Fail);
+    let running = interrupted_job(&config, "a.mkv", JobStatus::Running);
+    let queued = interrupted_job(&config, "b.mkv", JobStatus::Pending);
+
+    recover_interrupted_jobs(&config).unwrap();
+
+    for id in [&running.id, &queued.id] {
+        let job = reload(&config, id);
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.reason.unwrap().starts_with("Interrupted"));
+        assert!(has_skip_marker(&job.source_path));
+    }
+}
+
+#[test]
+fn test_recovery_leaves_jobs_with_live_encoder() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = recovery_config(&temp_dir, InterruptedJobPolicy::Requeue);
+    let running = interrupted_job(&config, "a.mkv", JobStatus::Running);
+    let output = running.output_path.clone().unwrap();
+
+    // Orphaned encoder from a previous run still writing the temp output
+    let mut orphan = std::process::Command::new("sh")
+        .arg("-c")
+        .arg("sleep 30")
+        .arg(&output)
+        .spawn()
+        .unwrap();
+    // Give the child time to exec so its command line is visible
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    let recovered = recover_interrupted_jobs(&config).unwrap();
+    orphan.kill().unwrap();
+    orphan.wait().unwrap();
+
+    assert!(recovered.is_empty());
+    assert_eq!(reload(&config, &running.id).status, JobStatus::Running);
+    assert!(output.exists());
+}
+
+#[test]
+fn test_orphaned_job_is_recovered_once_its_encoder_exits() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = recovery_config(&temp_dir, InterruptedJobPolicy::Requeue);
+    let running = interrupted_job(&config, "a.mkv", JobStatus::Running);
+    let output = running.output_path.clone().unwrap();
+
+    let mut orphan = std::process::Command::new("sh")
+        .arg("-c")
+        .arg("sleep 30")
+        .arg(&output)
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    assert!(recover_interrupted_jobs(&config).unwrap().is_empty());
+    assert!(recover_orphaned_jobs(&config, |_| false)
+        .unwrap()
+        .is_empty());
+    assert_eq!(reload(&config, &running.id).status, JobStatus::Running);
+
+    orphan.kill().unwrap();
+    orphan.wait().unwrap();
+
+    // Jobs this daemon is running itself are never touched
+    assert!(recover_orphaned_jobs(&config, |job| job.id == running.id)
+        .unwrap()
+        .is_empty());
+
+    let recovered = recover_orphaned_jobs(&config, |_| false).unwrap();
+    assert_eq!(recovered.len(), 1);
+    let job = reload(&config, &running.id);
+    assert_eq!(job.status, JobStatus::Pending);
+    assert!(job.reason.unwrap().starts_with("Interrupted"));
+    assert!(!output.exists());
+}
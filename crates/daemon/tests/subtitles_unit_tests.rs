@@ -0,0 +1,257 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::config::{DaemonConfig, SubtitleExtraction, TrackPolicy};
+use av1d_daemon::container::OutputContainer;
+use av1d_daemon::encode::common::stream_mapping_flags;
+use av1d_daemon::encode::{build_command, VideoSettings};
+use av1d_daemon::jobs::create_job;
+use av1d_daemon::probe::{FormatInfo, ProbeResult, SubtitleStream, VideoStream};
+use av1d_daemon::profiles::EncodingProfile;
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::startup::AvailableEncoder;
+use av1d_daemon::subtitles::{
+    extract_sidecar, plan_sidecars, plan_subtitles, subtitle_action, subtitle_codec_flags,
+    SubtitleAction, SubtitleTrack,
+};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// (codec, language)
+fn probe_with(subtitles: &[(&str, Option<&str>)]) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(3600.0),
+            size: 10_000_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![VideoStream {
+            index: 0,
+            codec_name: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            bitrate: None,
+            frame_rate: Some("24/1".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+            bit_depth: Some(8),
+            is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
+        }],
+        audio_streams: vec![],
+        subtitle_streams: subtitles
+            .iter()
+            .enumerate()
+            .map(|(i, (codec, language))| SubtitleStream {
+                index: i + 1,
+                codec_name: codec.to_string(),
+                language: language.map(str::to_string),
+                title: None,
+                is_default: false,
+                is_commentary: false,
+            })
+            .collect(),
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+fn convert(codec: &str) -> SubtitleAction {
+    SubtitleAction::Convert {
+        codec: codec.to_string(),
+    }
+}
+
+#[test]
+fn test_subtitle_action_by_container() {
+    use OutputContainer::*;
+
+    assert_eq!(subtitle_action("subrip", Matroska), SubtitleAction::Copy);
+    assert_eq!(
+        subtitle_action("hdmv_pgs_subtitle", Matroska),
+        SubtitleAction::Copy
+    );
+    assert_eq!(subtitle_action("mov_text", Matroska), convert("srt"));
+
+    assert_eq!(subtitle_action("mov_text", Mp4), SubtitleAction::Copy);
+    assert_eq!(subtitle_action("ass", Mp4), convert("mov_text"));
+    assert_eq!(subtitle_action("subrip", WebM), convert("webvtt"));
+
+    assert_eq!(
+        subtitle_action("dvb_teletext", Matroska),
+        SubtitleAction::Drop {
+            reason: "dvb_teletext subtitles cannot be written to .mkv".to_string()
+        }
+    );
+    assert!(matches!(
+        subtitle_action("eia_608", Matroska),
+        SubtitleAction::Drop { .. }
+    ));
+    assert!(matches!(
+        subtitle_action("hdmv_pgs_subtitle", Mp4),
+        SubtitleAction::Drop { .. }
+    ));
+}
+
+#[test]
+fn test_dropped_subtitles_are_not_mapped() {
+    let probe = probe_with(&[
+        ("subrip", Some("eng")),
+        ("dvb_teletext", Some("deu")),
+        ("mov_text", Some("fra")),
+    ]);
+    let policy = TrackPolicy::default();
+    let plan = plan_subtitles(&probe, &policy, OutputContainer::Matroska);
+    assert_eq!(plan.len(), 3);
+    assert!(plan[1].is_dropped());
+
+    let mapping = stream_mapping_flags(
+        &probe,
+        &policy,
+        &Default::default(),
+        OutputContainer::Matroska,
+    )
+    .join(" ");
+    assert!(
+        mapping.starts_with("-map 0:V -map 0:1 -map 0:3 -map 0:t?"),
+        "{}",
+        mapping
+    );
+    assert_eq!(
+        subtitle_codec_flags(&plan).join(" "),
+        "-c:s:0 copy -c:s:1 srt"
+    );
+}
+
+#[test]
+fn test_build_command_converts_for_the_output_container() {
+    let probe = probe_with(&[("mov_text", Some("eng"))]);
+    let candidate = CandidateFile {
+        path: PathBuf::from("/media/clip.mp4"),
+        size_bytes: probe.format.size,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::DiscLike,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    let job = create_job(candidate, probe.clone(), classification);
+    let config = DaemonConfig::default();
+    let video = VideoSettings::new(
+        &config,
+        AvailableEncoder::SvtAv1,
+        &EncodingProfile::default(),
+        1080,
+    );
+
+    let mp4 = build_command(&job, &probe, &video, &config, 20, "/tmp/out.mp4").join(" ");
+    assert!(mp4.contains("-c:s:0 copy"), "{}", mp4);
+    let mkv = build_command(&job, &probe, &video, &config, 20, "/tmp/out.mkv").join(" ");
+    assert!(mkv.contains("-c:s:0 srt"), "{}", mkv);
+
+    assert_eq!(
+        OutputContainer::from_path(Path::new("/tmp/out.M4V")),
+        OutputContainer::Mp4
+    );
+}
+
+#[test]
+fn test_sidecar_names() {
+    let probe = probe_with(&[
+        ("hdmv_pgs_subtitle", Some("eng")),
+        ("hdmv_pgs_subtitle", Some("eng")),
+        ("subrip", None),
+        ("dvd_subtitle", Some("fra")),
+    ]);
+    let plan = plan_subtitles(&probe, &TrackPolicy::default(), OutputContainer::Mp4);
+    let video = Path::new("/media/Movie (2001).mkv");
+
+    let sidecars = plan_sidecars(video, &plan, SubtitleExtraction::Dropped);
+    assert_eq!(
+        sidecars,
+        vec![
+            (0, PathBuf::from("/media/Movie (2001).eng.sup")),
+            (1, PathBuf::from("/media/Movie (2001).eng.2.sup")),
+        ]
+    );
+
+    // dvd_subtitle has no single-file format and is never extracted
+    let all = plan_sidecars(video, &plan, SubtitleExtraction::All);
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[2], (2, PathBuf::from("/media/Movie (2001).und.srt")));
+    assert!(plan_sidecars(video, &plan, SubtitleExtraction::None).is_empty());
+}
+
+#[tokio::test]
+async fn test_extract_sidecar() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = temp_dir.path().join("fake-ffmpeg");
+    fs::write(
+        &ffmpeg,
+        format!(
+            "#!/bin/sh\necho \"$@\" > \"{}/args\"\nfor last; do :; done\ntouch \"$last\"\n",
+            temp_dir.path().display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&ffmpeg, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let track = SubtitleTrack {
+        index: 3,
+        codec_name: "mov_text".to_string(),
+        language: Some("eng".to_string()),
+        action: SubtitleAction::Copy,
+        sidecar: None,
+    };
+    let sidecar = temp_dir.path().join("clip.eng.srt");
+    extract_sidecar(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/clip.mp4"),
+        &track,
+        &sidecar,
+    )
+    .await
+    .unwrap();
+    assert!(sidecar.exists());
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    assert!(
+        args.contains("-i /media/clip.mp4 -map 0:3 -c:s srt"),
+        "{}",
+        args
+    );
+
+    // Existing files are never overwritten
+    let err = extract_sidecar(
+        ffmpeg.to_str().unwrap(),
+        Path::new("/media/clip.mp4"),
+        &track,
+        &sidecar,
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("already exists"));
+}
+
+#[test]
+fn test_subtitle_track_serialization() {
+    let track = SubtitleTrack {
+        index: 4,
+        codec_name: "dvb_teletext".to_string(),
+        language: None,
+        action: SubtitleAction::Drop {
+            reason: "dvb_teletext subtitles cannot be written to .mkv".to_string(),
+        },
+        sidecar: None,
+    };
+    let json = serde_json::to_value(&track).unwrap();
+    assert_eq!(json["action"], "drop");
+    assert_eq!(
+        json["reason"],
+        "dvb_teletext subtitles cannot be written to .mkv"
+    );
+    let parsed: SubtitleTrack = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, track);
+}
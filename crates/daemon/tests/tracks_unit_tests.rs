@@ -0,0 +1,195 @@
+use av1d_daemon::config::TrackPolicy;
+use av1d_daemon::probe::{AudioStream, FormatInfo, ProbeResult, SubtitleStream};
+use av1d_daemon::tracks::{select_tracks, TrackKind};
+
+/// (language, is_default, is_commentary)
+type Track<'a> = (Option<&'a str>, bool, bool);
+
+fn probe_with(audio: &[Track], subtitles: &[Track]) -> ProbeResult {
+    ProbeResult {
+        format: FormatInfo {
+            duration: Some(60.0),
+            size: 1_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![],
+        audio_streams: audio
+            .iter()
+            .enumerate()
+            .map(|(i, (language, is_default, is_commentary))| AudioStream {
+                index: i + 1,
+                codec_name: "aac".to_string(),
+                language: language.map(str::to_string),
+                title: None,
+                is_default: *is_default,
+                is_commentary: *is_commentary,
+                channels: None,
+                channel_layout: None,
+                bitrate: None,
+            })
+            .collect(),
+        subtitle_streams: subtitles
+            .iter()
+            .enumerate()
+            .map(
+                |(i, (language, is_default, is_commentary))| SubtitleStream {
+                    index: audio.len() + i + 1,
+                    codec_name: "subrip".to_string(),
+                    language: language.map(str::to_string),
+                    title: None,
+                    is_default: *is_default,
+                    is_commentary: *is_commentary,
+                },
+            )
+            .collect(),
+        chapters: 0,
+        attachments: vec![],
+    }
+}
+
+fn drop_russian() -> TrackPolicy {
+    TrackPolicy {
+        audio_drop_languages: vec!["ru".to_string(), "rus".to_string()],
+        subtitle_drop_languages: vec!["ru".to_string(), "rus".to_string()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_default_policy_keeps_everything() {
+    let probe = probe_with(
+        &[(Some("eng"), true, false), (Some("rus"), false, false)],
+        &[(None, false, false), (Some("eng"), false, true)],
+    );
+
+    let selection = select_tracks(&probe, &TrackPolicy::default());
+    assert_eq!(selection.audio, vec![1, 2]);
+    assert_eq!(selection.subtitles, vec![3, 4]);
+    assert!(selection.dropped.is_empty());
+}
+
+#[test]
+fn test_drop_list_removes_matching_tracks() {
+    let probe = probe_with(
+        &[(Some("eng"), true, false), (Some("RUS"), false, false)],
+        &[(Some("eng"), false, false), (Some("ru"), false, false)],
+    );
+
+    let selection = select_tracks(&probe, &drop_russian());
+    assert_eq!(selection.audio, vec![1]);
+    assert_eq!(selection.subtitles, vec![3]);
+    assert_eq!(selection.dropped.len(), 2);
+    assert_eq!(selection.dropped[0].kind, TrackKind::Audio);
+    assert_eq!(
+        selection.dropped[0].reason,
+        "language rus is in the drop list"
+    );
+    assert_eq!(selection.dropped[1].kind, TrackKind::Subtitle);
+}
+
+#[test]
+fn test_keep_list_drops_unlisted_languages() {
+    let probe = probe_with(
+        &[
+            (Some("eng"), true, false),
+            (Some("fre"), false, false),
+            (Some("ger"), false, false),
+        ],
+        &[(Some("fre"), false, false), (Some("spa"), false, false)],
+    );
+    let policy = TrackPolicy {
+        audio_keep_languages: vec!["eng".to_string(), "ger".to_string()],
+        subtitle_keep_languages: vec!["spa".to_string()],
+        ..Default::default()
+    };
+
+    let selection = select_tracks(&probe, &policy);
+    assert_eq!(selection.audio, vec![1, 3]);
+    assert_eq!(selection.subtitles, vec![5]);
+    assert_eq!(
+        selection.dropped[0].reason,
+        "language fre is not in the keep list"
+    );
+}
+
+#[test]
+fn test_default_track_is_kept_even_if_listed() {
+    let probe = probe_with(
+        &[(Some("eng"), false, false), (Some("rus"), true, false)],
+        &[(Some("rus"), true, false)],
+    );
+
+    let selection = select_tracks(&probe, &drop_russian());
+    assert_eq!(selection.audio, vec![1, 2]);
+    assert_eq!(selection.subtitles, vec![3]);
+}
+
+#[test]
+fn test_original_language_is_kept_even_if_listed() {
+    // A Russian film: the first audio track sets the original language
+    let probe = probe_with(
+        &[(Some("rus"), false, false), (Some("eng"), true, false)],
+        &[(Some("rus"), false, false), (Some("eng"), false, false)],
+    );
+
+    let selection = select_tracks(&probe, &drop_russian());
+    assert_eq!(selection.audio, vec![1, 2]);
+    assert_eq!(selection.subtitles, vec![3, 4]);
+    assert!(selection.dropped.is_empty());
+}
+
+#[test]
+fn test_commentary_and_undetermined_drops_are_optional() {
+    let probe = probe_with(
+        &[
+            (Some("eng"), true, false),
+            (Some("eng"), false, true),
+            (Some("und"), false, false),
+        ],
+        &[(None, false, false)],
+    );
+
+    let selection = select_tracks(&probe, &TrackPolicy::default());
+    assert_eq!(selection.audio, vec![1, 2, 3]);
+    assert_eq!(selection.subtitles, vec![4]);
+
+    let policy = TrackPolicy {
+        drop_commentary: true,
+        drop_undetermined: true,
+        ..Default::default()
+    };
+    let selection = select_tracks(&probe, &policy);
+    assert_eq!(selection.audio, vec![1]);
+    assert!(selection.subtitles.is_empty());
+    let reasons: Vec<&str> = selection
+        .dropped
+        .iter()
+        .map(|d| d.reason.as_str())
+        .collect();
+    assert_eq!(
+        reasons,
+        vec![
+            "commentary",
+            "undetermined language",
+            "undetermined language"
+        ]
+    );
+}
+
+#[test]
+fn test_last_audio_track_is_never_dropped() {
+    let probe = probe_with(
+        &[(Some("und"), false, false), (Some("fre"), false, false)],
+        &[],
+    );
+    let policy = TrackPolicy {
+        audio_keep_languages: vec!["eng".to_string()],
+        drop_undetermined: true,
+        ..Default::default()
+    };
+
+    let selection = select_tracks(&probe, &policy);
+    assert_eq!(selection.audio, vec![1]);
+    assert_eq!(selection.dropped.len(), 1);
+    assert_eq!(selection.dropped[0].index, 2);
+}
@@ -45,6 +45,9 @@ fn property_output_validation() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: i == 0,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             });
         }
 
@@ -60,6 +63,9 @@ fn property_output_validation() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: false,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             });
         }
 
@@ -72,6 +78,8 @@ fn property_output_validation() {
             video_streams: output_video_streams,
             audio_streams: vec![],
             subtitle_streams: vec![],
+            chapters: 0,
+            attachments: vec![],
         };
 
         // Determine expected validation result
@@ -111,9 +119,14 @@ fn create_probe_result(duration: Option<f64>, width: i32, height: i32, codec: &s
             pix_fmt: Some("yuv420p".to_string()),
             bit_depth: Some(8),
             is_default: true,
+            color: Default::default(),
+            field_order: None,
+            frame_count: None,
         }],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     }
 }
 
@@ -250,6 +263,9 @@ fn test_invalid_output_multiple_av1_streams() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: true,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             },
             VideoStream {
                 index: 1,
@@ -261,10 +277,15 @@ fn test_invalid_output_multiple_av1_streams() {
                 pix_fmt: Some("yuv420p".to_string()),
                 bit_depth: Some(8),
                 is_default: false,
+                color: Default::default(),
+                field_order: None,
+                frame_count: None,
             },
         ],
         audio_streams: vec![],
         subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
     };
 
     let result = determine_expected_result(&original_probe, &output_probe, 2, 0.0);
@@ -0,0 +1,260 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::config::{AudioCodec, AudioPolicy, DaemonConfig, DeinterlaceConfig};
+use av1d_daemon::interlace::{IdetCounts, InterlaceDecision};
+use av1d_daemon::jobs::{create_job, Job};
+use av1d_daemon::probe::{parse_probe_json, Attachment, ProbeResult};
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::validate::{
+    check_frame_count, check_stream_parity, ExpectedStreams, ExpectedTrack, ValidationError,
+};
+use std::path::{Path, PathBuf};
+
+const SOURCE_JSON: &str = r#"{
+    "format": { "duration": "1440.0", "size": "4000000000" },
+    "streams": [
+        { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080,
+          "disposition": { "default": 1 }, "tags": { "NUMBER_OF_FRAMES-eng": "34526" } },
+        { "index": 1, "codec_type": "audio", "codec_name": "truehd", "channels": 8,
+          "tags": { "language": "jpn" } },
+        { "index": 2, "codec_type": "audio", "codec_name": "aac", "channels": 2 },
+        { "index": 3, "codec_type": "subtitle", "codec_name": "ass", "tags": { "language": "eng" } },
+        { "index": 4, "codec_type": "subtitle", "codec_name": "mov_text", "tags": { "language": "eng" } },
+        { "index": 5, "codec_type": "subtitle", "codec_name": "dvb_teletext", "tags": { "language": "deu" } },
+        { "index": 6, "codec_type": "attachment", "codec_name": "ttf",
+          "tags": { "filename": "Roboto-Bold.ttf", "mimetype": "application/x-truetype-font" } },
+        { "index": 7, "codec_type": "attachment",
+          "tags": { "filename": "cover.jpg", "mimetype": "image/jpeg" } }
+    ],
+    "chapters": [
+        { "id": 0, "start_time": "0.000000", "end_time": "90.000000" },
+        { "id": 1, "start_time": "90.000000", "end_time": "1440.000000" }
+    ]
+}"#;
+
+fn source() -> ProbeResult {
+    parse_probe_json(SOURCE_JSON).unwrap()
+}
+
+fn job(probe: &ProbeResult) -> Job {
+    let candidate = CandidateFile {
+        path: PathBuf::from("/media/Show S01E01.mkv"),
+        size_bytes: probe.format.size,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::DiscLike,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    create_job(candidate, probe.clone(), classification)
+}
+
+fn track(codec_name: &str, language: Option<&str>) -> ExpectedTrack {
+    ExpectedTrack {
+        codec_name: codec_name.to_string(),
+        language: language.map(str::to_string),
+    }
+}
+
+/// An output that carries exactly `expected`
+fn output_for(expected: &ExpectedStreams) -> ProbeResult {
+    let mut streams = vec![r#"{ "index": 0, "codec_type": "video", "codec_name": "av1",
+        "width": 1920, "height": 1080 }"#
+        .to_string()];
+    let mut stream = |codec_type: &str, track: &ExpectedTrack| {
+        let tags = match &track.language {
+            Some(language) => format!(r#", "tags": {{ "language": "{}" }}"#, language),
+            None => String::new(),
+        };
+        streams.push(format!(
+            r#"{{ "index": {}, "codec_type": "{}", "codec_name": "{}"{} }}"#,
+            streams.len(),
+            codec_type,
+            track.codec_name,
+            tags
+        ));
+    };
+    expected.audio.iter().for_each(|t| stream("audio", t));
+    expected
+        .subtitles
+        .iter()
+        .for_each(|t| stream("subtitle", t));
+    let mut probe =
+        parse_probe_json(&format!(r#"{{ "streams": [{}] }}"#, streams.join(","))).unwrap();
+    probe.chapters = expected.chapters;
+    probe.attachments = expected
+        .fonts
+        .iter()
+        .enumerate()
+        .map(|(i, font)| Attachment {
+            index: 100 + i,
+            filename: Some(font.clone()),
+            mimetype: Some("font/ttf".to_string()),
+        })
+        .collect();
+    probe
+}
+
+#[test]
+fn test_probe_reads_chapters_attachments_and_frame_counts() {
+    let probe = source();
+    assert_eq!(probe.chapters, 2);
+    assert_eq!(probe.video_streams[0].frame_count, Some(34526));
+
+    // Attachments without a codec name still parse
+    assert_eq!(probe.attachments.len(), 2);
+    assert!(probe.attachments[0].is_font());
+    assert!(!probe.attachments[1].is_font());
+
+    let mp4 = parse_probe_json(
+        r#"{ "streams": [{ "index": 0, "codec_type": "video", "codec_name": "hevc",
+             "width": 3840, "height": 2160, "nb_frames": "1000" }] }"#,
+    )
+    .unwrap();
+    assert_eq!(mp4.video_streams[0].frame_count, Some(1000));
+    assert_eq!(mp4.chapters, 0);
+}
+
+#[test]
+fn test_expected_streams_follow_the_track_plans() {
+    let probe = source();
+    let config = DaemonConfig {
+        audio_policy: AudioPolicy {
+            enabled: true,
+            codec: AudioCodec::Opus,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let expected =
+        ExpectedStreams::for_job(&job(&probe), &probe, &config, Path::new("/tmp/job.mkv"));
+    assert_eq!(
+        expected.audio,
+        vec![track("opus", Some("jpn")), track("aac", None)]
+    );
+    // mov_text is converted to SubRip and teletext is dropped
+    assert_eq!(
+        expected.subtitles,
+        vec![track("ass", Some("eng")), track("subrip", Some("eng"))]
+    );
+    assert_eq!(expected.chapters, 2);
+    assert_eq!(expected.fonts, vec!["Roboto-Bold.ttf".to_string()]);
+    assert_eq!(expected.frames, Some(34526));
+
+    // MP4 cannot carry attachments
+    let mp4 = ExpectedStreams::for_job(&job(&probe), &probe, &config, Path::new("/tmp/job.mp4"));
+    assert!(mp4.fonts.is_empty());
+    assert_eq!(
+        mp4.subtitles,
+        vec![
+            track("mov_text", Some("eng")),
+            track("mov_text", Some("eng"))
+        ]
+    );
+}
+
+#[test]
+fn test_inverse_telecine_expects_fewer_frames() {
+    let probe = source();
+    let mut job = job(&probe);
+    job.interlace = Some(InterlaceDecision::new(
+        None,
+        Some(IdetCounts {
+            tff: 60,
+            bff: 0,
+            progressive: 40,
+            undetermined: 0,
+            repeated: 20,
+            frames: 100,
+        }),
+        &DeinterlaceConfig::default(),
+    ));
+
+    let expected = ExpectedStreams::for_job(
+        &job,
+        &probe,
+        &DaemonConfig::default(),
+        Path::new("/tmp/job.mkv"),
+    );
+    assert_eq!(expected.frames, Some(27620));
+}
+
+#[test]
+fn test_stream_parity() {
+    let probe = source();
+    let expected = ExpectedStreams::for_job(
+        &job(&probe),
+        &probe,
+        &DaemonConfig::default(),
+        Path::new("/tmp/job.mkv"),
+    );
+    let output = output_for(&expected);
+    assert_eq!(check_stream_parity(&expected, &output), None);
+
+    // A mapping that lost the audio
+    let mut no_audio = output.clone();
+    no_audio.audio_streams.clear();
+    assert_eq!(
+        check_stream_parity(&expected, &no_audio),
+        Some(ValidationError::AudioTrackMismatch {
+            expected: vec!["truehd (jpn)".to_string(), "aac (und)".to_string()],
+            actual: vec![],
+        })
+    );
+
+    // Languages must survive, except where the source had none
+    let mut relabelled = output.clone();
+    relabelled.audio_streams[0].language = Some("eng".to_string());
+    assert!(matches!(
+        check_stream_parity(&expected, &relabelled),
+        Some(ValidationError::AudioTrackMismatch { .. })
+    ));
+    let mut tagged = output.clone();
+    tagged.audio_streams[1].language = Some("jpn".to_string());
+    assert_eq!(check_stream_parity(&expected, &tagged), None);
+
+    let mut wrong_codec = output.clone();
+    wrong_codec.subtitle_streams[1].codec_name = "ass".to_string();
+    assert!(matches!(
+        check_stream_parity(&expected, &wrong_codec),
+        Some(ValidationError::SubtitleTrackMismatch { .. })
+    ));
+
+    let mut no_chapters = output.clone();
+    no_chapters.chapters = 0;
+    assert_eq!(
+        check_stream_parity(&expected, &no_chapters),
+        Some(ValidationError::ChapterCountMismatch {
+            expected: 2,
+            actual: 0,
+        })
+    );
+
+    let mut no_fonts = output;
+    no_fonts.attachments.clear();
+    assert_eq!(
+        check_stream_parity(&expected, &no_fonts),
+        Some(ValidationError::MissingFontAttachments {
+            missing: vec!["Roboto-Bold.ttf".to_string()],
+        })
+    );
+}
+
+#[test]
+fn test_frame_count_tolerance() {
+    assert_eq!(check_frame_count(34526, 34526), None);
+    // 1% of the expected count
+    assert_eq!(check_frame_count(34526, 34200), None);
+    assert_eq!(
+        check_frame_count(34526, 34000),
+        Some(ValidationError::FrameCountMismatch {
+            expected: 34526,
+            actual: 34000,
+        })
+    );
+    // Short files still get two frames of slack
+    assert_eq!(check_frame_count(100, 98), None);
+    assert!(check_frame_count(100, 97).is_some());
+}
@@ -0,0 +1,150 @@
+use av1d_daemon::classify::{SourceClassification, SourceType};
+use av1d_daemon::jobs::{create_job, Job, JobStage};
+use av1d_daemon::probe::{FormatInfo, ProbeResult};
+use av1d_daemon::scan::CandidateFile;
+use av1d_daemon::validate::ValidationError;
+use av1d_daemon::verify::{record_verify_progress, verify_decode};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn job() -> Job {
+    let probe = ProbeResult {
+        format: FormatInfo {
+            duration: Some(100.0),
+            size: 1_000_000,
+            bitrate: None,
+        },
+        video_streams: vec![],
+        audio_streams: vec![],
+        subtitle_streams: vec![],
+        chapters: 0,
+        attachments: vec![],
+    };
+    let candidate = CandidateFile {
+        path: PathBuf::from("/media/movie.mkv"),
+        size_bytes: probe.format.size,
+        modified_time: std::time::SystemTime::now(),
+    };
+    let classification = SourceClassification {
+        source_type: SourceType::DiscLike,
+        web_score: 0,
+        disc_score: 0,
+        reasons: vec![],
+    };
+    create_job(candidate, probe, classification)
+}
+
+/// Stand-in for ffmpeg that logs its arguments, reports decoding half and then
+/// all of the file, prints `errors` decoder errors and exits with `exit_code`
+fn write_fake_ffmpeg(dir: &Path, errors: usize, exit_code: i32) -> PathBuf {
+    let script = dir.join("fake-ffmpeg");
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\necho \"$@\" > \"{dir}/args\"\n\
+             echo out_time_us=50000000\necho progress=continue\n\
+             i=0\nwhile [ $i -lt {errors} ]; do\n\
+             echo \"[av1 @ 0x1] Failed to decode tile $i\" >&2\n  i=$((i + 1))\ndone\n\
+             echo out_time_us=100000000\necho progress=end\nexit {exit_code}\n",
+            dir = dir.display(),
+            errors = errors,
+            exit_code = exit_code
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+fn output(dir: &Path) -> PathBuf {
+    let path = dir.join("job.mkv");
+    fs::write(&path, vec![0u8; 4096]).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn test_clean_decode_passes() {
+    let temp_dir = TempDir::new().unwrap();
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), 0, 0);
+    let output = output(temp_dir.path());
+    let mut job = job();
+
+    let result = verify_decode(ffmpeg.to_str().unwrap(), &output, &mut job, temp_dir.path())
+        .await
+        .unwrap();
+    assert_eq!(result, None);
+    assert_eq!(job.stage, Some(JobStage::Verifying));
+    assert_eq!(job.progress, Some(100.0));
+
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    assert!(args.contains("-v error"), "{}", args);
+    assert!(
+        args.contains(&format!(
+            "-i {} -map 0:v:0 -map 0:a? -f null -",
+            output.display()
+        )),
+        "{}",
+        args
+    );
+}
+
+#[tokio::test]
+async fn test_decoder_errors_fail_verification() {
+    let temp_dir = TempDir::new().unwrap();
+    // ffmpeg exits cleanly after concealing the broken frames
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), 7, 0);
+    let output = output(temp_dir.path());
+
+    let result = verify_decode(
+        ffmpeg.to_str().unwrap(),
+        &output,
+        &mut job(),
+        temp_dir.path(),
+    )
+    .await
+    .unwrap();
+    let Some(ValidationError::DecodeErrors { count, messages }) = result else {
+        panic!("expected decode errors, got {:?}", result);
+    };
+    assert_eq!(count, 7);
+    assert_eq!(messages.len(), 5);
+    assert_eq!(messages[0], "[av1 @ 0x1] Failed to decode tile 0");
+
+    // A failing exit without any message is an error too
+    let ffmpeg = write_fake_ffmpeg(temp_dir.path(), 0, 1);
+    let result = verify_decode(
+        ffmpeg.to_str().unwrap(),
+        &output,
+        &mut job(),
+        temp_dir.path(),
+    )
+    .await
+    .unwrap();
+    assert!(
+        matches!(result, Some(ValidationError::DecodeErrors { count: 1, .. })),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_verify_progress_fields() {
+    let mut job = job();
+    record_verify_progress(&mut job, 25.0, 4_000_000, Duration::from_secs(10));
+
+    assert_eq!(job.progress, Some(25.0));
+    // A quarter of the file in ten seconds
+    assert_eq!(job.speed_bps, Some(100_000.0));
+    let eta = (job.eta.unwrap() - chrono::Utc::now()).num_seconds();
+    assert!((29..=30).contains(&eta), "{}", eta);
+
+    // Without a known duration nothing is reported
+    let mut unknown = job.clone();
+    unknown.original_duration = None;
+    unknown.progress = None;
+    record_verify_progress(&mut unknown, 25.0, 4_000_000, Duration::from_secs(10));
+    assert_eq!(unknown.progress, None);
+}
@@ -0,0 +1,112 @@
+use av1d_daemon::watch::{Debouncer, LibraryWatcher, WatchEvent};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+async fn next_event(watcher: &mut LibraryWatcher) -> Option<WatchEvent> {
+    tokio::time::timeout(Duration::from_secs(5), watcher.next_event())
+        .await
+        .ok()
+        .flatten()
+}
+
+#[test]
+fn test_debouncer_waits_for_quiet_period() {
+    let start = Instant::now();
+    let window = Duration::from_secs(30);
+    let mut debouncer = Debouncer::new(window);
+    assert_eq!(debouncer.next_deadline(), None);
+
+    debouncer.touch(PathBuf::from("/media/a.mkv"), start);
+    debouncer.touch(
+        PathBuf::from("/media/b.mkv"),
+        start + Duration::from_secs(10),
+    );
+    assert_eq!(debouncer.next_deadline(), Some(start + window));
+
+    // A new event restarts the quiet period
+    debouncer.touch(
+        PathBuf::from("/media/a.mkv"),
+        start + Duration::from_secs(20),
+    );
+    assert!(debouncer.take_ready(start + window).is_empty());
+    assert_eq!(
+        debouncer.next_deadline(),
+        Some(start + Duration::from_secs(40))
+    );
+
+    assert_eq!(
+        debouncer.take_ready(start + Duration::from_secs(45)),
+        vec![PathBuf::from("/media/b.mkv")]
+    );
+    assert_eq!(
+        debouncer.take_ready(start + Duration::from_secs(50)),
+        vec![PathBuf::from("/media/a.mkv")]
+    );
+    assert!(debouncer.is_empty());
+}
+
+#[tokio::test]
+async fn test_watcher_reports_written_and_moved_video_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("library");
+    fs::create_dir_all(&root).unwrap();
+    let mut watcher = LibraryWatcher::start(std::slice::from_ref(&root)).unwrap();
+
+    // Non-video files are ignored
+    fs::write(root.join("notes.txt"), "text").unwrap();
+    fs::write(root.join("movie.mkv"), "video").unwrap();
+    assert_eq!(
+        next_event(&mut watcher).await,
+        Some(WatchEvent::File(root.join("movie.mkv")))
+    );
+
+    let download = temp_dir.path().join("show.mp4.part");
+    fs::write(&download, "video").unwrap();
+    fs::rename(&download, root.join("show.mp4")).unwrap();
+    assert_eq!(
+        next_event(&mut watcher).await,
+        Some(WatchEvent::File(root.join("show.mp4")))
+    );
+}
+
+#[tokio::test]
+async fn test_watcher_follows_new_directories() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("library");
+    fs::create_dir_all(&root).unwrap();
+    let mut watcher = LibraryWatcher::start(std::slice::from_ref(&root)).unwrap();
+
+    // A directory moved in with files already inside
+    let staged = temp_dir.path().join("staged");
+    fs::create_dir_all(&staged).unwrap();
+    fs::write(staged.join("episode.mkv"), "video").unwrap();
+    fs::rename(&staged, root.join("Season 1")).unwrap();
+    assert_eq!(
+        next_event(&mut watcher).await,
+        Some(WatchEvent::File(root.join("Season 1/episode.mkv")))
+    );
+
+    // The new directory is watched too
+    fs::write(root.join("Season 1/episode2.mkv"), "video").unwrap();
+    assert_eq!(
+        next_event(&mut watcher).await,
+        Some(WatchEvent::File(root.join("Season 1/episode2.mkv")))
+    );
+
+    // Hidden directories are not
+    fs::create_dir_all(root.join(".trash")).unwrap();
+    fs::write(root.join(".trash/old.mkv"), "video").unwrap();
+    fs::write(root.join("last.mkv"), "video").unwrap();
+    assert_eq!(
+        next_event(&mut watcher).await,
+        Some(WatchEvent::File(root.join("last.mkv")))
+    );
+}
+
+#[test]
+fn test_watcher_needs_a_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    assert!(LibraryWatcher::start(&[temp_dir.path().join("missing")]).is_err());
+}